@@ -0,0 +1,103 @@
+use core::fmt::Write;
+
+use r_efi::efi;
+
+use crate::{config::BootConfig, uefi::{BootSystemTable, SimpleTextOutputProtocol, SCAN_DOWN, SCAN_UP, TEXT_BLACK, TEXT_LIGHT_GRAY, TEXT_WHITE}};
+
+/// How often the keyboard is checked while the menu is open
+const POLL_INTERVAL_MICROSECONDS: usize = 100_000;
+const POLLS_PER_SECOND: usize = 1_000_000 / POLL_INTERVAL_MICROSECONDS;
+
+const NORMAL_ATTRIBUTE: usize = TEXT_LIGHT_GRAY | TEXT_BLACK << 4;
+const SELECTED_ATTRIBUTE: usize = TEXT_BLACK | TEXT_LIGHT_GRAY << 4;
+const TITLE_ATTRIBUTE: usize = TEXT_WHITE | TEXT_BLACK << 4;
+
+/// The row of the first entry, below the title
+const FIRST_ENTRY_ROW: usize = 2;
+
+/// Show the boot menu on the firmware console and return the index of the chosen entry
+///
+/// The default entry is booted when the timeout runs out. Pressing any key stops the countdown so an
+/// entry can be picked with the arrow keys and Enter. The menu is skipped when there is nothing to choose
+/// between or the timeout is 0.
+pub fn run(system_table: &BootSystemTable, config: &BootConfig) -> Result<usize, efi::Status> {
+    let entries = config.entries();
+    let default_entry = config.default_entry_index().unwrap_or(0);
+    if entries.len() < 2 || config.timeout == 0 { return Ok(default_entry); }
+
+    let mut con_out = system_table.con_out();
+    let con_in = system_table.con_in();
+    con_in.reset()?;
+
+    con_out.set_attribute(NORMAL_ATTRIBUTE)?;
+    con_out.clear_screen()?;
+    // Not every console can hide the cursor so failing here is fine
+    let _ = con_out.enable_cursor(false);
+
+    con_out.set_attribute(TITLE_ATTRIBUTE)?;
+    con_out.output_string(" Boot Menu")?;
+
+    let mut selected = default_entry;
+    for index in 0..entries.len() {
+        draw_entry(&con_out, config, index, index == selected)?;
+    }
+
+    let help_row = FIRST_ENTRY_ROW + entries.len() + 1;
+    con_out.set_attribute(NORMAL_ATTRIBUTE)?;
+    con_out.set_cursor_position(0, help_row)?;
+    con_out.output_string(" Use the arrow keys to choose an entry and Enter to boot it")?;
+
+    let mut polls_left = Some(config.timeout * POLLS_PER_SECOND);
+    loop {
+        if let Some(polls) = polls_left {
+            if polls == 0 { break; }
+            if polls % POLLS_PER_SECOND == 0 {
+                con_out.set_cursor_position(0, help_row + 1)?;
+                write!(con_out, " Booting the selected entry in {}s ", polls / POLLS_PER_SECOND)
+                    .map_err(|_| efi::Status::DEVICE_ERROR)?;
+            }
+            polls_left = Some(polls - 1);
+        }
+
+        let Some(key) = con_in.read_key()? else {
+            system_table.boot_services.stall(POLL_INTERVAL_MICROSECONDS)?;
+            continue;
+        };
+
+        // Any key press means someone is choosing an entry, so stop counting down
+        if polls_left.take().is_some() {
+            con_out.set_cursor_position(0, help_row + 1)?;
+            con_out.output_string("                                     ")?;
+        }
+
+        let previous = selected;
+        match (key.scan_code, key.character) {
+            (SCAN_UP, _) => selected = selected.checked_sub(1).unwrap_or(entries.len() - 1),
+            (SCAN_DOWN, _) => selected = (selected + 1) % entries.len(),
+            (_, Some('\r' | '\n')) => break,
+            _ => {},
+        }
+
+        if selected != previous {
+            draw_entry(&con_out, config, previous, false)?;
+            draw_entry(&con_out, config, selected, true)?;
+        }
+    }
+
+    con_out.set_attribute(NORMAL_ATTRIBUTE)?;
+    con_out.clear_screen()?;
+    Ok(selected)
+}
+
+fn draw_entry(con_out: &SimpleTextOutputProtocol, config: &BootConfig, index: usize, selected: bool) -> Result<(), efi::Status> {
+    let entry = &config.entries()[index];
+    let marker = if selected { ">" } else { " " };
+
+    con_out.set_cursor_position(2, FIRST_ENTRY_ROW + index)?;
+    con_out.set_attribute(if selected { SELECTED_ATTRIBUTE } else { NORMAL_ATTRIBUTE })?;
+    con_out.output_string(marker)?;
+    con_out.output_string(" ")?;
+    con_out.output_string(entry.title)?;
+    con_out.output_string(" ")?;
+    con_out.set_attribute(NORMAL_ATTRIBUTE)
+}
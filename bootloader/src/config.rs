@@ -0,0 +1,267 @@
+use r_efi::efi;
+use x86_64_hardware::com1_println;
+
+use crate::uefi::BootServices;
+
+/// Where the config file is on the boot disk
+pub const CONFIG_FILE_PATH: &str = "boot.cfg";
+
+/// The most modules that can be listed, including the font, logo and initrd
+const MAX_MODULES: usize = bootinfo::MAX_BOOT_MODULES;
+
+/// The most entries the boot menu can list
+const MAX_MENU_ENTRIES: usize = 8;
+
+/// Boot module names the kernel looks for
+pub const FONT_MODULE_NAME: &str = "font";
+pub const LOGO_MODULE_NAME: &str = "logo";
+pub const INITRD_MODULE_NAME: &str = "initrd";
+
+/// A file to pass to the kernel and the name the kernel knows it by
+#[derive(Debug, Clone, Copy)]
+pub struct ModuleEntry {
+    pub name: &'static str,
+    pub path: &'static str,
+}
+
+/// A choice in the boot menu of which kernel to boot and the options to give it
+#[derive(Debug, Clone, Copy)]
+pub struct MenuEntry {
+    pub title: &'static str,
+    pub kernel_path: &'static str,
+    pub command_line: &'static str,
+}
+
+/// Settings read from boot.cfg
+///
+/// The file holds one `key = value` per line. Blank lines and lines starting with # are ignored.
+/// Every key is optional and falls back to the defaults below.
+///
+/// An `entry = Title` line adds a boot menu entry. The `kernel` and `cmdline` keys after it apply to
+/// that entry, starting from the values set before the first entry.
+pub struct BootConfig {
+    /// `kernel`: path of the kernel ELF file
+    pub kernel_path: &'static str,
+    /// `resolution`: preferred video mode as WIDTHxHEIGHT, or `auto` for the highest resolution
+    pub resolution: Option<(u32, u32)>,
+    /// `verbose`: print the memory map, video modes and mappings to COM1
+    pub verbose: bool,
+    /// `kaslr`: randomize where the kernel, physical memory and boot data are mapped
+    pub kaslr: bool,
+    /// `cmdline`: options passed to the kernel, unless the firmware was given load options
+    pub command_line: &'static str,
+    /// `timeout`: seconds the boot menu waits before booting the default entry. 0 boots it straight away
+    pub timeout: usize,
+    /// `default`: the title or number, counting from 1, of the entry booted when the menu times out
+    default_entry: &'static str,
+    entries: [MenuEntry; MAX_MENU_ENTRIES],
+    entry_count: usize,
+    /// `font`, `logo`, `initrd` and `module`: files passed to the kernel. An empty font, logo or initrd path leaves it out
+    modules: [ModuleEntry; MAX_MODULES],
+    module_count: usize,
+}
+
+/// Why a line of the config file could not be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigError {
+    MissingEquals,
+    UnknownKey,
+    InvalidValue,
+    TooManyModules,
+    TooManyEntries,
+}
+
+impl BootConfig {
+    /// Read boot.cfg from the boot disk, using the defaults if it is missing or unreadable
+    pub fn load(image_handle: efi::Handle, boot_services: &BootServices) -> BootConfig {
+        let mut config = BootConfig::default();
+
+        let text = match read_config_file(image_handle, boot_services) {
+            Ok(text) => text,
+            Err(efi::Status::NOT_FOUND) => {
+                com1_println!("No {CONFIG_FILE_PATH} found, using the default configuration");
+                return config;
+            },
+            Err(status) => {
+                com1_println!("Could not read {CONFIG_FILE_PATH}, using the default configuration. Status: {status:?}");
+                return config;
+            }
+        };
+
+        config.parse(text);
+        if config.default_entry_index().is_none() {
+            com1_println!("{CONFIG_FILE_PATH}: default entry '{}' not found", config.default_entry);
+        }
+        config
+    }
+
+    /// Apply every valid line of a config file, reporting invalid lines to COM1
+    fn parse(&mut self, text: &'static str) {
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+
+            let result = match line.split_once('=') {
+                Some((key, value)) => self.apply(key.trim(), value.trim()),
+                None => Err(ConfigError::MissingEquals),
+            };
+
+            if let Err(error) = result {
+                com1_println!("{CONFIG_FILE_PATH} line {}: {} in '{line}'", index + 1, error.description());
+            }
+        }
+    }
+
+    fn apply(&mut self, key: &str, value: &'static str) -> Result<(), ConfigError> {
+        match key {
+            "kernel" if !value.is_empty() => match self.current_entry() {
+                Some(entry) => entry.kernel_path = value,
+                None => self.kernel_path = value,
+            },
+            "resolution" => self.resolution = parse_resolution(value)?,
+            "verbose" => self.verbose = parse_bool(value)?,
+            "kaslr" => self.kaslr = parse_bool(value)?,
+            "cmdline" if value.len() <= bootinfo::MAX_COMMAND_LINE_LENGTH => match self.current_entry() {
+                Some(entry) => entry.command_line = value,
+                None => self.command_line = value,
+            },
+            "entry" if !value.is_empty() => self.add_entry(value)?,
+            "default" => self.default_entry = value,
+            "timeout" => self.timeout = value.parse().map_err(|_| ConfigError::InvalidValue)?,
+            "font" => self.set_module_path(FONT_MODULE_NAME, value),
+            "logo" => self.set_module_path(LOGO_MODULE_NAME, value),
+            "initrd" => self.set_module_path(INITRD_MODULE_NAME, value),
+            // Extra modules are named by their path so it must fit in a boot module name
+            "module" if !value.is_empty() && value.len() <= bootinfo::MAX_MODULE_NAME_LENGTH => {
+                self.add_module(ModuleEntry { name: value, path: value })?
+            },
+            "kernel" | "module" | "cmdline" | "entry" => return Err(ConfigError::InvalidValue),
+            _ => return Err(ConfigError::UnknownKey),
+        }
+
+        Ok(())
+    }
+
+    /// The boot menu entries in the order they are listed
+    pub fn entries(&self) -> &[MenuEntry] {
+        &self.entries[..self.entry_count]
+    }
+
+    /// The entry to boot if nothing else is chosen, or None if the default doesn't match an entry
+    pub fn default_entry_index(&self) -> Option<usize> {
+        if self.default_entry.is_empty() { return Some(0); }
+
+        if let Some(index) = self.entries().iter().position(|entry| entry.title == self.default_entry) {
+            return Some(index);
+        }
+        match self.default_entry.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= self.entry_count => Some(number - 1),
+            _ => None,
+        }
+    }
+
+    /// Boot the kernel with the command line from a menu entry
+    pub fn select_entry(&mut self, index: usize) {
+        let Some(&entry) = self.entries().get(index) else { return; };
+        self.kernel_path = entry.kernel_path;
+        self.command_line = entry.command_line;
+    }
+
+    /// The files to load as boot modules
+    pub fn modules(&self) -> impl Iterator<Item = &ModuleEntry> {
+        self.modules[..self.module_count].iter().filter(|module| !module.path.is_empty())
+    }
+
+    fn set_module_path(&mut self, name: &'static str, path: &'static str) {
+        if let Some(module) = self.modules[..self.module_count].iter_mut().find(|module| module.name == name) {
+            module.path = path;
+        }
+    }
+
+    /// The entry that kernel and cmdline lines apply to
+    fn current_entry(&mut self) -> Option<&mut MenuEntry> {
+        self.entries[..self.entry_count].last_mut()
+    }
+
+    fn add_entry(&mut self, title: &'static str) -> Result<(), ConfigError> {
+        if self.entry_count == MAX_MENU_ENTRIES { return Err(ConfigError::TooManyEntries); }
+
+        self.entries[self.entry_count] = MenuEntry { title, kernel_path: self.kernel_path, command_line: self.command_line };
+        self.entry_count += 1;
+        Ok(())
+    }
+
+    fn add_module(&mut self, module: ModuleEntry) -> Result<(), ConfigError> {
+        if self.module_count == MAX_MODULES { return Err(ConfigError::TooManyModules); }
+
+        self.modules[self.module_count] = module;
+        self.module_count += 1;
+        Ok(())
+    }
+}
+
+impl Default for BootConfig {
+    fn default() -> BootConfig {
+        let empty_module = ModuleEntry { name: "", path: "" };
+        let mut modules = [empty_module; MAX_MODULES];
+        modules[0] = ModuleEntry { name: FONT_MODULE_NAME, path: "kernel/fonts/ascii.psf" };
+        modules[1] = ModuleEntry { name: LOGO_MODULE_NAME, path: "kernel/images/logo.bmp" };
+        modules[2] = ModuleEntry { name: INITRD_MODULE_NAME, path: "kernel/initrd.tar" };
+        let empty_entry = MenuEntry { title: "", kernel_path: "", command_line: "" };
+
+        BootConfig {
+            kernel_path: "kernel/kernel.elf",
+            resolution: None,
+            verbose: true,
+            kaslr: true,
+            command_line: "",
+            timeout: 5,
+            default_entry: "",
+            entries: [empty_entry; MAX_MENU_ENTRIES],
+            entry_count: 0,
+            modules,
+            module_count: 3,
+        }
+    }
+}
+
+impl ConfigError {
+    fn description(&self) -> &'static str {
+        match self {
+            ConfigError::MissingEquals => "expected key = value",
+            ConfigError::UnknownKey => "unknown key",
+            ConfigError::InvalidValue => "invalid value",
+            ConfigError::TooManyModules => "too many modules",
+            ConfigError::TooManyEntries => "too many entries",
+        }
+    }
+}
+
+/// Read the whole config file into pages that stay allocated for the rest of boot
+fn read_config_file(image_handle: efi::Handle, boot_services: &BootServices) -> Result<&'static str, efi::Status> {
+    let (address, _, size) = crate::load_file(image_handle, boot_services, CONFIG_FILE_PATH)?;
+
+    // Safety: load_file just read size bytes to this address and the pages are never freed
+    let bytes = unsafe { core::slice::from_raw_parts(address.as_u64() as *const u8, size) };
+    core::str::from_utf8(bytes).map_err(|_| {
+        com1_println!("{CONFIG_FILE_PATH} is not valid UTF-8");
+        efi::Status::LOAD_ERROR
+    })
+}
+
+fn parse_resolution(value: &str) -> Result<Option<(u32, u32)>, ConfigError> {
+    if value == "auto" { return Ok(None); }
+
+    let (width, height) = value.split_once('x').ok_or(ConfigError::InvalidValue)?;
+    let width = width.trim().parse().map_err(|_| ConfigError::InvalidValue)?;
+    let height = height.trim().parse().map_err(|_| ConfigError::InvalidValue)?;
+    Ok(Some((width, height)))
+}
+
+fn parse_bool(value: &str) -> Result<bool, ConfigError> {
+    match value {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(ConfigError::InvalidValue),
+    }
+}
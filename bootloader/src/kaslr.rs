@@ -0,0 +1,126 @@
+use core::arch::asm;
+
+use x86_64_hardware::{cpuid::{CpuFeatures, CpuInfo}, memory::{VirtualAddress, MEM_1G}, registers::Tsc};
+
+/// Where random values come from, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomSource {
+    /// The CPU's entropy source, seeding values directly
+    RdSeed,
+    /// The CPU's random number generator, reseeded from its entropy source
+    RdRand,
+    /// The timestamp counter. Only hard to guess because of timing jitter in the firmware
+    Tsc,
+}
+
+/// Start of the higher half of the address space, where every kernel mapping goes
+const HIGHER_HALF_START: u64 = 0xFFFF_8000_0000_0000;
+
+/// The most pages left unused before each group of mappings placed after the kernel
+const MAX_MAPPING_GAP_PAGES: u64 = 4096;
+
+/// How many times to retry RDRAND and RDSEED before giving up, as Intel recommends for RDRAND
+const HARDWARE_RETRIES: usize = 10;
+
+/// Randomizes where the kernel and its mappings are placed in virtual memory
+///
+/// When disabled every method returns the offset it would with no randomization, so the
+/// layout is the same on every boot.
+pub struct Kaslr {
+    enabled: bool,
+    source: RandomSource,
+    /// RDSEED and RDRAND are reported separately, so RDSEED can't fall back to RDRAND without this
+    has_rdrand: bool,
+    tsc_state: u64,
+}
+
+impl Kaslr {
+    pub fn new(enabled: bool, cpu: &CpuInfo) -> Kaslr {
+        let has_rdrand = cpu.has(CpuFeatures::RDRAND);
+        let source = if cpu.has(CpuFeatures::RDSEED) {
+            RandomSource::RdSeed
+        } else if has_rdrand {
+            RandomSource::RdRand
+        } else {
+            RandomSource::Tsc
+        };
+
+        Kaslr { enabled, source, has_rdrand, tsc_state: Tsc::read() }
+    }
+
+    pub fn is_enabled(&self) -> bool { self.enabled }
+
+    pub fn source(&self) -> RandomSource { self.source }
+
+    /// A random number below limit, or 0 if randomization is disabled
+    pub fn random_below(&mut self, limit: u64) -> u64 {
+        if !self.enabled || limit == 0 { return 0; }
+        self.random_u64() % limit
+    }
+
+    /// A random number of pages to leave unused before a group of mappings
+    pub fn mapping_gap_pages(&mut self) -> u64 {
+        self.random_below(MAX_MAPPING_GAP_PAGES)
+    }
+
+    /// Where to map all of physical memory so that it ends below the kernel
+    ///
+    /// Without randomization the window ends just below the kernel, rounded down to 1G. Returns None
+    /// if there isn't room for the window.
+    pub fn physical_memory_offset(&mut self, window_size: u64, kernel_base_address: VirtualAddress) -> Option<u64> {
+        let window_size = window_size.div_ceil(MEM_1G) * MEM_1G;
+        let highest_offset = (kernel_base_address.as_u64() & !(MEM_1G - 1)).checked_sub(window_size)?;
+        if highest_offset < HIGHER_HALF_START { return None; }
+
+        let slots = (highest_offset - HIGHER_HALF_START) / MEM_1G + 1;
+        Some(highest_offset - self.random_below(slots) * MEM_1G)
+    }
+
+    fn random_u64(&mut self) -> u64 {
+        let value = match self.source {
+            RandomSource::RdSeed => rdseed().or_else(|| if self.has_rdrand { rdrand() } else { None }),
+            RandomSource::RdRand => rdrand(),
+            RandomSource::Tsc => None,
+        };
+
+        value.unwrap_or_else(|| {
+            // Mix in a fresh TSC reading since the time between calls varies a little
+            self.tsc_state = self.tsc_state.wrapping_add(Tsc::read());
+            splitmix64(&mut self.tsc_state)
+        })
+    }
+}
+
+/// Get a value from RDRAND, or None if it keeps failing
+fn rdrand() -> Option<u64> {
+    for _ in 0..HARDWARE_RETRIES {
+        let value: u64;
+        let success: u8;
+        // Safety: Only called after CPUID reported RDRAND support
+        unsafe { asm!("rdrand {}", "setc {}", out(reg) value, out(reg_byte) success, options(nomem, nostack)) };
+        if success != 0 { return Some(value); }
+    }
+    None
+}
+
+/// Get a value from RDSEED, or None if the entropy source stays empty
+fn rdseed() -> Option<u64> {
+    for _ in 0..HARDWARE_RETRIES {
+        let value: u64;
+        let success: u8;
+        // Safety: Only called after CPUID reported RDSEED support
+        unsafe { asm!("rdseed {}", "setc {}", out(reg) value, out(reg_byte) success, options(nomem, nostack)) };
+        if success != 0 { return Some(value); }
+        core::hint::spin_loop();
+    }
+    None
+}
+
+/// Advance the state and scramble it so nearby TSC values give unrelated outputs
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut value = *state;
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
@@ -1,56 +1,170 @@
 use core::ffi::c_void;
 
+use bootinfo::TlsTemplate;
 use r_efi::efi;
 use x86_64_hardware::{com1_println, memory::{PhysicalAddress, VirtualAddress, PAGE_SIZE}};
 
-use crate::{elf_section_list::ElfSectionList, loaded_asset_list::{LoadedAsset, LoadedAssetList}, uefi::{file_protocol::FileProtocol, BootServices}};
+use crate::{elf_section_list::ElfSectionList, kaslr::Kaslr, loaded_asset_list::{LoadedAsset, LoadedAssetList}, uefi::BootServices};
+
+/// Where the lowest segment of a position independent (ET_DYN) kernel is loaded
+const KERNEL_LOAD_BASE: u64 = 0xFFFFFFFF80000000;
+
+/// KASLR moves a position independent kernel down from KERNEL_LOAD_BASE in steps of this size
+const KERNEL_SLIDE_ALIGNMENT: u64 = 2 * 1024 * 1024;
+
+/// The number of places KASLR can choose between, covering 1G below KERNEL_LOAD_BASE
+const KERNEL_SLIDE_SLOTS: u64 = 512;
+
+/// The kernel ELF file read into memory and checked by the ELF parser
+pub struct KernelFile {
+    elf: elf::ElfFile<'static>,
+    physical_address: PhysicalAddress,
+    num_pages: usize,
+}
+
+impl KernelFile {
+    /// Read the whole kernel file from the boot disk and check that it is a valid x86_64 ELF file
+    pub fn read(image_handle: efi::Handle, boot_services: &BootServices, path: &str) -> Result<KernelFile, efi::Status> {
+        let (physical_address, num_pages, size) = crate::load_file(image_handle, boot_services, path)?;
+
+        // Safety: load_file just read size bytes to this address and the pages stay allocated until free is called
+        let data = unsafe { core::slice::from_raw_parts(physical_address.as_u64() as *const u8, size) };
+        let kernel_file = KernelFile { elf: parse_kernel_elf(data)?, physical_address, num_pages };
+        com1_println!("Kernel header verified successfully!");
+
+        Ok(kernel_file)
+    }
+
+    /// The kernel's thread local storage template, moved by the same offset as the kernel was loaded at
+    pub fn tls_template(&self, load_offset: u64) -> TlsTemplate {
+        let Some(template) = self.elf.tls_template() else { return TlsTemplate::default(); };
+        com1_println!(
+            "Kernel TLS template: vaddr({:#X}), fs({:#X}), ms({:#X}), align({:#X})",
+            template.address + load_offset, template.file_size, template.memory_size, template.alignment
+        );
+
+        TlsTemplate {
+            address: VirtualAddress::new(template.address + load_offset),
+            file_size: template.file_size as usize,
+            memory_size: template.memory_size as usize,
+            alignment: template.alignment as usize,
+        }
+    }
+
+    /// Free the pages holding the file once everything needed has been copied out of it
+    pub fn free(self, boot_services: &BootServices) -> Result<(), efi::Status> {
+        // Safety: Loaded sections and symbols are copies, so nothing refers to the file any more
+        unsafe { boot_services.free_pages(self.physical_address.as_u64() as *mut c_void, self.num_pages) }
+    }
+}
 
 /// Load the kernel into memory
 /// 
-/// Finds the kernel located at kernel/kernel.elf and loads it into memory
-/// Returns a list of loaded program sections that later need to memory mapped
+/// Returns a list of loaded program sections that later need to memory mapped, the entry point
+/// and how far the kernel was moved from its linked addresses
+///
+/// An ET_EXEC kernel is loaded at its linked addresses. An ET_DYN kernel is moved so its lowest segment
+/// starts at KERNEL_LOAD_BASE, or a random 2M aligned address up to 1G below it with KASLR, and has its
+/// dynamic relocations applied.
 pub fn load_kernel(
-    image_handle: efi::Handle, boot_services: &BootServices
-) -> Result<(LoadedAssetList, VirtualAddress), efi::Status> {
-    // Open the kernel file
-    let kernel_file = boot_services.open_file(image_handle, "kernel/kernel.elf")?;
-    com1_println!("Opened kernel file");
+    kernel_file: &KernelFile, boot_services: &BootServices, kaslr: &mut Kaslr
+) -> Result<(LoadedAssetList, VirtualAddress, u64), efi::Status> {
+    let elf_file = &kernel_file.elf;
+    let elf_header = elf_file.header();
+
+    let (section_list, dynamic_header) = get_kernel_sections(elf_file, boot_services)?;
+
+    let is_position_independent = elf_header.common.e_type() == elf::ElfType::Dyn;
+    let load_offset = if is_position_independent {
+        let base = KERNEL_LOAD_BASE - kaslr.random_below(KERNEL_SLIDE_SLOTS) * KERNEL_SLIDE_ALIGNMENT;
+        let Some(load_offset) = base.checked_sub(lowest_load_address(elf_file)) else {
+            com1_println!("The position independent kernel is linked above {:#X}", base);
+            return Err(efi::Status::LOAD_ERROR);
+        };
+        load_offset
+    } else {
+        if kaslr.is_enabled() { com1_println!("The kernel is not position independent so it can't be moved"); }
+        0
+    };
+
+    let asset_list = load_kernel_sections(elf_file, &section_list, load_offset, boot_services)?;
+
+    if is_position_independent {
+        let Some(dynamic_header) = dynamic_header else {
+            com1_println!("The position independent kernel has no dynamic segment");
+            return Err(efi::Status::LOAD_ERROR);
+        };
+        relocate_kernel(&asset_list, &dynamic_header, load_offset)?;
+        com1_println!("Relocated kernel by {:#X}", load_offset);
+    }
 
-    let elf_common = kernel_file.read_struct::<elf::ElfHeaderCommon>()?;
-    validate_kernel_elf(&elf_common)?;
-    com1_println!("Kernel header verified successfully!");
+    Ok((asset_list, VirtualAddress::new(elf_header.e_entry + load_offset), load_offset))
+}
 
-    // Read the ELF header to get information about the ELF file
-    kernel_file.set_position(0)?;
-    let elf_header = kernel_file.read_struct::<elf::ElfHeader64>()?;
+/// The page holding the lowest address of any loadable segment, or 0 if there are none
+fn lowest_load_address(elf_file: &elf::ElfFile) -> u64 {
+    elf_file.program_headers()
+        .filter(|program_header| program_header.p_type() == elf::ElfPhysicalType::Load)
+        .map(|program_header| program_header.p_vaddr & !(PAGE_SIZE - 1))
+        .min()
+        .unwrap_or(0)
+}
 
-    let section_list = get_kernel_sections(
-        &kernel_file, &elf_header, boot_services
-    )?;
+/// A section of the kernel ELF file read into its own pages
+pub struct LoadedSection {
+    pub physical_address: PhysicalAddress,
+    pub num_pages: usize,
+    pub size: usize,
+}
 
-    let asset_list = load_kernel_sections(
-        &kernel_file, &elf_header, &section_list, boot_services
-    )?;
+/// Copy the kernel's symbol table and the string table holding the symbol names into their own pages
+///
+/// These sections are not part of any loadable segment so they are copied straight from the file.
+pub fn load_kernel_symbols(
+    kernel_file: &KernelFile, boot_services: &BootServices
+) -> Result<(LoadedSection, LoadedSection), efi::Status> {
+    let sections = kernel_file.elf.sections();
+    let Some(symbol_table) = sections.find_by_type(elf::ElfSectionType::SymTab) else {
+        com1_println!("The kernel has no symbol table");
+        return Err(efi::Status::NOT_FOUND);
+    };
+    let string_table = sections.get(symbol_table.sh_link as usize).map_err(elf_error)?;
 
-    Ok((asset_list, VirtualAddress::new(elf_header.e_entry)))
+    let symbol_table = copy_section(sections.data(&symbol_table).map_err(elf_error)?, boot_services)?;
+    let string_table = copy_section(sections.data(&string_table).map_err(elf_error)?, boot_services)?;
+    com1_println!("Loaded kernel symbols: symtab({:#X}), strtab({:#X})", symbol_table.size, string_table.size);
+
+    Ok((symbol_table, string_table))
+}
+
+/// Copy the contents of a section into newly allocated pages
+fn copy_section(data: &[u8], boot_services: &BootServices) -> Result<LoadedSection, efi::Status> {
+    let size = data.len();
+    let num_pages = size.div_ceil(PAGE_SIZE as usize).max(1);
+    let buffer = boot_services.allocate_pages::<u8>(r_efi::system::LOADER_DATA, num_pages)?;
+
+    // Safety: The pages were just allocated with room for the whole section
+    unsafe { core::ptr::copy_nonoverlapping(data.as_ptr(), buffer, size) };
+
+    Ok(LoadedSection { physical_address: PhysicalAddress::new(buffer as u64), num_pages, size })
 }
 
 /// Get a list of all program sections that need to be loaded
 /// 
 /// All loadable sections are collected into an ElfSectionList and are sorted with
-/// overlapping and adjacient sections being merged together
+/// overlapping and adjacient sections being merged together.
+/// The dynamic segment's header is returned as well if there is one
 fn get_kernel_sections(
-    kernel_file: &FileProtocol, elf_header: &elf::ElfHeader64, boot_services: &BootServices
-) -> Result<ElfSectionList, efi::Status> {
+    elf_file: &elf::ElfFile, boot_services: &BootServices
+) -> Result<(ElfSectionList, Option<elf::ElfPhysicalHeader64>), efi::Status> {
+    let elf_header = elf_file.header();
     com1_println!("File has {} program sections", elf_header.e_phnum);
 
     // Read all program headers and collect loadable ones into the list
     let mut section_list = ElfSectionList::new(elf_header.e_phnum as usize, &boot_services)?;
-    for header_index in 0..elf_header.e_phnum {
-        let entry_position = elf_header.e_phoff + (u64::from(header_index) * u64::from(elf_header.e_phentsize));
-        kernel_file.set_position(entry_position)?;
-        let program_header = kernel_file.read_struct::<elf::ElfPhysicalHeader64>()?;
-        
+    let mut dynamic_header = None;
+    for (header_index, program_header) in elf_file.program_headers().enumerate() {
+
         // Add loadable sections to the list
         match program_header.p_type() {
             elf::ElfPhysicalType::Load => {
@@ -61,6 +175,7 @@ fn get_kernel_sections(
                     header_index, program_header.p_memsz, program_header.p_filesz, program_header.p_vaddr, program_header.p_offset
                 );
             },
+            elf::ElfPhysicalType::Dynamic => dynamic_header = Some(program_header),
             _ => {},
         }
     }
@@ -68,65 +183,151 @@ fn get_kernel_sections(
     section_list.merge_sections();
     com1_println!("Merged down to {} sections", section_list.size());
 
-    Ok(section_list)
+    Ok((section_list, dynamic_header))
 }
 
 /// Load a list of sections from the ELF file into memory
 /// 
-/// The list of sections should already be merged
+/// The list of sections should already be merged. Every section is moved up by load_offset,
+/// which must be page aligned
 fn load_kernel_sections(
-    kernel_file: &FileProtocol, 
-    elf_header: &elf::ElfHeader64, 
+    elf_file: &elf::ElfFile,
     section_list: &ElfSectionList,
+    load_offset: u64,
     boot_services: &BootServices
 ) -> Result<LoadedAssetList, efi::Status> {
-    let mut kernel_asset_list = LoadedAssetList::new(elf_header.e_phnum as usize, &boot_services)?;
+    let mut kernel_asset_list = LoadedAssetList::new(elf_file.header().e_phnum as usize, &boot_services)?;
     
     // Load each section in the section_list
     for section in section_list.iter() {
         // Allocate pages for the section
-        let section_buffer = boot_services.allocate_pages::<c_void>(
+        let section_buffer = boot_services.allocate_pages::<u8>(
             r_efi::system::LOADER_DATA, 
             section.num_mem_pages as usize
         )?;
-        
-        // Read the program section into memory
-        kernel_file.set_position(section.file_address.as_u64())?;
-        let mut program_size = (section.num_file_pages * PAGE_SIZE) as usize;
-        kernel_file.read(&mut program_size, section_buffer)?;
+
+        // Safety: The pages were just allocated and nothing else refers to them
+        let buffer = unsafe {
+            core::slice::from_raw_parts_mut(section_buffer, (section.num_mem_pages * PAGE_SIZE) as usize)
+        };
+
+        // Start from zeroed pages so .bss and any padding around the segments is empty, then copy in
+        // exactly the file contents of every segment in this section
+        buffer.fill(0);
+        let section_start = section.virtual_address.as_u64();
+        let section_end = section.get_mem_end().as_u64();
+        let segments = elf_file.program_headers().filter(|program_header| {
+            program_header.p_type() == elf::ElfPhysicalType::Load
+                && program_header.p_vaddr >= section_start
+                && program_header.p_vaddr < section_end
+        });
+        for program_header in segments {
+            elf_file.load_segment(&program_header, buffer, section_start).map_err(elf_error)?;
+        }
 
         // Add the program section to the list of loaded assets
+        let virtual_address = VirtualAddress::new(section.virtual_address.as_u64() + load_offset);
         kernel_asset_list.add_asset(
             LoadedAsset::new(
                 PhysicalAddress::new(section_buffer as u64), 
                 section.num_mem_pages as usize, 
-                section.virtual_address
+                virtual_address
             )
         );
-        com1_println!("  Loaded section: \tvaddr({:#X}), \tmp({}), \tfp({})", section.virtual_address.as_u64(), section.num_mem_pages, section.num_file_pages);
+        com1_println!("  Loaded section: \tvaddr({:#X}), \tmp({}), \tfp({})", virtual_address.as_u64(), section.num_mem_pages, section.num_file_pages);
     }
 
     Ok(kernel_asset_list)
 }
 
-/// Check that the kernel ELF file is built for the correct system
-fn validate_kernel_elf(header: &elf::ElfHeaderCommon) -> Result<(), efi::Status> {
-    if !header.has_valid_magic() {
-        com1_println!("Invalid magic");
+/// Apply the relocations listed in the dynamic segment of a kernel loaded at load_offset
+///
+/// The dynamic segment, relocation table and symbol table all lie inside loaded sections, so they are
+/// read from the loaded copy rather than the file.
+fn relocate_kernel(
+    asset_list: &LoadedAssetList, dynamic_header: &elf::ElfPhysicalHeader64, load_offset: u64
+) -> Result<(), efi::Status> {
+    let dynamic_table = elf::ElfDynamicTable::new(
+        loaded_bytes(asset_list, dynamic_header.p_vaddr + load_offset, dynamic_header.p_filesz)?
+    );
+
+    if dynamic_table.find(elf::ElfDynamicTag::Rel).is_some() {
+        com1_println!("REL relocations are not supported, only RELA");
         return Err(efi::Status::LOAD_ERROR);
     }
 
-    if header.class() != elf::ElfClass::Bits64 {
-        com1_println!("Invalid class: {:?}", header.class());
+    let Some(rela_address) = dynamic_table.find(elf::ElfDynamicTag::Rela) else {
+        com1_println!("The kernel has no relocations");
+        return Ok(());
+    };
+    let rela_size = dynamic_table.find(elf::ElfDynamicTag::RelaSz).unwrap_or(0);
+    let rela_entry_size = dynamic_table.find(elf::ElfDynamicTag::RelaEnt).unwrap_or(0);
+    if rela_entry_size != size_of::<elf::ElfRela64>() as u64 {
+        com1_println!("Unexpected relocation entry size: {:#X}", rela_entry_size);
         return Err(efi::Status::LOAD_ERROR);
     }
+    let symbol_table_address = dynamic_table.find(elf::ElfDynamicTag::SymTab);
+    let symbol_entry_size = dynamic_table.find(elf::ElfDynamicTag::SymEnt).unwrap_or(size_of::<elf::ElfSymbol64>() as u64);
 
-    if header.endianness() != elf::ElfEndianness::Little {
-        com1_println!("Invalid endianness: {:?}", header.endianness());
-        return Err(efi::Status::LOAD_ERROR);
+    let relocations = elf::ElfRelaTable::new(loaded_bytes(asset_list, rela_address + load_offset, rela_size)?);
+    for relocation in relocations.iter() {
+        let symbol = match (relocation.symbol_index(), symbol_table_address) {
+            (0, _) | (_, None) => None,
+            (index, Some(address)) => {
+                let symbol_address = address + load_offset + index as u64 * symbol_entry_size;
+                let symbol = loaded_address(asset_list, symbol_address, size_of::<elf::ElfSymbol64>() as u64)?;
+                // Safety: loaded_address checked that the whole symbol is inside loaded memory
+                Some(unsafe { core::ptr::read_unaligned(symbol as *const elf::ElfSymbol64) })
+            },
+        };
+
+        let value = match relocation.value(load_offset, symbol.as_ref()) {
+            Some(elf::ElfRelocationValue::Write64(value)) => value,
+            Some(elf::ElfRelocationValue::Skip) => continue,
+            None => {
+                com1_println!("Unsupported relocation {:?} at {:#X}", relocation.relocation_type(), relocation.r_offset);
+                return Err(efi::Status::LOAD_ERROR);
+            },
+        };
+
+        let target = loaded_address(asset_list, relocation.r_offset + load_offset, size_of::<u64>() as u64)?;
+        // Safety: loaded_address checked that all 8 bytes are inside loaded memory
+        unsafe { core::ptr::write_unaligned(target as *mut u64, value) };
     }
+    com1_println!("Applied {} relocations", relocations.len());
 
-    if header.e_type() != elf::ElfType::Exec {
+    Ok(())
+}
+
+/// Get a pointer to the loaded memory backing a range of the kernel's virtual addresses
+///
+/// Boot services identity map memory, so the physical pages can be accessed directly
+fn loaded_address(asset_list: &LoadedAssetList, virtual_address: u64, size: u64) -> Result<*mut u8, efi::Status> {
+    for asset in asset_list.iter() {
+        let start = asset.virtual_address.as_u64();
+        let end = start + asset.num_pages as u64 * PAGE_SIZE;
+        if virtual_address < start || virtual_address.saturating_add(size) > end { continue; }
+
+        return Ok((asset.physical_address.as_u64() + (virtual_address - start)) as *mut u8);
+    }
+
+    com1_println!("Address range {:#X}+{:#X} is not inside the loaded kernel", virtual_address, size);
+    Err(efi::Status::LOAD_ERROR)
+}
+
+/// Borrow a range of the loaded kernel as bytes
+fn loaded_bytes(asset_list: &LoadedAssetList, virtual_address: u64, size: u64) -> Result<&[u8], efi::Status> {
+    let address = loaded_address(asset_list, virtual_address, size)?;
+    // Safety: The range is inside pages allocated for the asset, which stay allocated until the kernel runs
+    Ok(unsafe { core::slice::from_raw_parts(address, size as usize) })
+}
+
+/// Parse the kernel ELF file and check that it is built for the correct system
+fn parse_kernel_elf(data: &'static [u8]) -> Result<elf::ElfFile<'static>, efi::Status> {
+    let elf_file = elf::ElfFile::parse(data).map_err(elf_error)?;
+    let header = &elf_file.header().common;
+
+    if header.e_type() != elf::ElfType::Exec && header.e_type() != elf::ElfType::Dyn {
         com1_println!("Invalid type: {:?}", header.e_type());
         return Err(efi::Status::LOAD_ERROR);
     }
@@ -136,10 +337,11 @@ fn validate_kernel_elf(header: &elf::ElfHeaderCommon) -> Result<(), efi::Status>
         return Err(efi::Status::LOAD_ERROR);
     }
 
-    if header.e_version() != elf::ElfVersion::Current {
-        com1_println!("Invalid version: {:?}", header.e_version());
-        return Err(efi::Status::LOAD_ERROR);
-    }
+    Ok(elf_file)
+}
 
-    Ok(())
+/// Report a malformed kernel ELF file
+fn elf_error(error: elf::ElfError) -> efi::Status {
+    com1_println!("Invalid kernel ELF file: {error:?}");
+    efi::Status::LOAD_ERROR
 }
@@ -3,14 +3,17 @@
 
 use core::ffi::c_void;
 
-use bootinfo::{BootInfo, MemInfo};
+use bootinfo::{BootInfo, BootModule, CommandLine, KernelSymbols, MAX_COMMAND_LINE_LENGTH, BootModuleList, MemInfo, PixelFormat, MAX_BOOT_MODULES};
 use r_efi::efi;
 use uefi::BootSystemTable;
-use x86_64_hardware::{com1_println, memory::{PageFrameAllocator, PageTableManager, PhysicalAddress, VirtualAddress, MAX_MEM_SIZE, MAX_VIRTUAL_ADDRESS, MEM_1G, PAGE_SIZE}};
+use x86_64_hardware::{com1_println, cpuid::CpuInfo, memory::{PageFrameAllocator, PageTableManager, PhysicalAddress, VirtualAddress, MAX_MEM_SIZE, MAX_VIRTUAL_ADDRESS, MEM_1G, PAGE_SIZE}};
 
-use crate::{kernel_loader::load_kernel, uefi::BootServices};
+use crate::{config::BootConfig, kaslr::Kaslr, kernel_loader::{load_kernel, load_kernel_symbols, KernelFile}, loaded_asset_list::{LoadedAsset, LoadedAssetList}, uefi::{BootServices, GraphicsOutputProtocol, VideoMode}};
 
 mod uefi;
+mod config;
+mod kaslr;
+mod boot_menu;
 mod unicode;
 mod loaded_asset_list;
 mod elf_section_list;
@@ -58,21 +61,67 @@ pub extern "C" fn efi_main(image_handle: efi::Handle, system_table: *const efi::
 fn main(image_handle: efi::Handle, system_table: BootSystemTable) -> Result<(), efi::Status> {
     com1_println!("Bootloader loaded");
 
+    let cpu = CpuInfo::read();
+    com1_println!("CPU: {} {}", cpu.vendor(), cpu.brand());
+    com1_println!("CPU features: {}", cpu.features);
+
     // This is needed later for something
     let bootinfo_size_pages = (core::mem::size_of::<BootInfo>() + PAGE_SIZE as usize - 1) / PAGE_SIZE as usize;
 
     let bootinfo = setup_boot_info(&system_table.boot_services)?;
-    bootinfo.framebuffer = get_graphics_protocol_frame_buffer(image_handle, &system_table.boot_services)?;
+    let mut config = BootConfig::load(image_handle, &system_table.boot_services);
+    let entry = boot_menu::run(&system_table, &config).unwrap_or_else(|status| {
+        com1_println!("Could not show the boot menu, booting the default entry. Status: {status:?}");
+        config.default_entry_index().unwrap_or(0)
+    });
+    if let Some(menu_entry) = config.entries().get(entry) {
+        com1_println!("Booting {}", menu_entry.title);
+    }
+    config.select_entry(entry);
+    bootinfo.command_line = get_command_line(image_handle, &system_table.boot_services, &config);
+    com1_println!("Kernel command line: {}", bootinfo.command_line.as_str());
+    bootinfo.framebuffer = get_graphics_protocol_frame_buffer(image_handle, &system_table.boot_services, &config)?;
+
+    bootinfo.acpi_rsdp_address = match system_table.get_configuration_table().get_rsdp_address() {
+        Some(address) => address,
+        None => {
+            com1_println!("Could not find the ACPI RSDP");
+            0
+        }
+    };
+
+    let mut kaslr = Kaslr::new(config.kaslr, &cpu);
+    if kaslr.is_enabled() {
+        com1_println!("KASLR enabled using {:?}", kaslr.source());
+    }
 
     // Load the kernel into memory
-    let (kernel_asset_list, entry_point) = load_kernel(
-        image_handle, 
-        &system_table.boot_services
+    let kernel_file = KernelFile::read(image_handle, &system_table.boot_services, config.kernel_path)?;
+    let (kernel_asset_list, entry_point, kernel_slide) = load_kernel(
+        &kernel_file,
+        &system_table.boot_services,
+        &mut kaslr
+    )?;
+    bootinfo.kernel_slide = kernel_slide;
+    bootinfo.tls_template = kernel_file.tls_template(kernel_slide);
+
+    // Load the boot modules into memory
+    let module_asset_list = load_modules(
+        image_handle,
+        &system_table.boot_services,
+        &config,
+        end_of_assets(&kernel_asset_list).increment_pages(kaslr.mapping_gap_pages()),
+        &mut bootinfo.modules
     )?;
 
-    // Load the font file into memory
-    let (font_file_address, font_file_page_count, font_file_size) = 
-        load_font(image_handle, &system_table.boot_services)?;
+    // Load the kernel's symbols after the modules so the kernel can name addresses in backtraces
+    let symbol_asset_list = load_symbols(
+        &kernel_file,
+        &system_table.boot_services,
+        end_of_assets(&module_asset_list).max(end_of_assets(&kernel_asset_list)).increment_pages(kaslr.mapping_gap_pages()),
+        &mut bootinfo.kernel_symbols
+    )?;
+    kernel_file.free(&system_table.boot_services)?;
 
     // Exit boot services
     let (_runtime_system_table, mem_info) = unsafe {
@@ -86,9 +135,11 @@ fn main(image_handle: efi::Handle, system_table: BootSystemTable) -> Result<(),
         bootinfo.framebuffer.fill(0, 0);
     }
 
-    com1_println!("Memory Map:");
-    for descriptor in mem_info.map.iter() {
-        com1_println!("  {:?}: {:#X} -> {:#X} p({})", descriptor.mem_type(), descriptor.phys_addr.as_u64(), descriptor.max_physical_address().as_u64(), descriptor.num_pages)
+    if config.verbose {
+        com1_println!("Memory Map:");
+        for descriptor in mem_info.map.iter() {
+            com1_println!("  {:?}: {:#X} -> {:#X} p({})", descriptor.mem_type(), descriptor.phys_addr.as_u64(), descriptor.max_physical_address().as_u64(), descriptor.num_pages)
+        }
     }
 
     let mut allocator = mem_info.map.init_frame_allocator();
@@ -108,7 +159,7 @@ fn main(image_handle: efi::Handle, system_table: BootSystemTable) -> Result<(),
     }
 
     let firmware_page_table_manager = PageTableManager::new_from_cr3(0);
-    let (mut page_table_manager, offset) = match init_page_table_manager(&mut allocator, max_physical_address, kernel_base_address) {
+    let (mut page_table_manager, offset) = match init_page_table_manager(&mut allocator, max_physical_address, kernel_base_address, &mut kaslr) {
         Some(ptm) => ptm,
         None => {
             com1_println!("Memsize too large");
@@ -118,6 +169,7 @@ fn main(image_handle: efi::Handle, system_table: BootSystemTable) -> Result<(),
     com1_println!("Created page table");
 
     (*bootinfo).page_table_memory_offset = offset;
+    com1_println!("Physical memory mapped at {:#X}, kernel slide {:#X}", offset, kernel_slide);
 
     unsafe {
         page_table_manager.activate_page_table();
@@ -127,12 +179,14 @@ fn main(image_handle: efi::Handle, system_table: BootSystemTable) -> Result<(),
     firmware_page_table_manager.release_tables(&mut allocator)
         .expect("Could not release firmware page table.");
 
-    for asset in kernel_asset_list.iter() {
-        com1_println!(
-            "Mapping kernel asset. Phys: {:#X} -> {:#X}, Virt: {:#X} -> {:#X}", 
-            asset.physical_address.as_u64(), asset.physical_address.increment_pages(asset.num_pages as u64).as_u64(),
-            asset.virtual_address.as_u64(), asset.virtual_address.increment_pages(asset.num_pages as u64).as_u64(),
-        );
+    for asset in kernel_asset_list.iter().chain(module_asset_list.iter()).chain(symbol_asset_list.iter()) {
+        if config.verbose {
+            com1_println!(
+                "Mapping kernel asset. Phys: {:#X} -> {:#X}, Virt: {:#X} -> {:#X}", 
+                asset.physical_address.as_u64(), asset.physical_address.increment_pages(asset.num_pages as u64).as_u64(),
+                asset.virtual_address.as_u64(), asset.virtual_address.increment_pages(asset.num_pages as u64).as_u64(),
+            );
+        }
         page_table_manager.map_memory_pages(asset.virtual_address, asset.physical_address, asset.num_pages as u64, &mut allocator)
             .expect("Could not map kernel virtual memory");
         let max_address = asset.virtual_address.increment_pages(asset.num_pages as u64);
@@ -142,7 +196,7 @@ fn main(image_handle: efi::Handle, system_table: BootSystemTable) -> Result<(),
     }
 
     // Map bootinfo into kernel space
-    let bootinfo_virtual_address = bootinfo.next_availiable_kernel_page;
+    let bootinfo_virtual_address = bootinfo.next_availiable_kernel_page.increment_pages(kaslr.mapping_gap_pages());
     let bootinfo_physical_address = PhysicalAddress::new(bootinfo as *mut BootInfo as u64);
     com1_println!("Mapping bootinfo from {:#X} to {:#X}", bootinfo_physical_address.as_u64(), bootinfo_virtual_address.as_u64());
     page_table_manager.map_memory_pages(bootinfo_virtual_address, bootinfo_physical_address, bootinfo_size_pages as u64, &mut allocator)
@@ -159,7 +213,7 @@ fn main(image_handle: efi::Handle, system_table: BootSystemTable) -> Result<(),
     // Map allocator bitmap into kernel space
     let num_bitmap_pages = (allocator.page_bitmap().size() as u64 + PAGE_SIZE - 1) / PAGE_SIZE;
     let bitmap_buffer_physical_addr = PhysicalAddress::new(unsafe { allocator.page_bitmap().get_buffer() as u64 });
-    let bitmap_buffer_virtual_addr = bootinfo.next_availiable_kernel_page;
+    let bitmap_buffer_virtual_addr = bootinfo.next_availiable_kernel_page.increment_pages(kaslr.mapping_gap_pages());
     page_table_manager.map_memory_pages(bitmap_buffer_virtual_addr, bitmap_buffer_physical_addr, num_bitmap_pages, &mut allocator)
         .expect("Could not map allocator bitmap into virtual memory");
     bootinfo.next_availiable_kernel_page = bitmap_buffer_virtual_addr.increment_pages(num_bitmap_pages);
@@ -169,14 +223,6 @@ fn main(image_handle: efi::Handle, system_table: BootSystemTable) -> Result<(),
     let output_bitmap = unsafe { bitmap::Bitmap::new(allocator.page_bitmap().size(), bitmap_buffer_virtual_addr.get_mut_ptr::<u8>()) };
     bootinfo.meminfo = MemInfo::new(output_bitmap, allocator.get_free_ram(), 0, allocator.get_used_ram(), max_physical_address);
 
-    // Map font file into kernel space
-    let font_file_virtual_addr = bootinfo.next_availiable_kernel_page;
-    page_table_manager.map_memory_pages(font_file_virtual_addr, font_file_address, font_file_page_count as u64, &mut allocator)
-        .expect("Could not map font file into virtual memory");
-    bootinfo.next_availiable_kernel_page = font_file_virtual_addr.increment_pages(font_file_page_count as u64);
-    bootinfo.font_file_address = font_file_virtual_addr;
-    bootinfo.font_file_size = font_file_size;
-
     com1_println!("Starting Kernel");
     let kernel_start: unsafe extern "sysv64" fn(*mut BootInfo) = unsafe { core::mem::transmute(entry_point.get_mut_ptr::<c_void>()) };
     unsafe { kernel_start(bootinfo) };
@@ -196,30 +242,150 @@ fn setup_boot_info(boot_services: &BootServices) -> Result<&'static mut BootInfo
     Ok(bootinfo)
 }
 
-fn load_font(image_handle: efi::Handle, boot_services: &BootServices) -> Result<(PhysicalAddress, usize, usize), efi::Status>{
-    com1_println!("Loading Font");
-    let font_file = boot_services.open_file(
-        image_handle, "kernel/fonts/ascii.psf"
-    )?;
+/// Get the kernel command line from the load options the firmware started the bootloader with, or from the config
+///
+/// The UEFI shell passes the whole command used to start the bootloader so the program name is skipped.
+fn get_command_line(image_handle: efi::Handle, boot_services: &BootServices, config: &BootConfig) -> CommandLine {
+    let mut buffer = [0; MAX_COMMAND_LINE_LENGTH];
+    let load_options = match boot_services.get_loaded_image_protocol(image_handle) {
+        Ok(loaded_image) => {
+            let options = unicode::str_utf16_to_utf8(loaded_image.load_options(), &mut buffer);
+            if let Err(status) = loaded_image.close(boot_services) {
+                com1_println!("Could not close the loaded image protocol. Status: {status:?}");
+            }
+            options
+        },
+        Err(status) => {
+            com1_println!("Could not open the loaded image protocol. Status: {status:?}");
+            None
+        }
+    };
+
+    let load_options = load_options
+        .filter(|options| options.chars().all(|c| !c.is_control()))
+        .map(|options| {
+            let options = options.trim();
+            let (first_word, rest) = options.split_once(' ').unwrap_or((options, ""));
+            if is_efi_program(first_word) { rest.trim() } else { options }
+        })
+        .unwrap_or("");
+
+    let command_line = if load_options.is_empty() { config.command_line } else { load_options };
+    CommandLine::new(command_line).unwrap_or_else(|| {
+        com1_println!(
+            "The kernel command line is {} bytes, more than the {MAX_COMMAND_LINE_LENGTH} allowed, so it was ignored",
+            command_line.len()
+        );
+        CommandLine::default()
+    })
+}
+
+fn is_efi_program(path: &str) -> bool {
+    let path = path.as_bytes();
+    path.len() >= 4 && path[path.len() - 4..].eq_ignore_ascii_case(b".efi")
+}
+
+/// Read a whole file from the boot disk into pages, returning the address, page count and file size
+fn load_file(image_handle: efi::Handle, boot_services: &BootServices, path: &str) -> Result<(PhysicalAddress, usize, usize), efi::Status>{
+    com1_println!("Loading {path}");
+    let file = boot_services.open_file(image_handle, path)?;
 
     // Determine the number of pages required for the file
-    let file_info = font_file.get_info(boot_services)?;
+    let file_info = file.get_info(boot_services)?;
     let page_count = ((file_info.file_size + PAGE_SIZE - 1) / PAGE_SIZE) as usize;
-    com1_println!("Font File Size: {:#X}, p({:#X})", file_info.file_size, page_count);
+    com1_println!("File Size: {:#X}, p({:#X})", file_info.file_size, page_count);
 
-    // Read the font-file into memory
+    // Read the file into memory
     let pages = boot_services.allocate_pages(r_efi::system::LOADER_DATA, page_count)?;
     let mut buffer_size = page_count * PAGE_SIZE as usize;
-    font_file.set_position(0)?;
-    font_file.read(&mut buffer_size, pages)?;
+    file.set_position(0)?;
+    file.read(&mut buffer_size, pages)?;
 
     Ok((PhysicalAddress::new(pages as u64), page_count, file_info.file_size as usize))
 }
 
+/// Load every module listed in the boot config, placing them one after another in kernel space from a virtual address
+///
+/// Files that can't be loaded are skipped so the kernel can decide whether it can do without them.
+fn load_modules(
+    image_handle: efi::Handle,
+    boot_services: &BootServices,
+    config: &BootConfig,
+    mut virtual_address: VirtualAddress,
+    modules: &mut BootModuleList,
+) -> Result<LoadedAssetList, efi::Status> {
+    let mut module_asset_list = LoadedAssetList::new(MAX_BOOT_MODULES, boot_services)?;
+
+    for module in config.modules() {
+        let path = module.path;
+        let (physical_address, page_count, size) = match load_file(image_handle, boot_services, path) {
+            Ok(file) => file,
+            Err(status) => {
+                com1_println!("Could not load boot module {path}. Status: {status:?}");
+                continue;
+            }
+        };
+
+        // The config only accepts names that fit in a boot module
+        let boot_module = BootModule::new(module.name, virtual_address, size).expect("Boot module name is too long");
+        if modules.push(boot_module).is_err() {
+            com1_println!("Too many boot modules, skipping {path}");
+            continue;
+        }
+
+        module_asset_list.add_asset(LoadedAsset::new(physical_address, page_count, virtual_address));
+        virtual_address = virtual_address.increment_pages(page_count as u64);
+    }
+
+    Ok(module_asset_list)
+}
+
+/// Load the kernel's symbol and string tables to be mapped at a virtual address
+///
+/// The kernel can run without its symbols so failing to load them is not an error.
+fn load_symbols(
+    kernel_file: &KernelFile,
+    boot_services: &BootServices,
+    virtual_address: VirtualAddress,
+    kernel_symbols: &mut KernelSymbols,
+) -> Result<LoadedAssetList, efi::Status> {
+    let mut symbol_asset_list = LoadedAssetList::new(2, boot_services)?;
+
+    let (symbol_table, string_table) = match load_kernel_symbols(kernel_file, boot_services) {
+        Ok(tables) => tables,
+        Err(status) => {
+            com1_println!("Could not load the kernel symbols. Status: {status:?}");
+            return Ok(symbol_asset_list);
+        }
+    };
+
+    let string_table_address = virtual_address.increment_pages(symbol_table.num_pages as u64);
+    symbol_asset_list.add_asset(LoadedAsset::new(symbol_table.physical_address, symbol_table.num_pages, virtual_address));
+    symbol_asset_list.add_asset(LoadedAsset::new(string_table.physical_address, string_table.num_pages, string_table_address));
+
+    *kernel_symbols = KernelSymbols {
+        symbol_table: virtual_address,
+        symbol_table_size: symbol_table.size,
+        string_table: string_table_address,
+        string_table_size: string_table.size,
+    };
+
+    Ok(symbol_asset_list)
+}
+
+/// The first virtual address after every asset in a list
+fn end_of_assets(asset_list: &LoadedAssetList) -> VirtualAddress {
+    asset_list.iter()
+        .map(|asset| asset.virtual_address.increment_pages(asset.num_pages as u64))
+        .max()
+        .unwrap_or(VirtualAddress::new(0))
+}
+
 fn init_page_table_manager(
     allocator: &mut PageFrameAllocator, 
     max_physical_address: PhysicalAddress, 
-    kernel_base_address: VirtualAddress
+    kernel_base_address: VirtualAddress,
+    kaslr: &mut Kaslr
 ) -> Option<(PageTableManager, u64)> {
     if max_physical_address.as_u64() > MAX_MEM_SIZE {
         return None;
@@ -235,21 +401,25 @@ fn init_page_table_manager(
     // Size of address space set aside in GB
     let num_gb = (max_physical_address.as_u64() + MEM_1G - 1) / MEM_1G;
 
-    let offset;
-    if num_gb * MEM_1G < kernel_base_address.as_u64() {
-        offset = kernel_base_address.as_u64() - num_gb * MEM_1G;
-        page_table_manager.map_memory_pages(VirtualAddress::new(offset), PhysicalAddress::new(0), num_mem_pages, allocator)
-            .expect("Could not map memory pages.");
-    } else {
-        offset = 0;
-    }
+    // Map all of physical memory again below the kernel, at a random 1G aligned address with KASLR
+    let offset = match kaslr.physical_memory_offset(num_gb * MEM_1G, kernel_base_address) {
+        Some(offset) => {
+            page_table_manager.map_memory_pages(VirtualAddress::new(offset), PhysicalAddress::new(0), num_mem_pages, allocator)
+                .expect("Could not map memory pages.");
+            offset
+        },
+        None => {
+            com1_println!("No room to map physical memory below the kernel, so it is only identity mapped and not randomized");
+            0
+        },
+    };
     
     
     Some((page_table_manager, offset))
 }
 
 /// Uses the graphics output protocol to get access to a frame buffer
-fn get_graphics_protocol_frame_buffer(handle: efi::Handle, boot_services: &BootServices) -> Result<bootinfo::FrameBuffer, efi::Status>{
+fn get_graphics_protocol_frame_buffer(handle: efi::Handle, boot_services: &BootServices, config: &BootConfig) -> Result<bootinfo::FrameBuffer, efi::Status>{
     let gop = match boot_services.get_graphics_output_protocol(handle) {
         Ok(gop) => gop,
         Err(status) => {
@@ -258,8 +428,60 @@ fn get_graphics_protocol_frame_buffer(handle: efi::Handle, boot_services: &BootS
         }
     };
     com1_println!("Loaded Graphics Output Protocol");
+    select_video_mode(&gop, boot_services, config);
     let framebuffer = gop.get_framebuffer();
+    com1_println!("Frame buffer pixel format: {:?}", framebuffer.pixel_format);
     gop.close(boot_services)?;
 
     return Ok(framebuffer);
+}
+
+/// Log the video modes the display supports and switch to the one closest to the configured resolution
+///
+/// The firmware's mode is kept if no better mode is found or switching fails.
+fn select_video_mode(gop: &GraphicsOutputProtocol, boot_services: &BootServices, config: &BootConfig) {
+    let current_mode = gop.current_mode();
+    let mut best_mode: Option<VideoMode> = None;
+
+    if config.verbose { com1_println!("Video Modes:"); }
+    for number in 0..gop.max_mode() {
+        let mode = match gop.query_mode(number, boot_services) {
+            Ok(mode) => mode,
+            Err(status) => {
+                com1_println!("  {number}: Could not query mode. Status: {status:?}");
+                continue;
+            }
+        };
+
+        let current = if number == current_mode { " (current)" } else { "" };
+        if config.verbose {
+            com1_println!("  {number}: {}x{} {:?}{current}", mode.width, mode.height, mode.pixel_format);
+        }
+
+        // The kernel can only draw to modes with a linear frame buffer
+        if mode.pixel_format == PixelFormat::BltOnly { continue; }
+        if best_mode.is_none_or(|best| is_better_video_mode(mode, best, config.resolution)) {
+            best_mode = Some(mode);
+        }
+    }
+
+    let Some(mode) = best_mode else { return; };
+    if mode.number == current_mode { return; }
+
+    match gop.set_mode(mode.number) {
+        Ok(()) => com1_println!("Switched to video mode {}: {}x{}", mode.number, mode.width, mode.height),
+        Err(status) => com1_println!("Could not switch to video mode {}. Status: {status:?}", mode.number),
+    }
+}
+
+/// Whether a mode is a closer match to the preferred resolution than another, or larger if there is no preference
+fn is_better_video_mode(mode: VideoMode, other: VideoMode, preferred_resolution: Option<(u32, u32)>) -> bool {
+    match preferred_resolution {
+        Some(resolution) => resolution_distance(mode, resolution) < resolution_distance(other, resolution),
+        None => mode.width as u64 * mode.height as u64 > other.width as u64 * other.height as u64,
+    }
+}
+
+fn resolution_distance(mode: VideoMode, (width, height): (u32, u32)) -> u32 {
+    mode.width.abs_diff(width) + mode.height.abs_diff(height)
 }
\ No newline at end of file
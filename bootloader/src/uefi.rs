@@ -1,6 +1,7 @@
 mod system_table;
 mod boot_services;
 mod simple_text_output_protocol;
+mod simple_text_input_protocol;
 mod graphics_output_protocol;
 mod loaded_image_protocol;
 mod simple_file_system_protocol;
@@ -9,4 +10,7 @@ mod configuration_table;
 mod memory_map;
 
 pub use system_table::BootSystemTable;
-pub use boot_services::BootServices;
\ No newline at end of file
+pub use boot_services::BootServices;
+pub use graphics_output_protocol::{GraphicsOutputProtocol, VideoMode};
+pub use simple_text_input_protocol::{SCAN_DOWN, SCAN_UP};
+pub use simple_text_output_protocol::{SimpleTextOutputProtocol, TEXT_BLACK, TEXT_LIGHT_GRAY, TEXT_WHITE};
\ No newline at end of file
@@ -86,7 +86,7 @@ impl BootServices {
 
     /// ## Safety
     /// The caller must ensure that no references to the freed memory remains
-    unsafe fn free_pool(&self, buffer: *mut c_void) -> Result<(), efi::Status> {
+    pub unsafe fn free_pool(&self, buffer: *mut c_void) -> Result<(), efi::Status> {
         let status = unsafe {
             ((*self.boot_services_ptr).free_pool)(buffer)
         };
@@ -97,6 +97,19 @@ impl BootServices {
         }
     }
 
+    /// Busy wait for at least the given number of microseconds
+    pub fn stall(&self, microseconds: usize) -> Result<(), efi::Status> {
+        // Safety: This should be safe assuming that boot_service_ptr is valid
+        let status = unsafe {
+            ((*self.boot_services_ptr).stall)(microseconds)
+        };
+
+        match status {
+            efi::Status::SUCCESS => Ok(()),
+            _ => Err(status),
+        }
+    }
+
     /// Open a file at the given path on the provided disk that loaded the image
     pub fn open_file(
         &self, image_handle: efi::Handle, path: &str
@@ -33,6 +33,21 @@ impl ConfigurationTable {
         None
     }
 
+    /// Get the physical address of the ACPI RSDP, preferring the ACPI 2.0 table
+    pub fn get_rsdp_address(&self) -> Option<u64> {
+        let mut v1_address = None;
+
+        for entry in self.iter() {
+            match entry.get_type() {
+                TableType::AcpiV2_0 => return Some(entry.vendor_table as u64),
+                TableType::AcpiV1_0 => v1_address = Some(entry.vendor_table as u64),
+                TableType::Unknown => {},
+            }
+        }
+
+        v1_address
+    }
+
     pub fn get_entry(&self, index: usize) -> Option<ConfigurationTableEntry> {
         if index >= self.num_entries { return None; }
 
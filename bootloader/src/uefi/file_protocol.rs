@@ -1,4 +1,4 @@
-use core::{ffi::c_void, ptr::{addr_of, null_mut}};
+use core::{ffi::c_void, ptr::null_mut};
 
 use r_efi::{efi, protocols::file::{self, Info}};
 use x86_64_hardware::memory::PAGE_SIZE;
@@ -110,13 +110,6 @@ impl FileProtocol {
         }
     }
 
-    pub fn read_struct<T: Default>(&self) -> Result<T, efi::Status> {
-        let mut size = core::mem::size_of::<T>();
-        let output = Default::default();
-        self.read(&mut size, addr_of!(output) as *mut c_void)?;
-        return Ok(output);
-    }
-
     pub fn set_position(&self, pos: u64) -> Result<(), efi::Status> {
         let status = unsafe {
             ((*self.file_ptr).set_position)(self.file_ptr, pos)
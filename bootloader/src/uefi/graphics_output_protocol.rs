@@ -1,9 +1,20 @@
-use bootinfo::FrameBuffer;
+use core::{ffi::c_void, ptr::null_mut};
+
+use bootinfo::{FrameBuffer, PixelBitmask, PixelFormat};
 use r_efi::{efi, protocols::graphics_output};
 use x86_64_hardware::memory::PhysicalAddress;
 
 use crate::uefi::BootServices;
 
+/// A video mode reported by the graphics output protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoMode {
+    pub number: u32,
+    pub width: u32,
+    pub height: u32,
+    pub pixel_format: PixelFormat,
+}
+
 pub struct GraphicsOutputProtocol {
     device_handle: efi::Handle,
     agent_handle: efi::Handle,
@@ -36,6 +47,56 @@ impl GraphicsOutputProtocol {
         )
     }
 
+    /// The number of video modes supported. Modes are numbered from 0 to this minus 1
+    pub fn max_mode(&self) -> u32 { self.mode().max_mode() }
+
+    /// The number of the video mode currently in use
+    pub fn current_mode(&self) -> u32 { self.mode().mode_number() }
+
+    /// Get the details of a video mode
+    pub fn query_mode(&self, number: u32, boot_services: &BootServices) -> Result<VideoMode, efi::Status> {
+        let mut info_size = 0;
+        let mut info_ptr = null_mut();
+
+        // Safety: This is safe as long as the protocol pointer points to a valid GOP
+        let status = unsafe {
+            ((*self.graphics_output_protocol_ptr).query_mode)(
+                self.graphics_output_protocol_ptr,
+                number,
+                &mut info_size,
+                &mut info_ptr,
+            )
+        };
+        if status != efi::Status::SUCCESS { return Err(status); }
+
+        // Safety: The firmware just returned this pointer as the mode info
+        let info = unsafe { GopModeInfo::new(info_ptr) };
+        let mode = VideoMode {
+            number,
+            width: info.horizontal_resolution(),
+            height: info.vertical_resolution(),
+            pixel_format: info.pixel_format(),
+        };
+
+        // Safety: The info was allocated for us by the firmware and is no longer used
+        unsafe { boot_services.free_pool(info_ptr as *mut c_void)?; }
+
+        Ok(mode)
+    }
+
+    /// Switch to a different video mode. This clears the screen
+    pub fn set_mode(&self, number: u32) -> Result<(), efi::Status> {
+        // Safety: This is safe as long as the protocol pointer points to a valid GOP
+        let status = unsafe {
+            ((*self.graphics_output_protocol_ptr).set_mode)(self.graphics_output_protocol_ptr, number)
+        };
+
+        match status {
+            efi::Status::SUCCESS => Ok(()),
+            _ => Err(status),
+        }
+    }
+
     /// Get the framebuffer from the GraphicsOutputProtocol
     pub fn get_framebuffer(&self) -> FrameBuffer {
         let mode = self.mode();
@@ -46,6 +107,8 @@ impl GraphicsOutputProtocol {
             mode.info().horizontal_resolution(),
             mode.info().vertical_resolution(),
             mode.info().pixels_per_scan_line(),
+            mode.info().pixel_format(),
+            mode.info().pixel_bitmask(),
         ) {
             Ok(buffer) => buffer,
             Err(e) => panic!(
@@ -78,6 +141,20 @@ impl GopMode {
         GopMode { mode_ptr }
     }
 
+    pub fn max_mode(&self) -> u32 {
+        // Safety: This is safe as long as the mode pointer is actually a mode pointer
+        unsafe {
+            (*self.mode_ptr).max_mode
+        }
+    }
+
+    pub fn mode_number(&self) -> u32 {
+        // Safety: This is safe as long as the mode pointer is actually a mode pointer
+        unsafe {
+            (*self.mode_ptr).mode
+        }
+    }
+
     pub fn frame_buffer_base(&self) -> PhysicalAddress {
         // Safety: This is safe as long as the mode pointer is actually a mode pointer
         unsafe {
@@ -135,4 +212,30 @@ impl GopModeInfo {
             (*self.info_ptr).pixels_per_scan_line
         }
     }
+
+    /// The layout of each pixel. Unknown formats are treated as having no linear frame buffer
+    pub fn pixel_format(&self) -> PixelFormat {
+        // Safety: This is safe as long as the info pointer actuall points to mode info
+        let pixel_format = unsafe { (*self.info_ptr).pixel_format };
+
+        match pixel_format {
+            graphics_output::PIXEL_RED_GREEN_BLUE_RESERVED_8_BIT_PER_COLOR => PixelFormat::Rgb,
+            graphics_output::PIXEL_BLUE_GREEN_RED_RESERVED_8_BIT_PER_COLOR => PixelFormat::Bgr,
+            graphics_output::PIXEL_BIT_MASK => PixelFormat::Bitmask,
+            _ => PixelFormat::BltOnly,
+        }
+    }
+
+    /// The channel masks, only meaningful when the pixel format is Bitmask
+    pub fn pixel_bitmask(&self) -> PixelBitmask {
+        // Safety: This is safe as long as the info pointer actuall points to mode info
+        let bitmask = unsafe { (*self.info_ptr).pixel_information };
+
+        PixelBitmask {
+            red_mask: bitmask.red_mask,
+            green_mask: bitmask.green_mask,
+            blue_mask: bitmask.blue_mask,
+            reserved_mask: bitmask.reserved_mask,
+        }
+    }
 }
\ No newline at end of file
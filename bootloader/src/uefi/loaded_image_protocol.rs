@@ -33,4 +33,18 @@ impl LoadedImageProtocol {
         // Should be safe assuming the protocol_ptr is valid
         unsafe { (*self.protocol_ptr).device_handle }
     }
+
+    /// Get the options the image was started with, which firmware passes as a UTF-16 string
+    ///
+    /// Boot managers can pass any data here so it may not be text
+    pub fn load_options(&self) -> &[u16] {
+        // Should be safe assuming the protocol_ptr is valid
+        unsafe {
+            let options = (*self.protocol_ptr).load_options as *const u16;
+            let length = (*self.protocol_ptr).load_options_size as usize / core::mem::size_of::<u16>();
+            if options.is_null() || length == 0 { return &[]; }
+
+            core::slice::from_raw_parts(options, length)
+        }
+    }
 }
\ No newline at end of file
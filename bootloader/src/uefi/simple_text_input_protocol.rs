@@ -0,0 +1,50 @@
+use r_efi::{efi, protocols::simple_text_input};
+
+/// Scan codes for keys that don't produce a character
+pub const SCAN_UP: u16 = 0x01;
+pub const SCAN_DOWN: u16 = 0x02;
+
+/// A key press read from the console, either a character or a scan code for keys such as the arrows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub scan_code: u16,
+    pub character: Option<char>,
+}
+
+pub struct SimpleTextInputProtocol {
+    input_ptr: *mut simple_text_input::Protocol,
+}
+
+impl SimpleTextInputProtocol {
+    pub fn new(input_ptr: *mut simple_text_input::Protocol) -> SimpleTextInputProtocol {
+        SimpleTextInputProtocol { input_ptr }
+    }
+
+    /// Clear any key presses waiting to be read
+    pub fn reset(&self) -> Result<(), efi::Status> {
+        // Safety: This should be safe assuming input_ptr is valid
+        let status = unsafe { ((*self.input_ptr).reset)(self.input_ptr, efi::Boolean::FALSE) };
+
+        match status {
+            efi::Status::SUCCESS => Ok(()),
+            _ => Err(status),
+        }
+    }
+
+    /// Read the next key press without waiting, returning None if no key has been pressed
+    pub fn read_key(&self) -> Result<Option<Key>, efi::Status> {
+        let mut key = simple_text_input::InputKey { scan_code: 0, unicode_char: 0 };
+
+        // Safety: This should be safe assuming input_ptr is valid
+        let status = unsafe { ((*self.input_ptr).read_key_stroke)(self.input_ptr, &mut key) };
+
+        match status {
+            efi::Status::SUCCESS => Ok(Some(Key {
+                scan_code: key.scan_code,
+                character: char::from_u32(key.unicode_char as u32).filter(|&c| c != '\0'),
+            })),
+            efi::Status::NOT_READY => Ok(None),
+            _ => Err(status),
+        }
+    }
+}
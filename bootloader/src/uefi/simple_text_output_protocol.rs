@@ -1,6 +1,15 @@
-use r_efi;
+use core::fmt;
+
+use r_efi::{self, efi};
 use r_efi::protocols::simple_text_output;
 
+use crate::unicode::{str_utf8_to_utf16, EncodeStatus};
+
+/// Text colors, combined with a background color as `foreground | background << 4`
+pub const TEXT_BLACK: usize = 0x00;
+pub const TEXT_LIGHT_GRAY: usize = 0x07;
+pub const TEXT_WHITE: usize = 0x0F;
+
 pub struct SimpleTextOutputProtocol {
     output_ptr: *mut simple_text_output::Protocol,
 }
@@ -9,4 +18,70 @@ impl SimpleTextOutputProtocol {
     pub fn new(output_ptr: *mut simple_text_output::Protocol) -> SimpleTextOutputProtocol {
         SimpleTextOutputProtocol { output_ptr }
     }
-}
\ No newline at end of file
+
+    /// Write a string at the cursor, converting it to UTF-16 in chunks
+    pub fn output_string(&self, string: &str) -> Result<(), efi::Status> {
+        let mut buffer = [0; 128];
+        let mut remaining = string;
+
+        while !remaining.is_empty() {
+            let EncodeStatus { input_read, .. } = str_utf8_to_utf16(remaining, &mut buffer);
+            remaining = &remaining[input_read..];
+
+            // Safety: This should be safe assuming output_ptr is valid. The buffer is NULL terminated
+            let status = unsafe { ((*self.output_ptr).output_string)(self.output_ptr, buffer.as_mut_ptr()) };
+            if status.is_error() { return Err(status); }
+        }
+
+        Ok(())
+    }
+
+    /// Clear the screen with the current background color and move the cursor to the top left
+    pub fn clear_screen(&self) -> Result<(), efi::Status> {
+        // Safety: This should be safe assuming output_ptr is valid
+        let status = unsafe { ((*self.output_ptr).clear_screen)(self.output_ptr) };
+
+        match status {
+            efi::Status::SUCCESS => Ok(()),
+            _ => Err(status),
+        }
+    }
+
+    /// Set the colors for text written after this, as `foreground | background << 4`
+    pub fn set_attribute(&self, attribute: usize) -> Result<(), efi::Status> {
+        // Safety: This should be safe assuming output_ptr is valid
+        let status = unsafe { ((*self.output_ptr).set_attribute)(self.output_ptr, attribute) };
+
+        match status {
+            efi::Status::SUCCESS => Ok(()),
+            _ => Err(status),
+        }
+    }
+
+    pub fn set_cursor_position(&self, column: usize, row: usize) -> Result<(), efi::Status> {
+        // Safety: This should be safe assuming output_ptr is valid
+        let status = unsafe { ((*self.output_ptr).set_cursor_position)(self.output_ptr, column, row) };
+
+        match status {
+            efi::Status::SUCCESS => Ok(()),
+            _ => Err(status),
+        }
+    }
+
+    /// Show or hide the cursor. Not every console supports hiding it
+    pub fn enable_cursor(&self, visible: bool) -> Result<(), efi::Status> {
+        // Safety: This should be safe assuming output_ptr is valid
+        let status = unsafe { ((*self.output_ptr).enable_cursor)(self.output_ptr, visible.into()) };
+
+        match status {
+            efi::Status::SUCCESS => Ok(()),
+            _ => Err(status),
+        }
+    }
+}
+
+impl fmt::Write for SimpleTextOutputProtocol {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.output_string(s).map_err(|_| fmt::Error)
+    }
+}
@@ -3,6 +3,7 @@ use crate::uefi::memory_map::GetMemoryMapOutput;
 
 use super::boot_services::BootServices;
 use super::configuration_table::{ConfigurationTable, ConfigurationTableEntry};
+use super::simple_text_input_protocol::SimpleTextInputProtocol;
 use super::simple_text_output_protocol::SimpleTextOutputProtocol;
 
 /// Provides a wrapper around the system table while boot services are running
@@ -32,6 +33,13 @@ impl BootSystemTable {
         }
     }
 
+    pub fn con_in(&self) -> SimpleTextInputProtocol {
+        // Safety: This should be safe assuming this is a valid system table
+        unsafe {
+            return SimpleTextInputProtocol::new((*self.system_table_ptr).con_in)
+        }
+    }
+
     pub fn get_configuration_table(&self) -> ConfigurationTable {
         // Safety: This should be safe assuming this is a valid system table
         unsafe {
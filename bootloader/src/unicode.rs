@@ -38,6 +38,23 @@ pub fn str_utf8_to_utf16(input: &str, buffer: &mut [u16]) -> EncodeStatus {
 
 }
 
+/// Decode a UTF-16 string into a buffer, stopping at the first NULL
+///
+/// Returns None if the input is not valid UTF-16 or does not fit in the buffer
+pub fn str_utf16_to_utf8<'a>(input: &[u16], buffer: &'a mut [u8]) -> Option<&'a str> {
+    let input_length = input.iter().position(|&c| c == 0).unwrap_or(input.len());
+    let mut buffer_pos = 0;
+
+    for decoded in char::decode_utf16(input[..input_length].iter().copied()) {
+        let character = decoded.ok()?;
+        if buffer_pos + character.len_utf8() > buffer.len() { return None; }
+
+        buffer_pos += character.encode_utf8(&mut buffer[buffer_pos..]).len();
+    }
+
+    core::str::from_utf8(&buffer[..buffer_pos]).ok()
+}
+
 enum EncodeCharStatus {
     EncodedChar {
         encoded_char: u16,
@@ -0,0 +1,86 @@
+use acpi_system_tables::{FixedAcpiDescriptionTable, RsdpV1, RsdpV2, SignatureType, SystemDescriptionTable};
+use bootinfo::BootInfo;
+use spin::Mutex;
+use x86_64_hardware::devices::ioport::Port;
+
+use crate::log_warn;
+
+const ADDRESS_SPACE_SYSTEM_MEMORY: u8 = 0;
+const ADDRESS_SPACE_SYSTEM_IO: u8 = 1;
+
+struct AcpiInfo {
+    rsdp_address: u64,
+    memory_offset: u64,
+}
+
+static ACPI_INFO: Mutex<Option<AcpiInfo>> = Mutex::new(None);
+
+/// Locate and validate the RSDP passed by the bootloader
+pub fn initialize(bootinfo: &BootInfo) {
+    if bootinfo.acpi_rsdp_address == 0 {
+        log_warn!("ACPI", "The bootloader did not provide an RSDP");
+        return;
+    }
+
+    let memory_offset = bootinfo.page_table_memory_offset;
+    let rsdp = unsafe { *((bootinfo.acpi_rsdp_address + memory_offset) as *const RsdpV1) };
+    if !rsdp.is_valid() {
+        log_warn!("ACPI", "RSDP at {:#X} is invalid", bootinfo.acpi_rsdp_address);
+        return;
+    }
+
+    *ACPI_INFO.lock() = Some(AcpiInfo { rsdp_address: bootinfo.acpi_rsdp_address, memory_offset });
+}
+
+/// Call f with every table listed in the XSDT, or the RSDT on ACPI 1.0 systems
+pub fn for_each_table(mut f: impl FnMut(SystemDescriptionTable)) {
+    let info = ACPI_INFO.lock();
+    let Some(info) = info.as_ref() else { return };
+
+    let rsdp_ptr = info.rsdp_address + info.memory_offset;
+    let rsdp = unsafe { *(rsdp_ptr as *const RsdpV1) };
+
+    if rsdp.revision() >= 2 {
+        let rsdp = unsafe { *(rsdp_ptr as *const RsdpV2) };
+        if rsdp.is_valid() {
+            rsdp.get_xsdt(info.memory_offset).iter().for_each(&mut f);
+            return;
+        }
+    }
+
+    rsdp.get_rsdt(info.memory_offset).iter().for_each(&mut f);
+}
+
+/// Find the first table with the given signature
+pub fn find_table(signature: SignatureType) -> Option<SystemDescriptionTable> {
+    let mut output = None;
+
+    for_each_table(|table| {
+        if output.is_none() && table.get_signature() == signature {
+            output = Some(table);
+        }
+    });
+
+    output
+}
+
+pub fn fadt() -> Option<FixedAcpiDescriptionTable> {
+    FixedAcpiDescriptionTable::from_table(&find_table(SignatureType::FACP)?)
+}
+
+/// Reset the system using the FADT reset register
+///
+/// Returns if the table does not provide a usable reset register.
+pub fn reset_system() {
+    let Some((register, value)) = fadt().and_then(|fadt| fadt.reset_register()) else { return };
+    let address = register.address;
+
+    match register.address_space {
+        ADDRESS_SPACE_SYSTEM_IO => unsafe { Port::new(address as u16).out_u8(value) },
+        ADDRESS_SPACE_SYSTEM_MEMORY => {
+            let Some(memory_offset) = ACPI_INFO.lock().as_ref().map(|info| info.memory_offset) else { return };
+            unsafe { core::ptr::write_volatile((address + memory_offset) as *mut u8, value) }
+        },
+        address_space => log_warn!("ACPI", "Reset register is in unsupported address space {address_space}"),
+    }
+}
@@ -0,0 +1,83 @@
+use core::arch::asm;
+
+use x86_64_hardware::memory::VirtualAddress;
+
+use crate::{memory, println, symbols::{self, Demangle}};
+
+/// Stop after this many frames in case the frame chain loops
+const MAX_FRAMES: usize = 32;
+
+/// Print the functions that called the current one by following the saved frame pointers
+///
+/// The kernel is built with `-C force-frame-pointers=yes` so every function saves the caller's rbp
+/// next to its return address. `_start` clears rbp so the chain ends at kernel_main.
+#[inline(never)]
+pub fn print() {
+    println!("Backtrace:");
+    walk(frame_pointer());
+}
+
+/// Print a backtrace for code that was interrupted at an instruction with a frame pointer
+pub fn print_from(instruction_pointer: u64, frame_pointer: u64) {
+    println!("Backtrace:");
+    print_frame(0, instruction_pointer);
+    walk(frame_pointer);
+}
+
+/// The frame pointer of the function this is inlined into
+#[inline(always)]
+pub fn frame_pointer() -> u64 {
+    let frame_pointer: u64;
+    unsafe { asm!("mov {}, rbp", out(reg) frame_pointer, options(nomem, nostack, preserves_flags)); }
+    frame_pointer
+}
+
+/// The frame pointer of the function that called the one this is inlined into
+#[inline(always)]
+pub fn caller_frame_pointer() -> u64 {
+    let frame_pointer = frame_pointer();
+    if !is_valid_frame(frame_pointer) { return 0; }
+
+    // Safety: The frame was checked to be mapped
+    unsafe { *(frame_pointer as *const u64) }
+}
+
+fn walk(mut frame_pointer: u64) {
+    for index in 1..=MAX_FRAMES {
+        if !is_valid_frame(frame_pointer) { return; }
+
+        // Safety: The frame was checked to be mapped. Each frame holds the caller's rbp followed by the return address
+        let (caller_frame_pointer, return_address) = unsafe {
+            let frame = frame_pointer as *const u64;
+            (*frame, *frame.add(1))
+        };
+        if return_address == 0 { return; }
+
+        // The return address is just after the call so look up the call instruction itself
+        print_frame(index, return_address - 1);
+
+        // The stack grows down so callers' frames are always higher
+        if caller_frame_pointer <= frame_pointer { return; }
+        frame_pointer = caller_frame_pointer;
+    }
+
+    println!("  ...");
+}
+
+fn print_frame(index: usize, address: u64) {
+    match symbols::lookup(address) {
+        Some(symbol) => println!("  #{index:<2} {address:#018X} {}+{:#X}", Demangle(symbol.name), symbol.offset),
+        None => println!("  #{index:<2} {address:#018X} ??"),
+    }
+}
+
+/// Whether a frame pointer is aligned and both words of its frame are mapped
+fn is_valid_frame(frame_pointer: u64) -> bool {
+    if frame_pointer == 0 || !frame_pointer.is_multiple_of(8) { return false; }
+    // The return address is the second word, which a corrupt frame pointer can put past the address space
+    let Some(return_address_pointer) = frame_pointer.checked_add(8) else { return false; };
+
+    let page_table = memory::active_page_table();
+    [frame_pointer, return_address_pointer].iter()
+        .all(|&address| page_table.walk(VirtualAddress::new(address)).physical_address.is_some())
+}
@@ -0,0 +1,99 @@
+use bootinfo::BootInfo;
+
+use crate::{log_info, log_warn, logger::{self, LogLevel}};
+
+/// Where the kernel console is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Console {
+    /// Draw the console and boot splash on the frame buffer as well as logging to COM1
+    FrameBuffer,
+    /// Leave the frame buffer alone and only log to COM1
+    Serial,
+}
+
+/// Options from the command line that the rest of kernel initialization depends on
+#[derive(Debug, Clone, Copy)]
+pub struct KernelOptions {
+    pub console: Console,
+}
+
+impl Default for KernelOptions {
+    fn default() -> KernelOptions {
+        KernelOptions { console: Console::FrameBuffer }
+    }
+}
+
+/// Apply the options in the command line passed by the bootloader
+///
+/// The command line is made of space separated `option=value` words:
+/// - `loglevel=debug|info|warn|error|critical` sets the lowest level logged anywhere
+/// - `serial_loglevel=...` and `console_loglevel=...` set it for one output
+/// - `serial=on|off` turns all output to COM1 on or off
+/// - `serial_colors=on|off` colors COM1 log messages by level
+/// - `log_prefix=on|off` starts log messages with their level
+/// - `console=fb|serial` chooses whether the frame buffer console is used
+///
+/// Invalid options are logged and skipped so the rest still apply.
+pub fn apply(bootinfo: &BootInfo) -> KernelOptions {
+    let command_line = bootinfo.command_line.as_str();
+    let mut options = KernelOptions::default();
+
+    for word in command_line.split_whitespace() {
+        let Some((name, value)) = word.split_once('=') else {
+            log_warn!("Command Line", "Expected option=value but found '{word}'");
+            continue;
+        };
+
+        if !apply_option(&mut options, name, value) {
+            log_warn!("Command Line", "Ignoring invalid option '{word}'");
+        }
+    }
+
+    if !command_line.is_empty() {
+        log_info!("Command Line", "{command_line}");
+    }
+
+    options
+}
+
+/// Returns false if the option is unknown or its value is invalid
+fn apply_option(options: &mut KernelOptions, name: &str, value: &str) -> bool {
+    let mut output_options = logger::get_output_options();
+
+    match name {
+        "loglevel" | "serial_loglevel" | "console_loglevel" => {
+            let Some(level) = LogLevel::from_name(value) else { return false; };
+            if name != "console_loglevel" { logger::set_min_serial_log_level(level); }
+            if name != "serial_loglevel" { logger::set_min_display_log_level(level); }
+        },
+        "serial" => match parse_switch(value) {
+            Some(enabled) => output_options.serial = enabled,
+            None => return false,
+        },
+        "serial_colors" => match parse_switch(value) {
+            Some(enabled) => output_options.serial_colors = enabled,
+            None => return false,
+        },
+        "log_prefix" => match parse_switch(value) {
+            Some(enabled) => output_options.level_prefix = enabled,
+            None => return false,
+        },
+        "console" => match value {
+            "fb" => options.console = Console::FrameBuffer,
+            "serial" => options.console = Console::Serial,
+            _ => return false,
+        },
+        _ => return false,
+    }
+
+    logger::set_output_options(output_options);
+    true
+}
+
+fn parse_switch(value: &str) -> Option<bool> {
+    match value {
+        "on" | "yes" | "true" | "1" => Some(true),
+        "off" | "no" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
@@ -0,0 +1,43 @@
+use spin::Mutex;
+use x86_64_hardware::cpuid::{CpuFeatures, CpuInfo};
+
+use crate::{log_info, log_warn};
+
+/// Features later subsystems rely on, warned about at boot if the CPU lacks them
+const EXPECTED_FEATURES: [(CpuFeatures, &str); 6] = [
+    (CpuFeatures::NX, "NX"),
+    (CpuFeatures::PAGE_1G, "1G pages"),
+    (CpuFeatures::X2APIC, "x2APIC"),
+    (CpuFeatures::INVARIANT_TSC, "invariant TSC"),
+    (CpuFeatures::RDRAND, "RDRAND"),
+    (CpuFeatures::XSAVE, "XSAVE"),
+];
+
+/// The boot CPU's CPUID, read once so subsystems can check for features instead of assuming them
+static CPU_INFO: Mutex<Option<CpuInfo>> = Mutex::new(None);
+
+/// Decode CPUID on the boot CPU and log what it found
+pub fn initialize() {
+    let info = CpuInfo::read();
+    *CPU_INFO.lock() = Some(info);
+
+    log_info!("CPU", "{} {}", info.vendor(), info.brand());
+    log_info!("CPU", "Family {:#X}, model {:#X}, stepping {}", info.family, info.model, info.stepping);
+    log_info!(
+        "CPU", "APIC ID {}, {} logical processors, {} threads per core",
+        info.topology.apic_id, info.topology.logical_processors, info.topology.threads_per_core
+    );
+    log_info!("CPU", "{} bit physical and {} bit virtual addresses", info.physical_address_bits, info.virtual_address_bits);
+    log_info!("CPU", "Features: {}", info.features);
+
+    for (feature, name) in EXPECTED_FEATURES {
+        if !info.has(feature) {
+            log_warn!("CPU", "{name} is not supported");
+        }
+    }
+}
+
+/// The boot CPU's CPUID, or None before initialize has run
+pub fn info() -> Option<CpuInfo> {
+    *CPU_INFO.lock()
+}
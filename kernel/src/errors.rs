@@ -1,7 +1,11 @@
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorStatus {
-    InvalidFileFormat
+    InvalidFileFormat,
+    /// The frame buffer uses a pixel format that can't be drawn to
+    UnsupportedPixelFormat,
+    /// The heap could not grow to fit an allocation
+    OutOfMemory,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
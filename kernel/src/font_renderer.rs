@@ -1,10 +1,15 @@
-use crate::{errors::{Error, ErrorStatus}, graphics_renderer::{Color, FrameBuffer}};
+use crate::{errors::Error, graphics_renderer::{Canvas, Color, FrameBuffer}};
 
-const GLYPH_START_OFFSET: usize = 4;
+use self::psf::PsfFont;
+
+mod psf;
+
+const REPLACEMENT_CHARACTER: char = '\u{FFFD}';
 
 pub struct FontRenderer {
-    font_data: &'static [u8],
-    glyph_height: usize,
+    font: PsfFont,
+    /// Glyph drawn for characters the font does not contain
+    replacement_glyph: usize,
     foreground_color: Color,
     background_color: Color,
     scale: usize,
@@ -13,18 +18,20 @@ pub struct FontRenderer {
 
 impl FontRenderer {
 
+    /// Create a renderer for a PSF1 or PSF2 font
     pub fn create(font_address: *const u8, font_size: usize, frame_buffer: FrameBuffer) -> Result<FontRenderer, Error> {
         let font_data = unsafe {
             core::slice::from_raw_parts(font_address, font_size)
         };
 
-        if !is_valid_font_header(font_data) { return Err(Error::new(ErrorStatus::InvalidFileFormat)) }
+        let font = PsfFont::parse(font_data)?;
+        let replacement_glyph = font.glyph_index(REPLACEMENT_CHARACTER)
+            .or_else(|| font.glyph_index('?'))
+            .unwrap_or(0);
 
-        let glyph_height = font_data[3] as usize;
-
-        Ok( FontRenderer { 
-            font_data, 
-            glyph_height,
+        Ok( FontRenderer {
+            font,
+            replacement_glyph,
             foreground_color: Color::new(0x00B000),
             background_color: Color::new(0x000000),
             scale: 2,
@@ -34,35 +41,35 @@ impl FontRenderer {
 
     pub fn get_graphics_renderer(&self) -> &FrameBuffer { &self.frame_buffer }
 
-    pub fn draw_glyph(&mut self, glyph: u8, x: usize, y: usize) {
-        let glyph_start = (glyph as usize) * (self.glyph_height as usize) + GLYPH_START_OFFSET;
-        let glyph_end = glyph_start + (self.glyph_height as usize);
-        let glyph_bytes = &self.font_data[glyph_start..glyph_end];
+    pub fn get_graphics_renderer_mut(&mut self) -> &mut FrameBuffer { &mut self.frame_buffer }
+
+    /// Get the glyph used to draw a character, falling back to the replacement glyph
+    pub fn glyph_for_char(&self, character: char) -> usize {
+        self.font.glyph_index(character).unwrap_or(self.replacement_glyph)
+    }
+
+    pub fn draw_glyph(&mut self, glyph: usize, x: usize, y: usize) {
+        let glyph_bytes = self.font.glyph_bitmap(glyph);
+        let bytes_per_row = self.font.width().div_ceil(8);
 
-        for row in 0..self.glyph_height {
-            let byte = glyph_bytes[row];
-            for col in 0..8 {
-                let fill_pixel = byte & (1 << (7 - col)) != 0;
+        for row in 0..self.font.height() {
+            let row_bytes = &glyph_bytes[row * bytes_per_row..(row + 1) * bytes_per_row];
+            for col in 0..self.font.width() {
+                let fill_pixel = row_bytes[col / 8] & (1 << (7 - col % 8)) != 0;
                 let color = if fill_pixel { self.foreground_color } else { self.background_color };
 
-                for py in 0..self.scale {
-                    for px in 0..self.scale {
-                        self.frame_buffer.set_pixel(x + col * self.scale + px, y + row * self.scale + py, color);
-                    }
-                }
+                self.frame_buffer.fill_rect(x + col * self.scale, y + row * self.scale, self.scale, self.scale, color);
             }
         }
     }
 
-    pub fn get_glyph_width(&self) -> usize { 8 * self.scale }
+    pub fn get_glyph_width(&self) -> usize { self.font.width() * self.scale }
 
-    pub fn get_glyph_height(&self) -> usize { self.glyph_height * self.scale }
+    pub fn get_glyph_height(&self) -> usize { self.font.height() * self.scale }
 
     pub fn set_colors(&mut self, foreground: Color, background: Color) {
         self.foreground_color = foreground;
         self.background_color = background;
     }
 
-}
-
-fn is_valid_font_header(font_data: &[u8]) -> bool { (font_data[0] == 0x36) && (font_data[1] == 0x04) }
\ No newline at end of file
+}
\ No newline at end of file
@@ -0,0 +1,195 @@
+use alloc::vec::Vec;
+
+use crate::errors::{Error, ErrorStatus};
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_HEADER_SIZE: usize = 4;
+const PSF1_MODE_512: u8 = 0x01;
+const PSF1_MODE_HAS_TABLE: u8 = 0x02;
+const PSF1_MODE_HAS_SEQUENCES: u8 = 0x04;
+const PSF1_GLYPH_WIDTH: usize = 8;
+const PSF1_SEPARATOR: u16 = 0xFFFF;
+const PSF1_START_SEQUENCE: u16 = 0xFFFE;
+
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xB5, 0x4A, 0x86];
+const PSF2_HEADER_SIZE: usize = 32;
+const PSF2_FLAG_HAS_TABLE: u32 = 0x01;
+const PSF2_SEPARATOR: u8 = 0xFF;
+const PSF2_START_SEQUENCE: u8 = 0xFE;
+
+/// A PC Screen Font (version 1 or 2)
+pub struct PsfFont {
+    data: &'static [u8],
+    glyph_offset: usize,
+    glyph_count: usize,
+    bytes_per_glyph: usize,
+    width: usize,
+    height: usize,
+    /// Pairs of character and glyph index sorted by character
+    unicode_map: Vec<(char, usize)>,
+}
+
+impl PsfFont {
+    pub fn parse(data: &'static [u8]) -> Result<PsfFont, Error> {
+        if data.starts_with(&PSF2_MAGIC) {
+            PsfFont::parse_psf2(data)
+        } else if data.starts_with(&PSF1_MAGIC) {
+            PsfFont::parse_psf1(data)
+        } else {
+            Err(Error::new(ErrorStatus::InvalidFileFormat))
+        }
+    }
+
+    fn parse_psf1(data: &'static [u8]) -> Result<PsfFont, Error> {
+        if data.len() < PSF1_HEADER_SIZE { return Err(Error::new(ErrorStatus::InvalidFileFormat)); }
+
+        let mode = data[2];
+        let height = data[3] as usize;
+        let glyph_count = if mode & PSF1_MODE_512 != 0 { 512 } else { 256 };
+
+        let mut font = PsfFont {
+            data,
+            glyph_offset: PSF1_HEADER_SIZE,
+            glyph_count,
+            bytes_per_glyph: height,
+            width: PSF1_GLYPH_WIDTH,
+            height,
+            unicode_map: Vec::new(),
+        };
+        let table_offset = font.glyphs_end()?;
+
+        if mode & (PSF1_MODE_HAS_TABLE | PSF1_MODE_HAS_SEQUENCES) != 0 {
+            font.read_psf1_table(&data[table_offset..]);
+        }
+
+        Ok(font)
+    }
+
+    fn parse_psf2(data: &'static [u8]) -> Result<PsfFont, Error> {
+        if data.len() < PSF2_HEADER_SIZE { return Err(Error::new(ErrorStatus::InvalidFileFormat)); }
+
+        let header_size = read_u32(data, 8) as usize;
+        let flags = read_u32(data, 12);
+        let glyph_count = read_u32(data, 16) as usize;
+        let bytes_per_glyph = read_u32(data, 20) as usize;
+        let height = read_u32(data, 24) as usize;
+        let width = read_u32(data, 28) as usize;
+
+        if width == 0 || height == 0 || bytes_per_glyph < width.div_ceil(8) * height {
+            return Err(Error::new(ErrorStatus::InvalidFileFormat));
+        }
+        // Glyphs can't overlap the header, and glyph_bitmap falls back to glyph 0 so there must be one
+        if header_size < PSF2_HEADER_SIZE || glyph_count == 0 {
+            return Err(Error::new(ErrorStatus::InvalidFileFormat));
+        }
+
+        let mut font = PsfFont {
+            data,
+            glyph_offset: header_size,
+            glyph_count,
+            bytes_per_glyph,
+            width,
+            height,
+            unicode_map: Vec::new(),
+        };
+        let table_offset = font.glyphs_end()?;
+
+        if flags & PSF2_FLAG_HAS_TABLE != 0 {
+            font.read_psf2_table(&data[table_offset..]);
+        }
+
+        Ok(font)
+    }
+
+    /// The offset of the end of the glyph data, checking that it fits in the file
+    fn glyphs_end(&self) -> Result<usize, Error> {
+        let end = self.glyph_count.checked_mul(self.bytes_per_glyph)
+            .and_then(|size| size.checked_add(self.glyph_offset))
+            .ok_or(Error::new(ErrorStatus::InvalidFileFormat))?;
+
+        if end > self.data.len() { return Err(Error::new(ErrorStatus::InvalidFileFormat)); }
+        Ok(end)
+    }
+
+    /// Read a table of UCS-2 values where each glyph's entries end with 0xFFFF
+    fn read_psf1_table(&mut self, table: &[u8]) {
+        let mut glyph = 0;
+        let mut in_sequence = false;
+
+        for value in table.chunks_exact(2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]])) {
+            if glyph >= self.glyph_count { break; }
+
+            match value {
+                PSF1_SEPARATOR => {
+                    glyph += 1;
+                    in_sequence = false;
+                },
+                // Multi character sequences can't be looked up by a single character
+                PSF1_START_SEQUENCE => in_sequence = true,
+                _ if in_sequence => {},
+                _ => {
+                    if let Some(character) = char::from_u32(value as u32) {
+                        self.unicode_map.push((character, glyph));
+                    }
+                },
+            }
+        }
+
+        self.sort_unicode_map();
+    }
+
+    /// Read a table of UTF-8 strings where each glyph's entries end with 0xFF
+    fn read_psf2_table(&mut self, table: &[u8]) {
+        for (glyph, entry) in table.split(|&byte| byte == PSF2_SEPARATOR).enumerate() {
+            if glyph >= self.glyph_count { break; }
+
+            // Anything after 0xFE is a multi character sequence
+            let characters = entry.split(|&byte| byte == PSF2_START_SEQUENCE).next().unwrap_or(&[]);
+            if let Ok(characters) = core::str::from_utf8(characters) {
+                for character in characters.chars() {
+                    self.unicode_map.push((character, glyph));
+                }
+            }
+        }
+
+        self.sort_unicode_map();
+    }
+
+    fn sort_unicode_map(&mut self) {
+        // A stable sort keeps glyphs for the same character in table order
+        self.unicode_map.sort_by_key(|&(character, _)| character);
+        // Keep the first glyph listed for a character
+        self.unicode_map.dedup_by_key(|&mut (character, _)| character);
+    }
+
+    /// Find the glyph for a character
+    ///
+    /// Fonts without a unicode table are assumed to be indexed by code point.
+    pub fn glyph_index(&self, character: char) -> Option<usize> {
+        if self.unicode_map.is_empty() {
+            let index = character as usize;
+            return if index < self.glyph_count { Some(index) } else { None };
+        }
+
+        self.unicode_map.binary_search_by_key(&character, |&(character, _)| character)
+            .ok()
+            .map(|position| self.unicode_map[position].1)
+    }
+
+    /// The bitmap for a glyph, one row after another with each row padded to a whole byte
+    pub fn glyph_bitmap(&self, index: usize) -> &[u8] {
+        let index = if index < self.glyph_count { index } else { 0 };
+        let start = self.glyph_offset + index * self.bytes_per_glyph;
+        &self.data[start..start + self.bytes_per_glyph]
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize { self.width }
+
+    #[inline]
+    pub fn height(&self) -> usize { self.height }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
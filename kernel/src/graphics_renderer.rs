@@ -1,9 +1,15 @@
 
 use core::ops;
 
-use bootinfo::BootInfo;
+use alloc::{vec, vec::Vec};
+use bootinfo::{BootInfo, PixelBitmask, PixelFormat};
 
-use crate::errors::Error;
+use crate::errors::{Error, ErrorStatus};
+
+pub use self::{canvas::Canvas, surface::Surface};
+
+mod canvas;
+mod surface;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Color(pub u8, pub u8, pub u8);
@@ -26,6 +32,20 @@ impl Color {
     #[inline]
     pub fn blue(&self) -> u8 { self.2 }
 
+    /// Mix this color over a background, where alpha 255 is fully this color
+    pub fn blend(&self, background: Color, alpha: u8) -> Color {
+        let mix = |foreground: u8, background: u8| {
+            let alpha = alpha as u16;
+            ((foreground as u16 * alpha + background as u16 * (255 - alpha) + 127) / 255) as u8
+        };
+
+        Color(
+            mix(self.red(), background.red()),
+            mix(self.green(), background.green()),
+            mix(self.blue(), background.blue()),
+        )
+    }
+
     #[inline]
     pub fn hex_code(&self) -> u32 { (self.red() as u32) << 16 | (self.green() as u32) << 8 | (self.blue() as u32) }
 }
@@ -54,34 +74,123 @@ impl ops::Mul<u8> for Color {
     }
 }
 
+/// A pixel already converted to the frame buffer's layout
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(transparent)]
-struct BgrPixel(u32);
+struct Pixel(u32);
+
+/// Where a color channel sits in a pixel and how many bits it has
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ChannelMask {
+    shift: u32,
+    bits: u32,
+}
+
+impl ChannelMask {
+    const fn from_mask(mask: u32) -> ChannelMask {
+        if mask == 0 { return ChannelMask { shift: 0, bits: 0 }; }
+
+        let shift = mask.trailing_zeros();
+        ChannelMask { shift, bits: (mask >> shift).count_ones() }
+    }
+
+    /// Scale an 8 bit channel value to the width of the mask and move it into place
+    fn encode(&self, value: u8) -> u32 {
+        let value = value as u32;
+        let scaled = if self.bits >= 8 { value << (self.bits - 8) } else { value >> (8 - self.bits) };
+        scaled << self.shift
+    }
+
+    /// Pull the channel out of a pixel and scale it back to 8 bits
+    fn decode(&self, pixel: u32) -> u8 {
+        if self.bits == 0 { return 0; }
 
-impl BgrPixel {
-    fn new(color: Color) -> BgrPixel {
-        BgrPixel((color.red() as u32) << 16 | (color.green() as u32) << 8 | color.blue() as u32)
+        let value = (pixel >> self.shift) & (u32::MAX >> (32 - self.bits));
+        let scaled = if self.bits >= 8 { value >> (self.bits - 8) } else { value << (8 - self.bits) };
+        scaled as u8
+    }
+}
+
+/// Converts colors to the pixel format reported by the bootloader
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PixelEncoder {
+    red: ChannelMask,
+    green: ChannelMask,
+    blue: ChannelMask,
+}
+
+impl PixelEncoder {
+    /// Returns None when the format has no linear frame buffer to draw to
+    fn new(pixel_format: PixelFormat, bitmask: PixelBitmask) -> Option<PixelEncoder> {
+        let (red_mask, green_mask, blue_mask) = match pixel_format {
+            PixelFormat::Rgb => (0x0000FF, 0x00FF00, 0xFF0000),
+            PixelFormat::Bgr => (0xFF0000, 0x00FF00, 0x0000FF),
+            PixelFormat::Bitmask => (bitmask.red_mask, bitmask.green_mask, bitmask.blue_mask),
+            PixelFormat::BltOnly => return None,
+        };
+
+        if red_mask | green_mask | blue_mask == 0 { return None; }
+
+        Some(PixelEncoder {
+            red: ChannelMask::from_mask(red_mask),
+            green: ChannelMask::from_mask(green_mask),
+            blue: ChannelMask::from_mask(blue_mask),
+        })
+    }
+
+    fn encode(&self, color: Color) -> Pixel {
+        Pixel(self.red.encode(color.red()) | self.green.encode(color.green()) | self.blue.encode(color.blue()))
+    }
+
+    fn decode(&self, pixel: Pixel) -> Color {
+        Color(self.red.decode(pixel.0), self.green.decode(pixel.0), self.blue.decode(pixel.0))
+    }
+}
+
+/// The part of the back buffer that has changed since the last flush
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DirtyRect {
+    x_start: usize,
+    y_start: usize,
+    x_end: usize,
+    y_end: usize,
+}
+
+impl DirtyRect {
+    fn include(&mut self, other: DirtyRect) {
+        self.x_start = self.x_start.min(other.x_start);
+        self.y_start = self.y_start.min(other.y_start);
+        self.x_end = self.x_end.max(other.x_end);
+        self.y_end = self.y_end.max(other.y_end);
     }
 }
 
 pub struct FrameBuffer {
     pub width: usize,
     pub height: usize,
-    pixels: &'static mut [BgrPixel],
+    pixels: &'static mut [Pixel],
     stride: usize,
+    encoder: PixelEncoder,
+    /// When present, drawing goes here and is copied to the screen by flush
+    back_buffer: Option<Vec<Pixel>>,
+    dirty: Option<DirtyRect>,
 }
 
 impl FrameBuffer {
 
+    /// Fails if the firmware did not give a frame buffer that can be drawn to directly
     pub fn from_boot_data(bootinfo: &BootInfo) -> Result<FrameBuffer, Error> {
+        let encoder = PixelEncoder::new(bootinfo.framebuffer.pixel_format, bootinfo.framebuffer.pixel_bitmask)
+            .ok_or(Error::new(ErrorStatus::UnsupportedPixelFormat))?;
+
         let frame_buffer_size = bootinfo.framebuffer.buffer_size;
-        let pixel_count = frame_buffer_size / core::mem::size_of::<BgrPixel>();
+        let pixel_count = frame_buffer_size / core::mem::size_of::<Pixel>();
 
         let buffer_address = bootinfo.framebuffer.base_address.get_virtual_address_at_offset(bootinfo.page_table_memory_offset);
 
-        let pixels: &mut [BgrPixel] = unsafe {
+        let pixels: &mut [Pixel] = unsafe {
             core::slice::from_raw_parts_mut(
-                buffer_address.as_u64() as *mut BgrPixel, 
+                buffer_address.as_u64() as *mut Pixel, 
                 pixel_count
             )
         };
@@ -92,24 +201,108 @@ impl FrameBuffer {
                 width: bootinfo.framebuffer.width as usize,
                 height: bootinfo.framebuffer.height as usize,
                 stride: bootinfo.framebuffer.pixels_per_scan_line as usize,
+                encoder,
+                back_buffer: None,
+                dirty: None,
             }
         )
     }
 
-    pub fn fill(&mut self, color: Color) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                self.set_pixel(x, y, color);
-            }
+    /// Draw into a copy of the screen in the kernel heap instead of straight to video memory
+    ///
+    /// Nothing drawn shows up on screen until flush is called. Needs the kernel heap.
+    pub fn enable_back_buffer(&mut self) {
+        if self.back_buffer.is_some() { return; }
+
+        let mut back_buffer = vec![Pixel(0); self.width * self.height];
+        for row in 0..self.height {
+            let screen_row = &self.pixels[row * self.stride..row * self.stride + self.width];
+            back_buffer[row * self.width..(row + 1) * self.width].copy_from_slice(screen_row);
         }
+
+        self.back_buffer = Some(back_buffer);
     }
 
-    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
-        let x = if x >= self.width { self.width - 1} else {x};
-        let y = if y >= self.width { self.height - 1} else {y};
-        let pixel_index = y * self.stride + x;
-        self.pixels[pixel_index] = BgrPixel::new(color);
+    /// Copy the parts of the back buffer that have changed to the screen
+    pub fn flush(&mut self) {
+        let (Some(back_buffer), Some(dirty)) = (&self.back_buffer, self.dirty.take()) else { return; };
+
+        for row in dirty.y_start..dirty.y_end {
+            let source = row * self.width;
+            let destination = row * self.stride;
+            self.pixels[destination + dirty.x_start..destination + dirty.x_end]
+                .copy_from_slice(&back_buffer[source + dirty.x_start..source + dirty.x_end]);
+        }
+    }
+
+    /// Move the contents of the screen up by a number of pixel rows and fill the exposed rows
+    pub fn scroll_up(&mut self, rows: usize, fill: Color) {
+        let rows = rows.min(self.height);
+        let (width, height) = (self.width, self.height);
+        self.mark_dirty(DirtyRect { x_start: 0, y_start: 0, x_end: width, y_end: height });
+
+        let (pixels, stride) = self.draw_target();
+        for row in 0..height - rows {
+            let source = (row + rows) * stride;
+            pixels.copy_within(source..source + width, row * stride);
+        }
+
+        self.fill_rect(0, height - rows, width, rows, fill);
+    }
+
+    /// The pixels that drawing should go to and the distance between their rows
+    fn draw_target(&mut self) -> (&mut [Pixel], usize) {
+        match &mut self.back_buffer {
+            Some(back_buffer) => (back_buffer, self.width),
+            None => (&mut *self.pixels, self.stride),
+        }
+    }
+
+    fn mark_dirty(&mut self, rect: DirtyRect) {
+        if self.back_buffer.is_none() { return; }
+
+        match &mut self.dirty {
+            Some(dirty) => dirty.include(rect),
+            None => self.dirty = Some(rect),
+        }
     }
 
     pub fn get_resolution(&self) -> (usize, usize) { (self.width, self.height) }
+}
+
+impl Canvas for FrameBuffer {
+    fn size(&self) -> (usize, usize) { (self.width, self.height) }
+
+    fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        if x >= self.width || y >= self.height { return; }
+        self.mark_dirty(DirtyRect { x_start: x, y_start: y, x_end: x + 1, y_end: y + 1 });
+
+        let pixel = self.encoder.encode(color);
+        let (pixels, stride) = self.draw_target();
+        pixels[y * stride + x] = pixel;
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<Color> {
+        if x >= self.width || y >= self.height { return None; }
+
+        let pixel = match &self.back_buffer {
+            Some(back_buffer) => back_buffer[y * self.width + x],
+            None => self.pixels[y * self.stride + x],
+        };
+        Some(self.encoder.decode(pixel))
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color) {
+        let pixel = self.encoder.encode(color);
+        let x_end = (x + width).min(self.width);
+        let y_end = (y + height).min(self.height);
+        if x >= x_end || y >= y_end { return; }
+        self.mark_dirty(DirtyRect { x_start: x, y_start: y, x_end, y_end });
+
+        let (pixels, stride) = self.draw_target();
+        for row in y..y_end {
+            let row_start = row * stride;
+            pixels[row_start + x..row_start + x_end].fill(pixel);
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,151 @@
+use super::{Color, Surface};
+
+/// Something that can be drawn on, either the screen or an off-screen Surface
+///
+/// Positions are signed so that shapes can hang off the edges. Anything outside of the canvas is clipped.
+pub trait Canvas {
+    fn size(&self) -> (usize, usize);
+
+    /// Set a single pixel. Pixels outside of the canvas are ignored
+    fn set_pixel(&mut self, x: usize, y: usize, color: Color);
+
+    /// Read back a pixel or None if it is outside of the canvas
+    fn get_pixel(&self, x: usize, y: usize) -> Option<Color>;
+
+    /// Fill a rectangle, clipped to the canvas
+    fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color);
+
+    fn fill(&mut self, color: Color) {
+        let (width, height) = self.size();
+        self.fill_rect(0, 0, width, height, color);
+    }
+
+    /// Set a pixel that may be off the top or left of the canvas
+    fn plot(&mut self, x: isize, y: isize, color: Color) {
+        if x < 0 || y < 0 { return; }
+        self.set_pixel(x as usize, y as usize, color);
+    }
+
+    /// Fill a rectangle that may start off the top or left of the canvas
+    fn fill_rect_clipped(&mut self, x: isize, y: isize, width: usize, height: usize, color: Color) {
+        let width = width.saturating_sub(x.min(0).unsigned_abs());
+        let height = height.saturating_sub(y.min(0).unsigned_abs());
+        self.fill_rect(x.max(0) as usize, y.max(0) as usize, width, height, color);
+    }
+
+    /// Draw a one pixel wide line between two points using Bresenham's algorithm
+    fn draw_line(&mut self, start: (isize, isize), end: (isize, isize), color: Color) {
+        let (width, height) = self.size();
+        let (width, height) = (width as isize, height as isize);
+
+        // Skip lines that are entirely off one side of the canvas
+        if (start.0 < 0 && end.0 < 0) || (start.1 < 0 && end.1 < 0)
+            || (start.0 >= width && end.0 >= width) || (start.1 >= height && end.1 >= height) {
+            return;
+        }
+
+        let (mut x, mut y) = start;
+        let dx = (end.0 - x).abs();
+        let dy = -(end.1 - y).abs();
+        let step_x = if x < end.0 { 1 } else { -1 };
+        let step_y = if y < end.1 { 1 } else { -1 };
+        let mut error = dx + dy;
+
+        loop {
+            self.plot(x, y, color);
+            if (x, y) == end { break; }
+
+            let doubled_error = 2 * error;
+            if doubled_error >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled_error <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Draw the outline of a rectangle
+    fn draw_rect(&mut self, x: isize, y: isize, width: usize, height: usize, color: Color) {
+        if width == 0 || height == 0 { return; }
+
+        let right = x + width as isize - 1;
+        let bottom = y + height as isize - 1;
+        self.fill_rect_clipped(x, y, width, 1, color);
+        self.fill_rect_clipped(x, bottom, width, 1, color);
+        self.fill_rect_clipped(x, y, 1, height, color);
+        self.fill_rect_clipped(right, y, 1, height, color);
+    }
+
+    /// Draw the outline of a circle using the midpoint algorithm
+    fn draw_circle(&mut self, center_x: isize, center_y: isize, radius: usize, color: Color) {
+        let mut x = radius as isize;
+        let mut y = 0;
+        let mut error = 1 - x;
+
+        while x >= y {
+            for (offset_x, offset_y) in [(x, y), (y, x), (-y, x), (-x, y), (-x, -y), (-y, -x), (y, -x), (x, -y)] {
+                self.plot(center_x + offset_x, center_y + offset_y, color);
+            }
+
+            y += 1;
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    /// Draw a filled circle as horizontal spans found with the midpoint algorithm
+    fn fill_circle(&mut self, center_x: isize, center_y: isize, radius: usize, color: Color) {
+        let mut x = radius as isize;
+        let mut y = 0;
+        let mut error = 1 - x;
+
+        while x >= y {
+            for (half_width, offset_y) in [(x, y), (x, -y), (y, x), (y, -x)] {
+                self.fill_rect_clipped(center_x - half_width, center_y + offset_y, 2 * half_width as usize + 1, 1, color);
+            }
+
+            y += 1;
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    /// Draw a surface with its top left corner at a position, blending it by its alpha channel
+    fn blit(&mut self, source: &Surface, x: isize, y: isize) {
+        let (width, height) = self.size();
+
+        for source_y in 0..source.height() {
+            let target_y = y + source_y as isize;
+            if target_y < 0 { continue; }
+            if target_y as usize >= height { break; }
+
+            for source_x in 0..source.width() {
+                let target_x = x + source_x as isize;
+                if target_x < 0 { continue; }
+                if target_x as usize >= width { break; }
+
+                let (color, alpha) = source.get_pixel_with_alpha(source_x, source_y);
+                let (target_x, target_y) = (target_x as usize, target_y as usize);
+                match alpha {
+                    0 => {},
+                    255 => self.set_pixel(target_x, target_y, color),
+                    alpha => {
+                        let background = self.get_pixel(target_x, target_y).unwrap_or(color);
+                        self.set_pixel(target_x, target_y, color.blend(background, alpha));
+                    },
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use alloc::{vec, vec::Vec};
+
+use super::{Canvas, Color};
+
+/// An off-screen image in the kernel heap with an alpha channel, drawn onto other canvases with blit
+pub struct Surface {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    alpha: Vec<u8>,
+}
+
+impl Surface {
+    /// Create a fully transparent surface
+    pub fn new(width: usize, height: usize) -> Surface {
+        Surface {
+            width,
+            height,
+            pixels: vec![Color::new(0x000000); width * height],
+            alpha: vec![0; width * height],
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize { self.width }
+
+    #[inline]
+    pub fn height(&self) -> usize { self.height }
+
+    /// Set a pixel along with how opaque it is. Pixels outside of the surface are ignored
+    pub fn set_pixel_with_alpha(&mut self, x: usize, y: usize, color: Color, alpha: u8) {
+        if x >= self.width || y >= self.height { return; }
+
+        let index = y * self.width + x;
+        self.pixels[index] = color;
+        self.alpha[index] = alpha;
+    }
+
+    /// Get a pixel along with how opaque it is
+    ///
+    /// ## Panics
+    /// If the position is outside of the surface
+    pub fn get_pixel_with_alpha(&self, x: usize, y: usize) -> (Color, u8) {
+        let index = y * self.width + x;
+        (self.pixels[index], self.alpha[index])
+    }
+}
+
+impl Canvas for Surface {
+    fn size(&self) -> (usize, usize) { (self.width, self.height) }
+
+    fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        self.set_pixel_with_alpha(x, y, color, u8::MAX);
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> Option<Color> {
+        if x >= self.width || y >= self.height { return None; }
+        Some(self.pixels[y * self.width + x])
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color) {
+        let x_end = (x + width).min(self.width);
+        let y_end = (y + height).min(self.height);
+        if x >= x_end || y >= y_end { return; }
+
+        for row in y..y_end {
+            let row_start = row * self.width;
+            self.pixels[row_start + x..row_start + x_end].fill(color);
+            self.alpha[row_start + x..row_start + x_end].fill(u8::MAX);
+        }
+    }
+}
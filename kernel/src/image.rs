@@ -0,0 +1,30 @@
+use crate::{errors::{Error, ErrorStatus}, graphics_renderer::Surface};
+
+mod bmp;
+mod tga;
+
+/// Decode an uncompressed BMP or TGA image
+///
+/// BMP files are recognised by their signature. TGA has none so anything else is tried as TGA.
+pub fn decode(data: &[u8]) -> Result<Surface, Error> {
+    if data.starts_with(&bmp::SIGNATURE) {
+        bmp::decode(data)
+    } else {
+        tga::decode(data)
+    }
+}
+
+/// Read bytes from an image, failing if the file is too short
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], Error> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(Error::new(ErrorStatus::InvalidFileFormat))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, Error> {
+    Ok(u16::from_le_bytes(read_bytes(data, offset)?))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    Ok(u32::from_le_bytes(read_bytes(data, offset)?))
+}
@@ -0,0 +1,92 @@
+use crate::{errors::{Error, ErrorStatus}, graphics_renderer::{Color, Surface}};
+
+use super::{read_bytes, read_u16, read_u32};
+
+pub const SIGNATURE: [u8; 2] = *b"BM";
+
+const COMPRESSION_NONE: u32 = 0;
+const COMPRESSION_BITFIELDS: u32 = 3;
+/// Where the channel masks are for BITFIELDS images, either in the header or straight after it
+const MASKS_OFFSET: usize = 54;
+/// Headers at least this big include an alpha mask after the color masks
+const ALPHA_MASK_HEADER_SIZE: u32 = 56;
+
+/// Masks for each channel of a pixel. A zero alpha mask means the image is opaque
+struct ChannelMasks {
+    red: u32,
+    green: u32,
+    blue: u32,
+    alpha: u32,
+}
+
+/// Decode an uncompressed 24 or 32 bit BMP
+pub fn decode(data: &[u8]) -> Result<Surface, Error> {
+    let pixel_offset = read_u32(data, 10)? as usize;
+    let header_size = read_u32(data, 14)?;
+    let width = read_u32(data, 18)? as i32;
+    let height = read_u32(data, 22)? as i32;
+    let bits_per_pixel = read_u16(data, 28)?;
+    let compression = read_u32(data, 30)?;
+
+    if width <= 0 || height == 0 { return Err(Error::new(ErrorStatus::InvalidFileFormat)); }
+
+    // Rows are stored bottom up unless the height is negative
+    let top_down = height < 0;
+    let (width, height) = (width as usize, height.unsigned_abs() as usize);
+
+    let masks = match (compression, bits_per_pixel) {
+        (COMPRESSION_NONE, 24 | 32) => ChannelMasks { red: 0xFF0000, green: 0x00FF00, blue: 0x0000FF, alpha: 0 },
+        (COMPRESSION_BITFIELDS, 32) => ChannelMasks {
+            red: read_u32(data, MASKS_OFFSET)?,
+            green: read_u32(data, MASKS_OFFSET + 4)?,
+            blue: read_u32(data, MASKS_OFFSET + 8)?,
+            alpha: if header_size >= ALPHA_MASK_HEADER_SIZE { read_u32(data, MASKS_OFFSET + 12)? } else { 0 },
+        },
+        _ => return Err(Error::new(ErrorStatus::InvalidFileFormat)),
+    };
+
+    // Each row is padded to a multiple of 4 bytes
+    let bytes_per_pixel = bits_per_pixel as usize / 8;
+    let row_size = (width * bytes_per_pixel).div_ceil(4) * 4;
+    let pixels_end = row_size.checked_mul(height).and_then(|size| size.checked_add(pixel_offset));
+    if pixels_end.is_none_or(|end| end > data.len()) {
+        return Err(Error::new(ErrorStatus::InvalidFileFormat));
+    }
+
+    let mut surface = Surface::new(width, height);
+    for row in 0..height {
+        let y = if top_down { row } else { height - 1 - row };
+        let row_start = pixel_offset + row * row_size;
+
+        for x in 0..width {
+            let offset = row_start + x * bytes_per_pixel;
+            let pixel = match bytes_per_pixel {
+                3 => {
+                    let [blue, green, red] = read_bytes(data, offset)?;
+                    u32::from_le_bytes([blue, green, red, 0])
+                },
+                _ => read_u32(data, offset)?,
+            };
+
+            let color = Color(
+                read_channel(pixel, masks.red),
+                read_channel(pixel, masks.green),
+                read_channel(pixel, masks.blue),
+            );
+            let alpha = if masks.alpha == 0 { u8::MAX } else { read_channel(pixel, masks.alpha) };
+            surface.set_pixel_with_alpha(x, y, color, alpha);
+        }
+    }
+
+    Ok(surface)
+}
+
+/// Pull a channel out of a pixel and scale it to 8 bits
+fn read_channel(pixel: u32, mask: u32) -> u8 {
+    if mask == 0 { return 0; }
+
+    let shift = mask.trailing_zeros();
+    let bits = (mask >> shift).count_ones();
+    let value = (pixel & mask) >> shift;
+    if bits >= 8 { (value >> (bits - 8)) as u8 } else { (value << (8 - bits)) as u8 }
+}
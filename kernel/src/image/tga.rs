@@ -0,0 +1,67 @@
+use crate::{errors::{Error, ErrorStatus}, graphics_renderer::{Color, Surface}};
+
+use super::{read_bytes, read_u16};
+
+const HEADER_SIZE: usize = 18;
+const IMAGE_TYPE_TRUE_COLOR: u8 = 2;
+const IMAGE_TYPE_GRAYSCALE: u8 = 3;
+const DESCRIPTOR_ALPHA_BITS: u8 = 0x0F;
+const DESCRIPTOR_RIGHT_TO_LEFT: u8 = 0x10;
+const DESCRIPTOR_TOP_TO_BOTTOM: u8 = 0x20;
+
+/// Decode an uncompressed 24 or 32 bit true color or 8 bit grayscale TGA
+pub fn decode(data: &[u8]) -> Result<Surface, Error> {
+    let [id_length, color_map_type, image_type] = read_bytes(data, 0)?;
+    let color_map_length = read_u16(data, 5)? as usize;
+    let [color_map_entry_bits] = read_bytes(data, 7)?;
+    let width = read_u16(data, 12)? as usize;
+    let height = read_u16(data, 14)? as usize;
+    let [bits_per_pixel, descriptor] = read_bytes(data, 16)?;
+
+    let bytes_per_pixel = match (image_type, bits_per_pixel) {
+        (IMAGE_TYPE_TRUE_COLOR, 24) => 3,
+        (IMAGE_TYPE_TRUE_COLOR, 32) => 4,
+        (IMAGE_TYPE_GRAYSCALE, 8) => 1,
+        _ => return Err(Error::new(ErrorStatus::InvalidFileFormat)),
+    };
+    if color_map_type > 1 || width == 0 || height == 0 {
+        return Err(Error::new(ErrorStatus::InvalidFileFormat));
+    }
+
+    // The color map is unused by these image types but still has to be skipped
+    let color_map_size = if color_map_type == 1 { color_map_length * (color_map_entry_bits as usize).div_ceil(8) } else { 0 };
+    let pixel_offset = HEADER_SIZE + id_length as usize + color_map_size;
+    if pixel_offset + width * height * bytes_per_pixel > data.len() {
+        return Err(Error::new(ErrorStatus::InvalidFileFormat));
+    }
+
+    let has_alpha = descriptor & DESCRIPTOR_ALPHA_BITS != 0;
+    let mut surface = Surface::new(width, height);
+    for row in 0..height {
+        // Rows are stored bottom up unless the descriptor says otherwise
+        let y = if descriptor & DESCRIPTOR_TOP_TO_BOTTOM != 0 { row } else { height - 1 - row };
+
+        for column in 0..width {
+            let x = if descriptor & DESCRIPTOR_RIGHT_TO_LEFT != 0 { width - 1 - column } else { column };
+            let offset = pixel_offset + (row * width + column) * bytes_per_pixel;
+
+            let (color, alpha) = match bytes_per_pixel {
+                1 => {
+                    let [level] = read_bytes(data, offset)?;
+                    (Color(level, level, level), u8::MAX)
+                },
+                3 => {
+                    let [blue, green, red] = read_bytes(data, offset)?;
+                    (Color(red, green, blue), u8::MAX)
+                },
+                _ => {
+                    let [blue, green, red, alpha] = read_bytes(data, offset)?;
+                    (Color(red, green, blue), if has_alpha { alpha } else { u8::MAX })
+                },
+            };
+            surface.set_pixel_with_alpha(x, y, color, alpha);
+        }
+    }
+
+    Ok(surface)
+}
@@ -0,0 +1,51 @@
+use bootinfo::BootInfo;
+use spin::Mutex;
+
+use crate::{log_error, log_info, log_warn};
+
+pub use self::ustar::{Entry, EntryKind};
+
+use self::ustar::Archive;
+
+mod ustar;
+
+/// The boot module holding the initial ramdisk
+const INITRD_MODULE: &str = "initrd";
+
+/// The read-only filesystem loaded by the bootloader, or None if there isn't one
+static INITRD: Mutex<Option<Archive>> = Mutex::new(None);
+
+/// Mount the USTAR archive passed by the bootloader as the initial ramdisk
+pub fn initialize(bootinfo: &BootInfo) {
+    let Some(module) = bootinfo.modules.find(INITRD_MODULE) else {
+        log_warn!("Initrd", "The bootloader did not load an initial ramdisk");
+        return;
+    };
+
+    // Safety: Boot modules are mapped into kernel space by the bootloader and never freed
+    let data = unsafe { module.data() };
+    match Archive::new(data) {
+        Ok(archive) => {
+            log_info!("Initrd", "Mounted the initial ramdisk with {} entries", archive.entries().count());
+            *INITRD.lock() = Some(archive);
+        },
+        Err(error) => log_error!("Initrd", "The initial ramdisk is not a valid USTAR archive: {error:?}"),
+    }
+}
+
+/// Find a file or directory by its path, which may start with "/"
+pub fn find(path: &str) -> Option<Entry> {
+    let archive = (*INITRD.lock())?;
+    archive.find(path.trim_matches('/'))
+}
+
+/// Call a function for every file and directory directly inside a directory
+///
+/// Returns false if there is no initial ramdisk.
+pub fn for_each_in_directory(path: &str, mut callback: impl FnMut(Entry)) -> bool {
+    let Some(archive) = *INITRD.lock() else { return false; };
+
+    let path = path.trim_matches('/');
+    archive.entries().filter(|entry| entry.is_in_directory(path)).for_each(&mut callback);
+    true
+}
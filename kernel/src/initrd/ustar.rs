@@ -0,0 +1,175 @@
+use crate::errors::{Error, ErrorStatus};
+
+/// Archives are made of 512 byte blocks. Each file is a header block followed by its data blocks
+const BLOCK_SIZE: usize = 512;
+
+const NAME_OFFSET: usize = 0;
+const NAME_LENGTH: usize = 100;
+const SIZE_OFFSET: usize = 124;
+const SIZE_LENGTH: usize = 12;
+const CHECKSUM_OFFSET: usize = 148;
+const CHECKSUM_LENGTH: usize = 8;
+const TYPE_OFFSET: usize = 156;
+const MAGIC_OFFSET: usize = 257;
+const PREFIX_OFFSET: usize = 345;
+const PREFIX_LENGTH: usize = 155;
+
+/// POSIX archives use "ustar\0" and GNU tar uses "ustar " followed by a space
+const MAGIC: &[u8] = b"ustar";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Directory,
+    /// Links, devices and other entries that can't be read as files
+    Other,
+}
+
+/// A file or directory in an archive
+///
+/// Long paths are split between a prefix and a name in the header. Both are kept without a leading
+/// "./" or "/" or a trailing "/".
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    prefix: &'static str,
+    name: &'static str,
+    pub kind: EntryKind,
+    pub data: &'static [u8],
+}
+
+impl Entry {
+    /// The last part of the path
+    pub fn file_name(&self) -> &'static str {
+        self.name.rsplit('/').next().unwrap_or(self.name)
+    }
+
+    /// Whether this entry is at a path, given without a leading or trailing "/"
+    pub fn has_path(&self, path: &str) -> bool {
+        join_path(self.prefix, self.name).eq(path.bytes())
+    }
+
+    /// Whether this entry is directly inside a directory, where "" is the root of the archive
+    pub fn is_in_directory(&self, directory: &str) -> bool {
+        let parent_name = self.name.rsplit_once('/').map_or("", |(parent, _)| parent);
+        join_path(self.prefix, parent_name).eq(directory.bytes())
+    }
+
+    /// The root directory is often stored as "./"
+    fn is_root(&self) -> bool {
+        self.prefix.is_empty() && self.name.is_empty()
+    }
+}
+
+/// A USTAR archive held in memory
+///
+/// GNU long name entries are not understood, so archives should be made with `tar --format=ustar`.
+#[derive(Clone, Copy)]
+pub struct Archive {
+    data: &'static [u8],
+}
+
+impl Archive {
+    /// Check that every header in the archive is valid
+    pub fn new(data: &'static [u8]) -> Result<Archive, Error> {
+        let archive = Archive { data };
+        let mut offset = 0;
+        while let Some((_, next_offset)) = read_entry(data, offset)? {
+            offset = next_offset;
+        }
+
+        Ok(archive)
+    }
+
+    /// Every entry in the archive apart from the root directory
+    pub fn entries(&self) -> impl Iterator<Item = Entry> {
+        Entries { data: self.data, offset: 0 }.filter(|entry| !entry.is_root())
+    }
+
+    pub fn find(&self, path: &str) -> Option<Entry> {
+        self.entries().find(|entry| entry.has_path(path))
+    }
+}
+
+/// Iterator over the entries of an archive
+struct Entries {
+    data: &'static [u8],
+    offset: usize,
+}
+
+impl Iterator for Entries {
+    type Item = Entry;
+
+    fn next(&mut self) -> Option<Entry> {
+        // The archive was checked when it was created so there are no errors here
+        let (entry, next_offset) = read_entry(self.data, self.offset).ok()??;
+        self.offset = next_offset;
+        Some(entry)
+    }
+}
+
+/// Read the entry with its header at an offset, returning it and the offset of the next header
+///
+/// Returns None at the end of the archive, which is marked by an empty block or the end of the data.
+fn read_entry(data: &'static [u8], offset: usize) -> Result<Option<(Entry, usize)>, Error> {
+    let Some(header) = data.get(offset..offset + BLOCK_SIZE) else { return Ok(None); };
+    if header.iter().all(|&byte| byte == 0) { return Ok(None); }
+
+    if !header[MAGIC_OFFSET..].starts_with(MAGIC) || !has_valid_checksum(header) {
+        return Err(Error::new(ErrorStatus::InvalidFileFormat));
+    }
+
+    let size = parse_octal(&header[SIZE_OFFSET..SIZE_OFFSET + SIZE_LENGTH])?;
+    let data_start = offset + BLOCK_SIZE;
+    let entry_data = data.get(data_start..data_start + size).ok_or(Error::new(ErrorStatus::InvalidFileFormat))?;
+
+    let kind = match header[TYPE_OFFSET] {
+        b'0' | b'\0' | b'7' => EntryKind::File,
+        b'5' => EntryKind::Directory,
+        _ => EntryKind::Other,
+    };
+
+    let prefix = trim_path(read_string(&header[PREFIX_OFFSET..PREFIX_OFFSET + PREFIX_LENGTH])?);
+    let name = trim_path(read_string(&header[NAME_OFFSET..NAME_OFFSET + NAME_LENGTH])?);
+    let entry = Entry { prefix, name, kind, data: entry_data };
+    let next_offset = data_start + size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+    Ok(Some((entry, next_offset)))
+}
+
+/// The checksum is the sum of the header bytes with the checksum field counted as spaces
+fn has_valid_checksum(header: &[u8]) -> bool {
+    let Ok(checksum) = parse_octal(&header[CHECKSUM_OFFSET..CHECKSUM_OFFSET + CHECKSUM_LENGTH]) else { return false; };
+
+    let sum: usize = header.iter().enumerate()
+        .map(|(index, &byte)| {
+            if (CHECKSUM_OFFSET..CHECKSUM_OFFSET + CHECKSUM_LENGTH).contains(&index) { b' ' as usize } else { byte as usize }
+        })
+        .sum();
+
+    sum == checksum
+}
+
+/// Numbers are stored as octal text ending in a NULL or space
+fn parse_octal(field: &[u8]) -> Result<usize, Error> {
+    let text = read_string(field)?.trim_matches(|c| c == ' ' || c == '\0');
+    if text.is_empty() { return Ok(0); }
+
+    usize::from_str_radix(text, 8).map_err(|_| Error::new(ErrorStatus::InvalidFileFormat))
+}
+
+/// Read a NULL padded text field
+fn read_string(field: &[u8]) -> Result<&str, Error> {
+    let length = field.iter().position(|&byte| byte == 0).unwrap_or(field.len());
+    core::str::from_utf8(&field[..length]).map_err(|_| Error::new(ErrorStatus::InvalidFileFormat))
+}
+
+/// The bytes of a path split into two parts, joined by a "/" if both are present
+fn join_path(first: &'static str, second: &'static str) -> impl Iterator<Item = u8> {
+    let separator: &[u8] = if first.is_empty() || second.is_empty() { b"" } else { b"/" };
+    first.bytes().chain(separator.iter().copied()).chain(second.bytes())
+}
+
+/// Drop the leading "./" or "/" and trailing "/" from a path, so archives made with `tar -C dir .` work
+fn trim_path(path: &'static str) -> &'static str {
+    let path = path.strip_prefix('.').filter(|rest| rest.is_empty() || rest.starts_with('/')).unwrap_or(path);
+    path.trim_matches('/')
+}
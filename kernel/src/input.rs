@@ -0,0 +1,219 @@
+use alloc::collections::VecDeque;
+
+use spin::Mutex;
+use x86_64_hardware::devices::ps2_controller::{Ps2Controller, Ps2Error, Ps2Port, PS2_CONTROLLER};
+
+use crate::{acpi, log_info, log_warn};
+
+use self::{keyboard::{KeyCode, KeyEvent, KeyState, Keyboard, Modifiers, ScancodeSet}, keymap::US_QWERTY, mouse::{Mouse, MouseEvent, MouseType}};
+
+pub mod keyboard;
+pub mod keymap;
+pub mod mouse;
+
+const EVENT_QUEUE_CAPACITY: usize = 256;
+
+const KEYBOARD_SET_LEDS: u8 = 0xED;
+const KEYBOARD_SCANCODE_SET: u8 = 0xF0;
+const KEYBOARD_ENABLE_SCANNING: u8 = 0xF4;
+const SCANCODE_SET_QUERY: u8 = 0x00;
+/// How many times to ask for scancode set 2 while the keyboard reports set 3
+const SCANCODE_SET_ATTEMPTS: usize = 3;
+
+const LED_SCROLL_LOCK: u8 = 1 << 0;
+const LED_NUM_LOCK: u8 = 1 << 1;
+const LED_CAPS_LOCK: u8 = 1 << 2;
+
+const MOUSE_GET_DEVICE_ID: u8 = 0xF2;
+const MOUSE_SET_SAMPLE_RATE: u8 = 0xF3;
+const MOUSE_ENABLE_REPORTING: u8 = 0xF4;
+const MOUSE_SET_DEFAULTS: u8 = 0xF6;
+const MOUSE_ID_INTELLIMOUSE: u8 = 0x03;
+/// Setting these sample rates in order enables the scroll wheel on an IntelliMouse
+const INTELLIMOUSE_SAMPLE_RATE_SEQUENCE: [u8; 3] = [200, 100, 80];
+const MOUSE_SAMPLE_RATE: u8 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    Keyboard(KeyEvent),
+    Mouse(MouseEvent),
+}
+
+/// A fixed size FIFO of input events
+///
+/// When the queue is full new events are dropped so that earlier input is not lost.
+pub struct EventQueue {
+    events: [Option<InputEvent>; EVENT_QUEUE_CAPACITY],
+    head: usize,
+    len: usize,
+}
+
+impl EventQueue {
+    pub const fn new() -> EventQueue {
+        EventQueue { events: [None; EVENT_QUEUE_CAPACITY], head: 0, len: 0 }
+    }
+
+    /// Add an event to the back of the queue
+    ///
+    /// Returns false if the queue was full and the event was dropped
+    pub fn push(&mut self, event: InputEvent) -> bool {
+        if self.len == EVENT_QUEUE_CAPACITY { return false; }
+
+        let index = (self.head + self.len) % EVENT_QUEUE_CAPACITY;
+        self.events[index] = Some(event);
+        self.len += 1;
+        true
+    }
+
+    /// Remove the event at the front of the queue
+    pub fn pop(&mut self) -> Option<InputEvent> {
+        if self.len == 0 { return None; }
+
+        let event = self.events[self.head].take();
+        self.head = (self.head + 1) % EVENT_QUEUE_CAPACITY;
+        self.len -= 1;
+        event
+    }
+}
+
+static EVENT_QUEUE: Mutex<EventQueue> = Mutex::new(EventQueue::new());
+static KEYBOARD: Mutex<Option<Keyboard>> = Mutex::new(None);
+static MOUSE: Mutex<Option<Mouse>> = Mutex::new(None);
+
+/// Initialize the PS/2 controller and the devices attached to it
+///
+/// The controller is skipped if the ACPI FADT says the system does not have one.
+pub fn initialize() {
+    if let Some(fadt) = acpi::fadt() {
+        if !fadt.has_8042_controller() {
+            log_warn!("Input", "ACPI reports that there is no PS/2 controller");
+            return;
+        }
+    }
+
+    let mut controller = PS2_CONTROLLER.lock();
+    let ports = match controller.initialize() {
+        Ok(ports) => ports,
+        Err(error) => {
+            log_warn!("Input", "Could not initialize the PS/2 controller: {error:?}");
+            return;
+        }
+    };
+    log_info!("Input", "PS/2 controller initialized. First port: {}, Second port: {}", ports.first, ports.second);
+
+    if ports.first {
+        match initialize_keyboard(&mut controller) {
+            Ok(keyboard) => {
+                log_info!("Input", "Keyboard initialized using scancode {:?}", keyboard.scancode_set());
+                *KEYBOARD.lock() = Some(keyboard);
+            },
+            Err(error) => log_warn!("Input", "Could not initialize keyboard: {error:?}"),
+        }
+    }
+
+    if ports.second {
+        match initialize_mouse(&mut controller) {
+            Ok(mouse) => {
+                log_info!("Input", "Mouse initialized as {:?}", mouse.mouse_type());
+                *MOUSE.lock() = Some(mouse);
+            },
+            Err(error) => log_warn!("Input", "Could not initialize mouse: {error:?}"),
+        }
+    }
+
+    if let Err(error) = controller.enable_interrupts() {
+        log_warn!("Input", "Could not enable PS/2 interrupts: {error:?}");
+    }
+}
+
+fn initialize_keyboard(controller: &mut Ps2Controller) -> Result<Keyboard, Ps2Error> {
+    controller.reset_device(Ps2Port::First)?;
+
+    // Translation is disabled so ask for set 2 and fall back to whatever the keyboard reports. Set 3
+    // can't be decoded, so a keyboard still in it gets asked for set 2 again before giving up
+    let mut reply = 0;
+    for _ in 0..SCANCODE_SET_ATTEMPTS {
+        let _ = controller.send_device_command_with_data(Ps2Port::First, KEYBOARD_SCANCODE_SET, 2);
+        controller.send_device_command_with_data(Ps2Port::First, KEYBOARD_SCANCODE_SET, SCANCODE_SET_QUERY)?;
+        reply = controller.read_data()?;
+        if reply != 3 { break; }
+    }
+    let scancode_set = match reply {
+        1 => ScancodeSet::Set1,
+        2 => ScancodeSet::Set2,
+        reply => return Err(Ps2Error::UnexpectedReply(reply)),
+    };
+
+    controller.send_device_command(Ps2Port::First, KEYBOARD_ENABLE_SCANNING)?;
+
+    Ok(Keyboard::new(scancode_set, &US_QWERTY))
+}
+
+fn initialize_mouse(controller: &mut Ps2Controller) -> Result<Mouse, Ps2Error> {
+    controller.reset_device(Ps2Port::Second)?;
+    controller.send_device_command(Ps2Port::Second, MOUSE_SET_DEFAULTS)?;
+
+    for rate in INTELLIMOUSE_SAMPLE_RATE_SEQUENCE {
+        controller.send_device_command_with_data(Ps2Port::Second, MOUSE_SET_SAMPLE_RATE, rate)?;
+    }
+    controller.send_device_command(Ps2Port::Second, MOUSE_GET_DEVICE_ID)?;
+    let mouse_type = match controller.read_data()? {
+        MOUSE_ID_INTELLIMOUSE => MouseType::IntelliMouse,
+        _ => MouseType::Standard,
+    };
+
+    controller.send_device_command_with_data(Ps2Port::Second, MOUSE_SET_SAMPLE_RATE, MOUSE_SAMPLE_RATE)?;
+    controller.send_device_command(Ps2Port::Second, MOUSE_ENABLE_REPORTING)?;
+
+    Ok(Mouse::new(mouse_type))
+}
+
+/// Read all pending bytes from the PS/2 controller and queue the resulting events
+pub fn poll() {
+    let mut controller = PS2_CONTROLLER.lock();
+    let mut keyboard = KEYBOARD.lock();
+    let mut mouse = MOUSE.lock();
+    // Input that arrived while waiting for the keyboard to acknowledge an LED update
+    let mut pending = VecDeque::new();
+
+    while let Some((port, byte)) = pending.pop_front().or_else(|| controller.try_read_data()) {
+        match (port, keyboard.as_mut(), mouse.as_mut()) {
+            (Ps2Port::First, Some(keyboard), _) => {
+                let Some(event) = keyboard.process_byte(byte) else { continue };
+
+                if event.state == KeyState::Pressed && is_lock_key(event.key) {
+                    update_keyboard_leds(&mut controller, event.modifiers, &mut pending);
+                }
+                EVENT_QUEUE.lock().push(InputEvent::Keyboard(event));
+            },
+            (Ps2Port::Second, _, Some(mouse)) => {
+                let Some(event) = mouse.process_byte(byte) else { continue };
+                EVENT_QUEUE.lock().push(InputEvent::Mouse(event));
+            },
+            _ => {},
+        }
+    }
+}
+
+/// Get the next input event, polling the devices first
+pub fn next_event() -> Option<InputEvent> {
+    poll();
+    EVENT_QUEUE.lock().pop()
+}
+
+fn is_lock_key(key: KeyCode) -> bool {
+    matches!(key, KeyCode::CapsLock | KeyCode::NumLock | KeyCode::ScrollLock)
+}
+
+/// Set the LEDs to match the lock keys, keeping any input that arrives before the keyboard acknowledges it
+fn update_keyboard_leds(controller: &mut Ps2Controller, modifiers: Modifiers, pending: &mut VecDeque<(Ps2Port, u8)>) {
+    let mut leds = 0;
+    if modifiers.contains(Modifiers::SCROLL_LOCK) { leds |= LED_SCROLL_LOCK; }
+    if modifiers.contains(Modifiers::NUM_LOCK) { leds |= LED_NUM_LOCK; }
+    if modifiers.contains(Modifiers::CAPS_LOCK) { leds |= LED_CAPS_LOCK; }
+
+    let result = controller.send_device_sequence(Ps2Port::First, &[KEYBOARD_SET_LEDS, leds], |port, byte| pending.push_back((port, byte)));
+    if let Err(error) = result {
+        log_warn!("Input", "Could not update keyboard LEDs: {error:?}");
+    }
+}
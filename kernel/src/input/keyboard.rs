@@ -0,0 +1,474 @@
+use super::keymap::Keymap;
+
+const SET1_EXTENDED_PREFIX: u8 = 0xE0;
+const SET1_PAUSE_PREFIX: u8 = 0xE1;
+const SET1_RELEASE_BIT: u8 = 0x80;
+/// Bytes following 0xE1 in the set 1 pause sequence: 1D 45 E1 9D C5
+const SET1_PAUSE_LENGTH: u8 = 5;
+
+const SET2_EXTENDED_PREFIX: u8 = 0xE0;
+const SET2_PAUSE_PREFIX: u8 = 0xE1;
+const SET2_RELEASE_PREFIX: u8 = 0xF0;
+/// Bytes following 0xE1 in the set 2 pause sequence: 14 77 E1 F0 14 F0 77
+const SET2_PAUSE_LENGTH: u8 = 7;
+
+/// Fake shift codes sent around some extended keys (eg. print screen) which should be ignored
+const SET1_FAKE_SHIFT: u8 = 0x2A;
+const SET2_FAKE_SHIFT: u8 = 0x12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScancodeSet {
+    Set1,
+    Set2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyState {
+    Pressed,
+    Released,
+}
+
+/// A physical key on the keyboard, independent of the layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCode {
+    Escape,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    PrintScreen, ScrollLock, Pause,
+    Backtick,
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+    Minus, Equals, Backspace,
+    Tab,
+    Q, W, E, R, T, Y, U, I, O, P,
+    LeftBracket, RightBracket, Backslash,
+    CapsLock,
+    A, S, D, F, G, H, J, K, L,
+    Semicolon, Quote, Enter,
+    LeftShift,
+    NonUsBackslash,
+    Z, X, C, V, B, N, M,
+    Comma, Period, Slash,
+    RightShift,
+    LeftControl, LeftGui, LeftAlt, Space, RightAlt, RightGui, Apps, RightControl,
+    Insert, Home, PageUp, Delete, End, PageDown,
+    ArrowUp, ArrowLeft, ArrowDown, ArrowRight,
+    NumLock, KeypadSlash, KeypadAsterisk, KeypadMinus, KeypadPlus, KeypadEnter, KeypadPeriod,
+    Keypad0, Keypad1, Keypad2, Keypad3, Keypad4, Keypad5, Keypad6, Keypad7, Keypad8, Keypad9,
+}
+
+/// The modifier keys and lock states held when a key event was generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Modifiers(u16);
+
+impl Modifiers {
+    pub const LEFT_SHIFT: Modifiers = Modifiers(1 << 0);
+    pub const RIGHT_SHIFT: Modifiers = Modifiers(1 << 1);
+    pub const LEFT_CONTROL: Modifiers = Modifiers(1 << 2);
+    pub const RIGHT_CONTROL: Modifiers = Modifiers(1 << 3);
+    pub const LEFT_ALT: Modifiers = Modifiers(1 << 4);
+    pub const RIGHT_ALT: Modifiers = Modifiers(1 << 5);
+    pub const LEFT_GUI: Modifiers = Modifiers(1 << 6);
+    pub const RIGHT_GUI: Modifiers = Modifiers(1 << 7);
+    pub const CAPS_LOCK: Modifiers = Modifiers(1 << 8);
+    pub const NUM_LOCK: Modifiers = Modifiers(1 << 9);
+    pub const SCROLL_LOCK: Modifiers = Modifiers(1 << 10);
+
+    pub const fn empty() -> Modifiers { Modifiers(0) }
+
+    #[inline]
+    pub const fn contains(&self, other: Modifiers) -> bool { self.0 & other.0 == other.0 }
+
+    #[inline]
+    pub const fn intersects(&self, other: Modifiers) -> bool { self.0 & other.0 != 0 }
+
+    #[inline]
+    pub fn set(&mut self, other: Modifiers, value: bool) {
+        if value {
+            self.0 |= other.0;
+        } else {
+            self.0 &= !other.0;
+        }
+    }
+
+    #[inline]
+    pub fn toggle(&mut self, other: Modifiers) { self.0 ^= other.0; }
+
+    #[inline]
+    pub fn shift(&self) -> bool { self.intersects(Modifiers::LEFT_SHIFT | Modifiers::RIGHT_SHIFT) }
+
+    #[inline]
+    pub fn caps_lock(&self) -> bool { self.contains(Modifiers::CAPS_LOCK) }
+
+    #[inline]
+    pub fn num_lock(&self) -> bool { self.contains(Modifiers::NUM_LOCK) }
+}
+
+impl core::ops::BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Modifiers) -> Modifiers { Modifiers(self.0 | rhs.0) }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: KeyCode,
+    pub state: KeyState,
+    pub modifiers: Modifiers,
+    /// The character produced by the keymap, if any
+    pub character: Option<char>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DecodeState {
+    Start,
+    Extended,
+    Release,
+    ExtendedRelease,
+    Pause(u8),
+}
+
+/// Translates raw scancodes from a PS/2 keyboard into key events
+pub struct Keyboard {
+    scancode_set: ScancodeSet,
+    keymap: &'static dyn Keymap,
+    state: DecodeState,
+    modifiers: Modifiers,
+}
+
+impl Keyboard {
+    pub fn new(scancode_set: ScancodeSet, keymap: &'static dyn Keymap) -> Keyboard {
+        Keyboard { scancode_set, keymap, state: DecodeState::Start, modifiers: Modifiers::empty() }
+    }
+
+    pub fn scancode_set(&self) -> ScancodeSet { self.scancode_set }
+
+    /// Process a single byte received from the keyboard
+    ///
+    /// Returns an event once a complete scancode has been received
+    pub fn process_byte(&mut self, byte: u8) -> Option<KeyEvent> {
+        let (key, state) = match self.scancode_set {
+            ScancodeSet::Set1 => self.decode_set1(byte)?,
+            ScancodeSet::Set2 => self.decode_set2(byte)?,
+        };
+
+        self.update_modifiers(key, state);
+
+        let character = match state {
+            KeyState::Pressed => self.keymap.map_key(key, self.modifiers),
+            KeyState::Released => None,
+        };
+
+        Some(KeyEvent { key, state, modifiers: self.modifiers, character })
+    }
+
+    fn decode_set1(&mut self, byte: u8) -> Option<(KeyCode, KeyState)> {
+        match self.state {
+            DecodeState::Pause(remaining) => {
+                self.state = if remaining > 1 { DecodeState::Pause(remaining - 1) } else { DecodeState::Start };
+                if remaining == 1 { Some((KeyCode::Pause, KeyState::Pressed)) } else { None }
+            },
+            DecodeState::Start => match byte {
+                SET1_EXTENDED_PREFIX => { self.state = DecodeState::Extended; None },
+                SET1_PAUSE_PREFIX => { self.state = DecodeState::Pause(SET1_PAUSE_LENGTH); None },
+                _ => {
+                    let key = set1_key(byte & !SET1_RELEASE_BIT)?;
+                    Some((key, set1_state(byte)))
+                }
+            },
+            _ => {
+                self.state = DecodeState::Start;
+                if byte & !SET1_RELEASE_BIT == SET1_FAKE_SHIFT { return None; }
+
+                let key = set1_extended_key(byte & !SET1_RELEASE_BIT)?;
+                Some((key, set1_state(byte)))
+            },
+        }
+    }
+
+    fn decode_set2(&mut self, byte: u8) -> Option<(KeyCode, KeyState)> {
+        match (self.state, byte) {
+            (DecodeState::Pause(remaining), _) => {
+                self.state = if remaining > 1 { DecodeState::Pause(remaining - 1) } else { DecodeState::Start };
+                if remaining == 1 { Some((KeyCode::Pause, KeyState::Pressed)) } else { None }
+            },
+            (DecodeState::Start, SET2_EXTENDED_PREFIX) => { self.state = DecodeState::Extended; None },
+            (DecodeState::Start, SET2_PAUSE_PREFIX) => { self.state = DecodeState::Pause(SET2_PAUSE_LENGTH); None },
+            (DecodeState::Start, SET2_RELEASE_PREFIX) => { self.state = DecodeState::Release; None },
+            (DecodeState::Extended, SET2_RELEASE_PREFIX) => { self.state = DecodeState::ExtendedRelease; None },
+            (DecodeState::Start, _) => Some((set2_key(byte)?, KeyState::Pressed)),
+            (DecodeState::Release, _) => {
+                self.state = DecodeState::Start;
+                Some((set2_key(byte)?, KeyState::Released))
+            },
+            (DecodeState::Extended, _) | (DecodeState::ExtendedRelease, _) => {
+                let key_state = if self.state == DecodeState::Extended { KeyState::Pressed } else { KeyState::Released };
+                self.state = DecodeState::Start;
+                if byte == SET2_FAKE_SHIFT { return None; }
+
+                Some((set2_extended_key(byte)?, key_state))
+            },
+        }
+    }
+
+    fn update_modifiers(&mut self, key: KeyCode, state: KeyState) {
+        let pressed = state == KeyState::Pressed;
+
+        match key {
+            KeyCode::LeftShift => self.modifiers.set(Modifiers::LEFT_SHIFT, pressed),
+            KeyCode::RightShift => self.modifiers.set(Modifiers::RIGHT_SHIFT, pressed),
+            KeyCode::LeftControl => self.modifiers.set(Modifiers::LEFT_CONTROL, pressed),
+            KeyCode::RightControl => self.modifiers.set(Modifiers::RIGHT_CONTROL, pressed),
+            KeyCode::LeftAlt => self.modifiers.set(Modifiers::LEFT_ALT, pressed),
+            KeyCode::RightAlt => self.modifiers.set(Modifiers::RIGHT_ALT, pressed),
+            KeyCode::LeftGui => self.modifiers.set(Modifiers::LEFT_GUI, pressed),
+            KeyCode::RightGui => self.modifiers.set(Modifiers::RIGHT_GUI, pressed),
+            KeyCode::CapsLock if pressed => self.modifiers.toggle(Modifiers::CAPS_LOCK),
+            KeyCode::NumLock if pressed => self.modifiers.toggle(Modifiers::NUM_LOCK),
+            KeyCode::ScrollLock if pressed => self.modifiers.toggle(Modifiers::SCROLL_LOCK),
+            _ => {},
+        }
+    }
+}
+
+fn set1_state(byte: u8) -> KeyState {
+    if byte & SET1_RELEASE_BIT != 0 { KeyState::Released } else { KeyState::Pressed }
+}
+
+fn set1_key(code: u8) -> Option<KeyCode> {
+    let key = match code {
+        0x01 => KeyCode::Escape,
+        0x02 => KeyCode::Key1,
+        0x03 => KeyCode::Key2,
+        0x04 => KeyCode::Key3,
+        0x05 => KeyCode::Key4,
+        0x06 => KeyCode::Key5,
+        0x07 => KeyCode::Key6,
+        0x08 => KeyCode::Key7,
+        0x09 => KeyCode::Key8,
+        0x0A => KeyCode::Key9,
+        0x0B => KeyCode::Key0,
+        0x0C => KeyCode::Minus,
+        0x0D => KeyCode::Equals,
+        0x0E => KeyCode::Backspace,
+        0x0F => KeyCode::Tab,
+        0x10 => KeyCode::Q,
+        0x11 => KeyCode::W,
+        0x12 => KeyCode::E,
+        0x13 => KeyCode::R,
+        0x14 => KeyCode::T,
+        0x15 => KeyCode::Y,
+        0x16 => KeyCode::U,
+        0x17 => KeyCode::I,
+        0x18 => KeyCode::O,
+        0x19 => KeyCode::P,
+        0x1A => KeyCode::LeftBracket,
+        0x1B => KeyCode::RightBracket,
+        0x1C => KeyCode::Enter,
+        0x1D => KeyCode::LeftControl,
+        0x1E => KeyCode::A,
+        0x1F => KeyCode::S,
+        0x20 => KeyCode::D,
+        0x21 => KeyCode::F,
+        0x22 => KeyCode::G,
+        0x23 => KeyCode::H,
+        0x24 => KeyCode::J,
+        0x25 => KeyCode::K,
+        0x26 => KeyCode::L,
+        0x27 => KeyCode::Semicolon,
+        0x28 => KeyCode::Quote,
+        0x29 => KeyCode::Backtick,
+        0x2A => KeyCode::LeftShift,
+        0x2B => KeyCode::Backslash,
+        0x2C => KeyCode::Z,
+        0x2D => KeyCode::X,
+        0x2E => KeyCode::C,
+        0x2F => KeyCode::V,
+        0x30 => KeyCode::B,
+        0x31 => KeyCode::N,
+        0x32 => KeyCode::M,
+        0x33 => KeyCode::Comma,
+        0x34 => KeyCode::Period,
+        0x35 => KeyCode::Slash,
+        0x36 => KeyCode::RightShift,
+        0x37 => KeyCode::KeypadAsterisk,
+        0x38 => KeyCode::LeftAlt,
+        0x39 => KeyCode::Space,
+        0x3A => KeyCode::CapsLock,
+        0x3B => KeyCode::F1,
+        0x3C => KeyCode::F2,
+        0x3D => KeyCode::F3,
+        0x3E => KeyCode::F4,
+        0x3F => KeyCode::F5,
+        0x40 => KeyCode::F6,
+        0x41 => KeyCode::F7,
+        0x42 => KeyCode::F8,
+        0x43 => KeyCode::F9,
+        0x44 => KeyCode::F10,
+        0x45 => KeyCode::NumLock,
+        0x46 => KeyCode::ScrollLock,
+        0x47 => KeyCode::Keypad7,
+        0x48 => KeyCode::Keypad8,
+        0x49 => KeyCode::Keypad9,
+        0x4A => KeyCode::KeypadMinus,
+        0x4B => KeyCode::Keypad4,
+        0x4C => KeyCode::Keypad5,
+        0x4D => KeyCode::Keypad6,
+        0x4E => KeyCode::KeypadPlus,
+        0x4F => KeyCode::Keypad1,
+        0x50 => KeyCode::Keypad2,
+        0x51 => KeyCode::Keypad3,
+        0x52 => KeyCode::Keypad0,
+        0x53 => KeyCode::KeypadPeriod,
+        0x56 => KeyCode::NonUsBackslash,
+        0x57 => KeyCode::F11,
+        0x58 => KeyCode::F12,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+fn set1_extended_key(code: u8) -> Option<KeyCode> {
+    let key = match code {
+        0x1C => KeyCode::KeypadEnter,
+        0x1D => KeyCode::RightControl,
+        0x35 => KeyCode::KeypadSlash,
+        0x37 => KeyCode::PrintScreen,
+        0x38 => KeyCode::RightAlt,
+        0x47 => KeyCode::Home,
+        0x48 => KeyCode::ArrowUp,
+        0x49 => KeyCode::PageUp,
+        0x4B => KeyCode::ArrowLeft,
+        0x4D => KeyCode::ArrowRight,
+        0x4F => KeyCode::End,
+        0x50 => KeyCode::ArrowDown,
+        0x51 => KeyCode::PageDown,
+        0x52 => KeyCode::Insert,
+        0x53 => KeyCode::Delete,
+        0x5B => KeyCode::LeftGui,
+        0x5C => KeyCode::RightGui,
+        0x5D => KeyCode::Apps,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+fn set2_key(code: u8) -> Option<KeyCode> {
+    let key = match code {
+        0x01 => KeyCode::F9,
+        0x03 => KeyCode::F5,
+        0x04 => KeyCode::F3,
+        0x05 => KeyCode::F1,
+        0x06 => KeyCode::F2,
+        0x07 => KeyCode::F12,
+        0x09 => KeyCode::F10,
+        0x0A => KeyCode::F8,
+        0x0B => KeyCode::F6,
+        0x0C => KeyCode::F4,
+        0x0D => KeyCode::Tab,
+        0x0E => KeyCode::Backtick,
+        0x11 => KeyCode::LeftAlt,
+        0x12 => KeyCode::LeftShift,
+        0x14 => KeyCode::LeftControl,
+        0x15 => KeyCode::Q,
+        0x16 => KeyCode::Key1,
+        0x1A => KeyCode::Z,
+        0x1B => KeyCode::S,
+        0x1C => KeyCode::A,
+        0x1D => KeyCode::W,
+        0x1E => KeyCode::Key2,
+        0x21 => KeyCode::C,
+        0x22 => KeyCode::X,
+        0x23 => KeyCode::D,
+        0x24 => KeyCode::E,
+        0x25 => KeyCode::Key4,
+        0x26 => KeyCode::Key3,
+        0x29 => KeyCode::Space,
+        0x2A => KeyCode::V,
+        0x2B => KeyCode::F,
+        0x2C => KeyCode::T,
+        0x2D => KeyCode::R,
+        0x2E => KeyCode::Key5,
+        0x31 => KeyCode::N,
+        0x32 => KeyCode::B,
+        0x33 => KeyCode::H,
+        0x34 => KeyCode::G,
+        0x35 => KeyCode::Y,
+        0x36 => KeyCode::Key6,
+        0x3A => KeyCode::M,
+        0x3B => KeyCode::J,
+        0x3C => KeyCode::U,
+        0x3D => KeyCode::Key7,
+        0x3E => KeyCode::Key8,
+        0x41 => KeyCode::Comma,
+        0x42 => KeyCode::K,
+        0x43 => KeyCode::I,
+        0x44 => KeyCode::O,
+        0x45 => KeyCode::Key0,
+        0x46 => KeyCode::Key9,
+        0x49 => KeyCode::Period,
+        0x4A => KeyCode::Slash,
+        0x4B => KeyCode::L,
+        0x4C => KeyCode::Semicolon,
+        0x4D => KeyCode::P,
+        0x4E => KeyCode::Minus,
+        0x52 => KeyCode::Quote,
+        0x54 => KeyCode::LeftBracket,
+        0x55 => KeyCode::Equals,
+        0x58 => KeyCode::CapsLock,
+        0x59 => KeyCode::RightShift,
+        0x5A => KeyCode::Enter,
+        0x5B => KeyCode::RightBracket,
+        0x5D => KeyCode::Backslash,
+        0x61 => KeyCode::NonUsBackslash,
+        0x66 => KeyCode::Backspace,
+        0x69 => KeyCode::Keypad1,
+        0x6B => KeyCode::Keypad4,
+        0x6C => KeyCode::Keypad7,
+        0x70 => KeyCode::Keypad0,
+        0x71 => KeyCode::KeypadPeriod,
+        0x72 => KeyCode::Keypad2,
+        0x73 => KeyCode::Keypad5,
+        0x74 => KeyCode::Keypad6,
+        0x75 => KeyCode::Keypad8,
+        0x76 => KeyCode::Escape,
+        0x77 => KeyCode::NumLock,
+        0x78 => KeyCode::F11,
+        0x79 => KeyCode::KeypadPlus,
+        0x7A => KeyCode::Keypad3,
+        0x7B => KeyCode::KeypadMinus,
+        0x7C => KeyCode::KeypadAsterisk,
+        0x7D => KeyCode::Keypad9,
+        0x7E => KeyCode::ScrollLock,
+        0x83 => KeyCode::F7,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+fn set2_extended_key(code: u8) -> Option<KeyCode> {
+    let key = match code {
+        0x11 => KeyCode::RightAlt,
+        0x14 => KeyCode::RightControl,
+        0x1F => KeyCode::LeftGui,
+        0x27 => KeyCode::RightGui,
+        0x2F => KeyCode::Apps,
+        0x4A => KeyCode::KeypadSlash,
+        0x5A => KeyCode::KeypadEnter,
+        0x69 => KeyCode::End,
+        0x6B => KeyCode::ArrowLeft,
+        0x6C => KeyCode::Home,
+        0x70 => KeyCode::Insert,
+        0x71 => KeyCode::Delete,
+        0x72 => KeyCode::ArrowDown,
+        0x74 => KeyCode::ArrowRight,
+        0x75 => KeyCode::ArrowUp,
+        0x7A => KeyCode::PageDown,
+        0x7C => KeyCode::PrintScreen,
+        0x7D => KeyCode::PageUp,
+        _ => return None,
+    };
+
+    Some(key)
+}
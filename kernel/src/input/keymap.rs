@@ -0,0 +1,132 @@
+use super::keyboard::{KeyCode, Modifiers};
+
+/// Maps physical keys to the characters they produce for a keyboard layout
+pub trait Keymap: Sync {
+    /// Get the character produced when the key is pressed with the given modifiers
+    fn map_key(&self, key: KeyCode, modifiers: Modifiers) -> Option<char>;
+}
+
+/// The standard US QWERTY layout
+pub struct UsQwerty;
+
+pub static US_QWERTY: UsQwerty = UsQwerty;
+
+impl Keymap for UsQwerty {
+    fn map_key(&self, key: KeyCode, modifiers: Modifiers) -> Option<char> {
+        if let Some(letter) = us_qwerty_letter(key) {
+            // Caps lock only affects letters and is undone by shift
+            let upper = modifiers.shift() != modifiers.caps_lock();
+            return Some(if upper { letter.to_ascii_uppercase() } else { letter });
+        }
+
+        if let Some((normal, shifted)) = us_qwerty_symbol(key) {
+            return Some(if modifiers.shift() { shifted } else { normal });
+        }
+
+        if let Some(character) = keypad_character(key, modifiers.num_lock()) {
+            return Some(character);
+        }
+
+        match key {
+            KeyCode::Enter | KeyCode::KeypadEnter => Some('\n'),
+            KeyCode::Tab => Some('\t'),
+            KeyCode::Backspace => Some('\x08'),
+            KeyCode::Escape => Some('\x1b'),
+            KeyCode::Space => Some(' '),
+            _ => None,
+        }
+    }
+}
+
+fn us_qwerty_letter(key: KeyCode) -> Option<char> {
+    let letter = match key {
+        KeyCode::A => 'a',
+        KeyCode::B => 'b',
+        KeyCode::C => 'c',
+        KeyCode::D => 'd',
+        KeyCode::E => 'e',
+        KeyCode::F => 'f',
+        KeyCode::G => 'g',
+        KeyCode::H => 'h',
+        KeyCode::I => 'i',
+        KeyCode::J => 'j',
+        KeyCode::K => 'k',
+        KeyCode::L => 'l',
+        KeyCode::M => 'm',
+        KeyCode::N => 'n',
+        KeyCode::O => 'o',
+        KeyCode::P => 'p',
+        KeyCode::Q => 'q',
+        KeyCode::R => 'r',
+        KeyCode::S => 's',
+        KeyCode::T => 't',
+        KeyCode::U => 'u',
+        KeyCode::V => 'v',
+        KeyCode::W => 'w',
+        KeyCode::X => 'x',
+        KeyCode::Y => 'y',
+        KeyCode::Z => 'z',
+        _ => return None,
+    };
+
+    Some(letter)
+}
+
+fn us_qwerty_symbol(key: KeyCode) -> Option<(char, char)> {
+    let symbols = match key {
+        KeyCode::Backtick => ('`', '~'),
+        KeyCode::Key1 => ('1', '!'),
+        KeyCode::Key2 => ('2', '@'),
+        KeyCode::Key3 => ('3', '#'),
+        KeyCode::Key4 => ('4', '$'),
+        KeyCode::Key5 => ('5', '%'),
+        KeyCode::Key6 => ('6', '^'),
+        KeyCode::Key7 => ('7', '&'),
+        KeyCode::Key8 => ('8', '*'),
+        KeyCode::Key9 => ('9', '('),
+        KeyCode::Key0 => ('0', ')'),
+        KeyCode::Minus => ('-', '_'),
+        KeyCode::Equals => ('=', '+'),
+        KeyCode::LeftBracket => ('[', '{'),
+        KeyCode::RightBracket => (']', '}'),
+        KeyCode::Backslash | KeyCode::NonUsBackslash => ('\\', '|'),
+        KeyCode::Semicolon => (';', ':'),
+        KeyCode::Quote => ('\'', '"'),
+        KeyCode::Comma => (',', '<'),
+        KeyCode::Period => ('.', '>'),
+        KeyCode::Slash => ('/', '?'),
+        _ => return None,
+    };
+
+    Some(symbols)
+}
+
+/// Keypad keys that produce the same character on every layout
+fn keypad_character(key: KeyCode, num_lock: bool) -> Option<char> {
+    match key {
+        KeyCode::KeypadSlash => return Some('/'),
+        KeyCode::KeypadAsterisk => return Some('*'),
+        KeyCode::KeypadMinus => return Some('-'),
+        KeyCode::KeypadPlus => return Some('+'),
+        _ => {},
+    }
+
+    if !num_lock { return None; }
+
+    let character = match key {
+        KeyCode::Keypad0 => '0',
+        KeyCode::Keypad1 => '1',
+        KeyCode::Keypad2 => '2',
+        KeyCode::Keypad3 => '3',
+        KeyCode::Keypad4 => '4',
+        KeyCode::Keypad5 => '5',
+        KeyCode::Keypad6 => '6',
+        KeyCode::Keypad7 => '7',
+        KeyCode::Keypad8 => '8',
+        KeyCode::Keypad9 => '9',
+        KeyCode::KeypadPeriod => '.',
+        _ => return None,
+    };
+
+    Some(character)
+}
@@ -0,0 +1,127 @@
+const PACKET_LEFT_BUTTON: u8 = 1 << 0;
+const PACKET_RIGHT_BUTTON: u8 = 1 << 1;
+const PACKET_MIDDLE_BUTTON: u8 = 1 << 2;
+/// Always set in the first byte of a packet, used to resynchronise with the device
+const PACKET_ALWAYS_SET: u8 = 1 << 3;
+const PACKET_X_SIGN: u8 = 1 << 4;
+const PACKET_Y_SIGN: u8 = 1 << 5;
+const PACKET_X_OVERFLOW: u8 = 1 << 6;
+const PACKET_Y_OVERFLOW: u8 = 1 << 7;
+
+const STANDARD_PACKET_LENGTH: usize = 3;
+const INTELLIMOUSE_PACKET_LENGTH: usize = 4;
+
+/// The packet format reported by the device ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseType {
+    /// Standard PS/2 mouse with 3 byte packets (ID 0x00)
+    Standard,
+    /// IntelliMouse with a scroll wheel and 4 byte packets (ID 0x03)
+    IntelliMouse,
+}
+
+impl MouseType {
+    fn packet_length(&self) -> usize {
+        match self {
+            MouseType::Standard => STANDARD_PACKET_LENGTH,
+            MouseType::IntelliMouse => INTELLIMOUSE_PACKET_LENGTH,
+        }
+    }
+}
+
+/// The set of mouse buttons held down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MouseButtons(u8);
+
+impl MouseButtons {
+    pub const LEFT: MouseButtons = MouseButtons(1 << 0);
+    pub const RIGHT: MouseButtons = MouseButtons(1 << 1);
+    pub const MIDDLE: MouseButtons = MouseButtons(1 << 2);
+
+    pub const fn empty() -> MouseButtons { MouseButtons(0) }
+
+    fn from_packet(flags: u8) -> MouseButtons {
+        let mut buttons = MouseButtons::empty();
+        if flags & PACKET_LEFT_BUTTON != 0 { buttons.0 |= MouseButtons::LEFT.0; }
+        if flags & PACKET_RIGHT_BUTTON != 0 { buttons.0 |= MouseButtons::RIGHT.0; }
+        if flags & PACKET_MIDDLE_BUTTON != 0 { buttons.0 |= MouseButtons::MIDDLE.0; }
+        buttons
+    }
+}
+
+impl core::ops::BitXor for MouseButtons {
+    type Output = MouseButtons;
+
+    fn bitxor(self, rhs: MouseButtons) -> MouseButtons { MouseButtons(self.0 ^ rhs.0) }
+}
+
+/// Relative motion and button state decoded from a single mouse packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseEvent {
+    /// Horizontal motion, positive to the right
+    pub dx: i16,
+    /// Vertical motion, positive downwards to match framebuffer coordinates
+    pub dy: i16,
+    /// Scroll wheel motion, positive towards the user. Always 0 on a standard mouse
+    pub scroll: i8,
+    /// Buttons held after this packet
+    pub buttons: MouseButtons,
+    /// Buttons that were pressed or released by this packet
+    pub changed: MouseButtons,
+}
+
+/// Assembles packets from a PS/2 mouse into mouse events
+pub struct Mouse {
+    mouse_type: MouseType,
+    packet: [u8; INTELLIMOUSE_PACKET_LENGTH],
+    received: usize,
+    buttons: MouseButtons,
+}
+
+impl Mouse {
+    pub fn new(mouse_type: MouseType) -> Mouse {
+        Mouse { mouse_type, packet: [0; INTELLIMOUSE_PACKET_LENGTH], received: 0, buttons: MouseButtons::empty() }
+    }
+
+    pub fn mouse_type(&self) -> MouseType { self.mouse_type }
+
+    /// Process a single byte received from the mouse
+    ///
+    /// Returns an event once a complete packet has been received
+    pub fn process_byte(&mut self, byte: u8) -> Option<MouseEvent> {
+        // Drop bytes until the start of a packet if we have lost track of the packet boundary
+        if self.received == 0 && byte & PACKET_ALWAYS_SET == 0 { return None; }
+
+        self.packet[self.received] = byte;
+        self.received += 1;
+        if self.received < self.mouse_type.packet_length() { return None; }
+        self.received = 0;
+
+        Some(self.decode_packet())
+    }
+
+    fn decode_packet(&mut self) -> MouseEvent {
+        let flags = self.packet[0];
+
+        let dx = if flags & PACKET_X_OVERFLOW != 0 { 0 } else { sign_extend_9(self.packet[1], flags & PACKET_X_SIGN != 0) };
+        let dy = if flags & PACKET_Y_OVERFLOW != 0 { 0 } else { sign_extend_9(self.packet[2], flags & PACKET_Y_SIGN != 0) };
+
+        let scroll = match self.mouse_type {
+            // The wheel movement is a 4 bit two's complement value in the low bits
+            MouseType::IntelliMouse => ((self.packet[3] << 4) as i8) >> 4,
+            MouseType::Standard => 0,
+        };
+
+        let buttons = MouseButtons::from_packet(flags);
+        let changed = buttons ^ self.buttons;
+        self.buttons = buttons;
+
+        // The mouse reports upward motion as positive
+        MouseEvent { dx, dy: -dy, scroll, buttons, changed }
+    }
+}
+
+/// Combine a movement byte with its sign bit from the flags byte
+fn sign_extend_9(value: u8, negative: bool) -> i16 {
+    if negative { value as i16 - 0x100 } else { value as i16 }
+}
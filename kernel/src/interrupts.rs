@@ -0,0 +1,142 @@
+use core::{arch::asm, ptr::addr_of_mut};
+
+use x86_64_hardware::{devices::pic::PIC, registers::Cr2};
+
+use crate::{backtrace, log_critical, log_info, println};
+
+use self::idt::InterruptDescriptorTable;
+
+mod idt;
+
+const DIVIDE_ERROR_VECTOR: u8 = 0;
+const BREAKPOINT_VECTOR: u8 = 3;
+const INVALID_OPCODE_VECTOR: u8 = 6;
+const DOUBLE_FAULT_VECTOR: u8 = 8;
+const GENERAL_PROTECTION_VECTOR: u8 = 13;
+const PAGE_FAULT_VECTOR: u8 = 14;
+
+/// IRQs are delivered to the vectors just past the ones the CPU reserves for exceptions
+const PIC_VECTOR_OFFSET: u8 = 0x20;
+const KEYBOARD_IRQ: u8 = 1;
+const COM1_IRQ: u8 = 4;
+const PRIMARY_SPURIOUS_IRQ: u8 = 7;
+const MOUSE_IRQ: u8 = 12;
+const SECONDARY_SPURIOUS_IRQ: u8 = 15;
+
+/// What the CPU pushes onto the stack before calling a handler
+#[repr(C)]
+#[derive(Debug)]
+pub struct InterruptStackFrame {
+    pub instruction_pointer: u64,
+    pub code_segment: u64,
+    pub cpu_flags: u64,
+    pub stack_pointer: u64,
+    pub stack_segment: u64,
+}
+
+static mut IDT: InterruptDescriptorTable = InterruptDescriptorTable::new();
+
+/// Install handlers for CPU exceptions that report what went wrong along with a backtrace
+///
+/// The input IRQs are also routed to handlers so wait_for_interrupt can sleep until there is input.
+/// Interrupts stay disabled outside of wait_for_interrupt.
+pub fn initialize() {
+    // Safety: This runs once during boot before interrupts are used
+    unsafe {
+        asm!("cli", options(nomem, nostack));
+        let idt = &mut *addr_of_mut!(IDT);
+        idt.set_handler(DIVIDE_ERROR_VECTOR, divide_error_handler as *const ());
+        idt.set_handler(BREAKPOINT_VECTOR, breakpoint_handler as *const ());
+        idt.set_handler(INVALID_OPCODE_VECTOR, invalid_opcode_handler as *const ());
+        idt.set_handler(DOUBLE_FAULT_VECTOR, double_fault_handler as *const ());
+        idt.set_handler(GENERAL_PROTECTION_VECTOR, general_protection_handler as *const ());
+        idt.set_handler(PAGE_FAULT_VECTOR, page_fault_handler as *const ());
+        idt.set_handler(PIC_VECTOR_OFFSET + KEYBOARD_IRQ, keyboard_irq_handler as *const ());
+        idt.set_handler(PIC_VECTOR_OFFSET + COM1_IRQ, com1_irq_handler as *const ());
+        idt.set_handler(PIC_VECTOR_OFFSET + PRIMARY_SPURIOUS_IRQ, primary_spurious_irq_handler as *const ());
+        idt.set_handler(PIC_VECTOR_OFFSET + MOUSE_IRQ, mouse_irq_handler as *const ());
+        idt.set_handler(PIC_VECTOR_OFFSET + SECONDARY_SPURIOUS_IRQ, secondary_spurious_irq_handler as *const ());
+        idt.load();
+    }
+
+    let mut pic = PIC.lock();
+    pic.initialize(PIC_VECTOR_OFFSET);
+    for irq in [KEYBOARD_IRQ, COM1_IRQ, MOUSE_IRQ] {
+        pic.unmask(irq);
+    }
+}
+
+/// Halt until the next interrupt arrives
+///
+/// Interrupts are only enabled for the hlt, so an IRQ that arrived after the caller last checked for
+/// input is delivered as soon as sti takes effect and wakes the CPU straight away.
+pub fn wait_for_interrupt() {
+    unsafe { asm!("sti; hlt; cli", options(nostack)); }
+}
+
+extern "x86-interrupt" fn divide_error_handler(stack_frame: InterruptStackFrame) {
+    fatal_exception("Divide Error", &stack_frame, None, backtrace::caller_frame_pointer());
+}
+
+/// Breakpoints only report where they were hit, so int3 can be used to print a backtrace
+extern "x86-interrupt" fn breakpoint_handler(stack_frame: InterruptStackFrame) {
+    log_info!("Interrupts", "Breakpoint at {:#X}", stack_frame.instruction_pointer);
+    backtrace::print_from(stack_frame.instruction_pointer, backtrace::caller_frame_pointer());
+}
+
+extern "x86-interrupt" fn invalid_opcode_handler(stack_frame: InterruptStackFrame) {
+    fatal_exception("Invalid Opcode", &stack_frame, None, backtrace::caller_frame_pointer());
+}
+
+extern "x86-interrupt" fn double_fault_handler(stack_frame: InterruptStackFrame, error_code: u64) -> ! {
+    fatal_exception("Double Fault", &stack_frame, Some(error_code), backtrace::caller_frame_pointer());
+}
+
+extern "x86-interrupt" fn general_protection_handler(stack_frame: InterruptStackFrame, error_code: u64) {
+    fatal_exception("General Protection Fault", &stack_frame, Some(error_code), backtrace::caller_frame_pointer());
+}
+
+extern "x86-interrupt" fn page_fault_handler(stack_frame: InterruptStackFrame, error_code: u64) {
+    let fault_address = Cr2::read();
+    log_critical!("Interrupts", "Page fault accessing {:#X}", fault_address.as_u64());
+    fatal_exception("Page Fault", &stack_frame, Some(error_code), backtrace::caller_frame_pointer());
+}
+
+// The input IRQs only wake the CPU. The devices are read by polling once it is awake
+
+extern "x86-interrupt" fn keyboard_irq_handler(_stack_frame: InterruptStackFrame) {
+    PIC.lock().end_of_interrupt(KEYBOARD_IRQ);
+}
+
+extern "x86-interrupt" fn com1_irq_handler(_stack_frame: InterruptStackFrame) {
+    PIC.lock().end_of_interrupt(COM1_IRQ);
+}
+
+extern "x86-interrupt" fn mouse_irq_handler(_stack_frame: InterruptStackFrame) {
+    PIC.lock().end_of_interrupt(MOUSE_IRQ);
+}
+
+extern "x86-interrupt" fn primary_spurious_irq_handler(_stack_frame: InterruptStackFrame) {
+    PIC.lock().end_of_interrupt(PRIMARY_SPURIOUS_IRQ);
+}
+
+extern "x86-interrupt" fn secondary_spurious_irq_handler(_stack_frame: InterruptStackFrame) {
+    PIC.lock().end_of_interrupt(SECONDARY_SPURIOUS_IRQ);
+}
+
+/// Report an exception the kernel can't recover from and stop
+///
+/// The frame pointer is the one the interrupted code was using, so the backtrace starts where the
+/// exception happened.
+fn fatal_exception(name: &str, stack_frame: &InterruptStackFrame, error_code: Option<u64>, frame_pointer: u64) -> ! {
+    log_critical!("Interrupts", "{name} at {:#X}", stack_frame.instruction_pointer);
+    if let Some(error_code) = error_code {
+        println!("  Error code: {error_code:#X}");
+    }
+    println!("  {stack_frame:#X?}");
+    backtrace::print_from(stack_frame.instruction_pointer, frame_pointer);
+
+    loop {
+        unsafe { asm!("cli; hlt", options(nomem, nostack)); }
+    }
+}
@@ -0,0 +1,82 @@
+use core::arch::asm;
+
+/// Present, ring 0, 64 bit interrupt gate, which clears IF while the handler runs
+const INTERRUPT_GATE: u8 = 0x8E;
+
+const ENTRY_COUNT: usize = 256;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IdtEntry {
+    offset_low: u16,
+    selector: u16,
+    interrupt_stack_table: u8,
+    attributes: u8,
+    offset_middle: u16,
+    offset_high: u32,
+    reserved: u32,
+}
+
+impl IdtEntry {
+    const MISSING: IdtEntry = IdtEntry {
+        offset_low: 0,
+        selector: 0,
+        interrupt_stack_table: 0,
+        attributes: 0,
+        offset_middle: 0,
+        offset_high: 0,
+        reserved: 0,
+    };
+
+    fn new(handler: u64, selector: u16) -> IdtEntry {
+        IdtEntry {
+            offset_low: handler as u16,
+            selector,
+            interrupt_stack_table: 0,
+            attributes: INTERRUPT_GATE,
+            offset_middle: (handler >> 16) as u16,
+            offset_high: (handler >> 32) as u32,
+            reserved: 0,
+        }
+    }
+}
+
+/// The operand of lidt
+#[repr(C, packed)]
+struct IdtDescriptor {
+    limit: u16,
+    base: u64,
+}
+
+/// The table of handlers the CPU calls for each interrupt vector
+#[repr(C, align(16))]
+pub struct InterruptDescriptorTable {
+    entries: [IdtEntry; ENTRY_COUNT],
+}
+
+impl InterruptDescriptorTable {
+    pub const fn new() -> InterruptDescriptorTable {
+        InterruptDescriptorTable { entries: [IdtEntry::MISSING; ENTRY_COUNT] }
+    }
+
+    /// Set the handler for a vector, running it in the current code segment
+    pub fn set_handler(&mut self, vector: u8, handler: *const ()) {
+        let code_segment: u16;
+        unsafe { asm!("mov {0:x}, cs", out(reg) code_segment, options(nomem, nostack, preserves_flags)); }
+
+        self.entries[vector as usize] = IdtEntry::new(handler as u64, code_segment);
+    }
+
+    /// Make this the table the CPU uses
+    ///
+    /// ## Safety
+    /// Every handler in the table must be a valid interrupt handler
+    pub unsafe fn load(&'static self) {
+        let descriptor = IdtDescriptor {
+            limit: (core::mem::size_of::<InterruptDescriptorTable>() - 1) as u16,
+            base: self as *const InterruptDescriptorTable as u64,
+        };
+
+        unsafe { asm!("lidt [{}]", in(reg) &descriptor, options(readonly, nostack, preserves_flags)); }
+    }
+}
@@ -1,67 +1,314 @@
 use core::fmt;
 
-use crate::{font_renderer::FontRenderer, graphics_renderer::Color};
+use alloc::{vec, vec::Vec};
+
+use crate::{font_renderer::FontRenderer, graphics_renderer::{Canvas, Color, FrameBuffer}};
+
+use self::ansi::{AnsiAction, AnsiParser, CsiSequence, TextAttributes};
+
+mod ansi;
+
+/// Number of lines kept in the text history, including the ones on screen
+const SCROLLBACK_LINES: usize = 1000;
+
+const TAB_WIDTH: usize = 8;
+
+const REPLACEMENT_CHARACTER: char = '\u{FFFD}';
+
+/// A character on the text grid along with the colors it was drawn in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    glyph: usize,
+    foreground: Color,
+    background: Color,
+}
+
+impl Cell {
+    const fn blank(glyph: usize, background: Color) -> Cell {
+        Cell { glyph, foreground: background, background }
+    }
+}
 
 pub struct LayoutRenderer {
     font_renderer: FontRenderer,
+    /// The glyph for a space, used to fill cleared cells
+    blank_glyph: usize,
     cols: usize,
     rows: usize,
     cell_width: usize,
     cell_height: usize,
     x: usize,
-    y: usize
+    y: usize,
+    saved_cursor: (usize, usize),
+    /// The colors set by the owner of the renderer, used when no SGR colors are active
+    default_colors: (Color, Color),
+    attributes: TextAttributes,
+    parser: AnsiParser,
+    /// Ring buffer of the last SCROLLBACK_LINES lines of text
+    history: Vec<Cell>,
+    history_lines: usize,
+    /// The line in the history shown at the top of the screen
+    top_line: usize,
+    /// How many lines the view is scrolled back from the top line
+    view_offset: usize,
+    /// While hidden text is only added to the history so something else can use the screen
+    visible: bool,
 }
 
 impl LayoutRenderer {
-    
+
     pub fn new(font_renderer: FontRenderer) -> LayoutRenderer {
         let frame_buffer = font_renderer.get_graphics_renderer();
         let (width, height) = frame_buffer.get_resolution();
         let cell_width = font_renderer.get_glyph_width();
         let cell_height = font_renderer.get_glyph_height();
-        let cols = width / cell_width;
-        let rows = height / cell_height;
+        // A glyph bigger than the screen still gets one cell, drawn clipped, so there is somewhere to put text
+        let cols = (width / cell_width).max(1);
+        let rows = (height / cell_height).max(1);
+
+        let default_colors = (Color::new(0xFFFFFF), Color::new(0x000000));
+        let history_lines = SCROLLBACK_LINES.max(rows);
+        let blank_glyph = font_renderer.glyph_for_char(' ');
+
+        LayoutRenderer {
+            font_renderer, blank_glyph, cols, rows, cell_width, cell_height, x: 0, y:0,
+            saved_cursor: (0, 0),
+            default_colors,
+            attributes: TextAttributes::new(),
+            parser: AnsiParser::new(),
+            history: vec![Cell::blank(blank_glyph, default_colors.1); history_lines * cols],
+            history_lines,
+            top_line: 0,
+            view_offset: 0,
+            visible: true,
+        }
+    }
 
-        LayoutRenderer { font_renderer, cols, rows, cell_width, cell_height, x: 0, y:0 }
+    pub fn print_char(&mut self, character: char) {
+        self.write_char(character);
+        self.flush();
     }
 
-    pub fn print_char(&mut self, char: u8) {
-        match char {
-            b'\n' => self.newline(),
-            b'\r' => {
+    /// Print a string, interpreting ANSI escape sequences
+    pub fn print_string(&mut self, s: &str) {
+        for character in s.chars() {
+            match self.parser.process_char(character) {
+                Some(AnsiAction::Print(character)) => self.write_char(character),
+                Some(AnsiAction::Csi(sequence)) => self.execute_csi(sequence),
+                None => {},
+            }
+        }
+        self.flush();
+    }
+
+    /// The frame buffer the console draws to
+    pub fn frame_buffer_mut(&mut self) -> &mut FrameBuffer {
+        self.font_renderer.get_graphics_renderer_mut()
+    }
+
+    /// Stop drawing to the screen or start again, redrawing everything
+    pub fn set_visible(&mut self, visible: bool) {
+        if visible == self.visible { return; }
+
+        self.visible = visible;
+        if visible {
+            let background = self.default_colors.1;
+            self.frame_buffer_mut().fill(background);
+            self.redraw();
+            self.flush();
+        }
+    }
+
+    /// Set the colors used for text without SGR color attributes
+    pub fn set_colors(&mut self, colors: (Color, Color)) {
+        self.default_colors = colors;
+    }
+
+    /// Scroll the view back through the history by a screen
+    pub fn page_up(&mut self) {
+        let max_offset = self.top_line - self.oldest_line();
+        let view_offset = (self.view_offset + self.rows).min(max_offset);
+        if view_offset != self.view_offset {
+            self.view_offset = view_offset;
+            self.redraw();
+            self.flush();
+        }
+    }
+
+    /// Scroll the view forward through the history by a screen
+    pub fn page_down(&mut self) {
+        if self.view_offset == 0 { return; }
+
+        self.view_offset = self.view_offset.saturating_sub(self.rows);
+        self.redraw();
+        self.flush();
+    }
+
+    fn write_char(&mut self, character: char) {
+        self.scroll_to_bottom();
+
+        match character {
+            '\n' => self.newline(),
+            '\r' => {
                 self.x = 0;
             },
-            byte => {
+            // Backspace only moves the cursor so that "\x08 \x08" erases a character
+            '\x08' => {
+                self.x = self.x.saturating_sub(1);
+            },
+            '\t' => {
+                self.x = ((self.x / TAB_WIDTH + 1) * TAB_WIDTH).min(self.cols - 1);
+            },
+            character => {
                 if self.x >= self.cols {
                     self.newline()
                 }
 
-                self.font_renderer.draw_glyph(byte, self.x * self.cell_width, self.y * self.cell_height);
+                // Other control characters have no glyph so they show up as the replacement glyph
+                let glyph = if character.is_control() { REPLACEMENT_CHARACTER } else { character };
+                let (foreground, background) = self.colors();
+                let cell = Cell { glyph: self.font_renderer.glyph_for_char(glyph), foreground, background };
+                let index = self.history_index(self.top_line + self.y, self.x);
+                self.history[index] = cell;
+                self.draw_cell(self.x, self.y, cell);
                 self.x += 1;
             }
         }
     }
 
-    pub fn print_string(&mut self, s: &str) {
-        for byte in s.bytes() {
-            match byte {
-                0x20..=0x7e | b'\n' | b'\r' => self.print_char(byte),
-                _ => self.print_char(0x04),
-            }
+    fn execute_csi(&mut self, sequence: CsiSequence) {
+        self.scroll_to_bottom();
+
+        // DEC private modes such as cursor visibility have no effect here
+        if sequence.private { return; }
+
+        let count = sequence.parameter_or(0, 1) as usize;
+        match sequence.command {
+            b'm' => self.attributes.apply_sgr(sequence.parameters()),
+            b'A' => self.y = self.y.saturating_sub(count),
+            b'B' => self.y = (self.y + count).min(self.rows - 1),
+            b'C' => self.x = (self.x + count).min(self.cols - 1),
+            b'D' => self.x = self.x.saturating_sub(count).min(self.cols - 1),
+            b'E' => {
+                self.x = 0;
+                self.y = (self.y + count).min(self.rows - 1);
+            },
+            b'F' => {
+                self.x = 0;
+                self.y = self.y.saturating_sub(count);
+            },
+            b'G' => self.x = (count - 1).min(self.cols - 1),
+            b'H' | b'f' => {
+                self.y = (sequence.parameter_or(0, 1) as usize - 1).min(self.rows - 1);
+                self.x = (sequence.parameter_or(1, 1) as usize - 1).min(self.cols - 1);
+            },
+            b'J' => self.erase_screen(sequence.parameter_or(0, 0)),
+            b'K' => self.erase_line(sequence.parameter_or(0, 0)),
+            b's' => self.saved_cursor = (self.x, self.y),
+            b'u' => (self.x, self.y) = self.saved_cursor,
+            _ => {},
         }
     }
 
-    pub fn set_colors(&mut self, (foreground, background): (Color, Color)) {
-        self.font_renderer.set_colors(foreground, background);
+    /// Erase part of the screen: 0 from the cursor to the end, 1 from the start to the cursor, 2 or 3 everything
+    fn erase_screen(&mut self, mode: u16) {
+        match mode {
+            0 => {
+                self.erase_line(0);
+                for row in self.y + 1..self.rows { self.clear_row(row, 0, self.cols); }
+            },
+            1 => {
+                for row in 0..self.y { self.clear_row(row, 0, self.cols); }
+                self.erase_line(1);
+            },
+            2 | 3 => {
+                for row in 0..self.rows { self.clear_row(row, 0, self.cols); }
+            },
+            _ => {},
+        }
+    }
+
+    /// Erase part of the cursor's line: 0 from the cursor to the end, 1 from the start to the cursor, 2 everything
+    fn erase_line(&mut self, mode: u16) {
+        match mode {
+            0 => self.clear_row(self.y, self.x.min(self.cols), self.cols),
+            1 => self.clear_row(self.y, 0, (self.x + 1).min(self.cols)),
+            2 => self.clear_row(self.y, 0, self.cols),
+            _ => {},
+        }
+    }
+
+    /// Clear the cells of a screen row from start_col up to but not including end_col
+    fn clear_row(&mut self, row: usize, start_col: usize, end_col: usize) {
+        let background = self.colors().1;
+        let line_start = self.history_index(self.top_line + row, 0);
+        self.history[line_start + start_col..line_start + end_col].fill(Cell::blank(self.blank_glyph, background));
+        if !self.visible { return; }
+
+        self.font_renderer.get_graphics_renderer_mut().fill_rect(
+            start_col * self.cell_width, row * self.cell_height,
+            (end_col - start_col) * self.cell_width, self.cell_height,
+            background
+        );
     }
 
     fn newline(&mut self) {
         self.x = 0;
-        self.y += 1;
-        
-        if self.y >= self.rows { 
-            self.y = 0; 
+
+        if self.y + 1 < self.rows {
+            self.y += 1;
+            return;
         }
+
+        self.top_line += 1;
+        let background = self.colors().1;
+        let line_start = self.history_index(self.top_line + self.rows - 1, 0);
+        self.history[line_start..line_start + self.cols].fill(Cell::blank(self.blank_glyph, background));
+        if self.visible {
+            self.font_renderer.get_graphics_renderer_mut().scroll_up(self.cell_height, background);
+        }
+    }
+
+    /// Jump back to the cursor if the view has been scrolled back
+    fn scroll_to_bottom(&mut self) {
+        if self.view_offset == 0 { return; }
+
+        self.view_offset = 0;
+        self.redraw();
+    }
+
+    /// Draw every visible line from the history
+    fn redraw(&mut self) {
+        let first_line = self.top_line - self.view_offset;
+
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let cell = self.history[self.history_index(first_line + row, col)];
+                self.draw_cell(col, row, cell);
+            }
+        }
+    }
+
+    /// Show what has been drawn if the frame buffer is double buffered
+    fn flush(&mut self) {
+        self.font_renderer.get_graphics_renderer_mut().flush();
+    }
+
+    fn draw_cell(&mut self, col: usize, row: usize, cell: Cell) {
+        if !self.visible { return; }
+
+        self.font_renderer.set_colors(cell.foreground, cell.background);
+        self.font_renderer.draw_glyph(cell.glyph, col * self.cell_width, row * self.cell_height);
+    }
+
+    /// The colors new text is drawn with
+    fn colors(&self) -> (Color, Color) { self.attributes.resolve(self.default_colors) }
+
+    /// The oldest line still held in the history
+    fn oldest_line(&self) -> usize { (self.top_line + self.rows).saturating_sub(self.history_lines) }
+
+    fn history_index(&self, line: usize, col: usize) -> usize {
+        (line % self.history_lines) * self.cols + col
     }
 }
 
@@ -0,0 +1,262 @@
+use crate::graphics_renderer::Color;
+
+const ESCAPE: char = '\x1B';
+const MAX_PARAMETERS: usize = 16;
+
+/// Something the renderer should do in response to the text written to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnsiAction {
+    /// A character outside of any escape sequence
+    Print(char),
+    /// A complete control sequence introduced by ESC [
+    Csi(CsiSequence),
+}
+
+/// The parameters and final byte of a CSI sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsiSequence {
+    parameters: [u16; MAX_PARAMETERS],
+    parameter_count: usize,
+    /// Set when the sequence starts with '?', used for DEC private modes
+    pub private: bool,
+    pub command: u8,
+}
+
+impl CsiSequence {
+    const fn new() -> CsiSequence {
+        CsiSequence { parameters: [0; MAX_PARAMETERS], parameter_count: 0, private: false, command: 0 }
+    }
+
+    pub fn parameters(&self) -> &[u16] { &self.parameters[..self.parameter_count] }
+
+    /// Get a parameter, treating missing and zero values as the default
+    pub fn parameter_or(&self, index: usize, default: u16) -> u16 {
+        match self.parameters().get(index) {
+            Some(0) | None => default,
+            Some(&value) => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParserState {
+    Ground,
+    Escape,
+    Csi,
+}
+
+/// Splits a character stream into printable characters and CSI sequences
+pub struct AnsiParser {
+    state: ParserState,
+    sequence: CsiSequence,
+}
+
+impl AnsiParser {
+    pub const fn new() -> AnsiParser {
+        AnsiParser { state: ParserState::Ground, sequence: CsiSequence::new() }
+    }
+
+    /// Process a single character, returning an action once one is complete
+    ///
+    /// Escape sequences other than CSI are discarded.
+    pub fn process_char(&mut self, character: char) -> Option<AnsiAction> {
+        match self.state {
+            ParserState::Ground => {
+                if character == ESCAPE {
+                    self.state = ParserState::Escape;
+                    return None;
+                }
+                Some(AnsiAction::Print(character))
+            },
+            ParserState::Escape => {
+                self.state = match character {
+                    '[' => {
+                        self.sequence = CsiSequence::new();
+                        ParserState::Csi
+                    },
+                    ESCAPE => ParserState::Escape,
+                    _ => ParserState::Ground,
+                };
+                None
+            },
+            ParserState::Csi => self.process_csi_char(character),
+        }
+    }
+
+    fn process_csi_char(&mut self, character: char) -> Option<AnsiAction> {
+        // Anything outside of ASCII can't be part of a sequence so the sequence is dropped
+        if !character.is_ascii() {
+            self.state = ParserState::Ground;
+            return None;
+        }
+
+        let byte = character as u8;
+        let sequence = &mut self.sequence;
+
+        match byte {
+            b'0'..=b'9' => {
+                if sequence.parameter_count == 0 { sequence.parameter_count = 1; }
+                let parameter = &mut sequence.parameters[sequence.parameter_count - 1];
+                *parameter = parameter.saturating_mul(10).saturating_add((byte - b'0') as u16);
+                None
+            },
+            // Colon separated sub-parameters (38:2:r:g:b) are treated like normal parameters
+            b';' | b':' => {
+                if sequence.parameter_count == 0 { sequence.parameter_count = 1; }
+                if sequence.parameter_count < MAX_PARAMETERS { sequence.parameter_count += 1; }
+                None
+            },
+            b'?' if sequence.parameter_count == 0 => {
+                sequence.private = true;
+                None
+            },
+            // An escape abandons this sequence and starts another
+            0x1B => {
+                self.state = ParserState::Escape;
+                None
+            },
+            // Other control characters take effect as usual without ending the sequence
+            0x00..=0x1F => Some(AnsiAction::Print(character)),
+            // Intermediate bytes are not used by any supported sequence
+            0x20..=0x2F => None,
+            0x40..=0x7E => {
+                sequence.command = byte;
+                self.state = ParserState::Ground;
+                Some(AnsiAction::Csi(*sequence))
+            },
+            _ => {
+                self.state = ParserState::Ground;
+                None
+            },
+        }
+    }
+}
+
+/// A color selected by an SGR sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextColor {
+    /// The color set by the renderer's owner
+    Default,
+    /// An entry in the 256 color palette
+    Indexed(u8),
+    Rgb(Color),
+}
+
+/// Text attributes set by SGR sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextAttributes {
+    pub foreground: TextColor,
+    pub background: TextColor,
+    pub bold: bool,
+    pub dim: bool,
+    pub reverse: bool,
+}
+
+impl TextAttributes {
+    pub const fn new() -> TextAttributes {
+        TextAttributes {
+            foreground: TextColor::Default,
+            background: TextColor::Default,
+            bold: false,
+            dim: false,
+            reverse: false,
+        }
+    }
+
+    /// Apply the parameters of an SGR (ESC [ ... m) sequence
+    pub fn apply_sgr(&mut self, parameters: &[u16]) {
+        if parameters.is_empty() {
+            *self = TextAttributes::new();
+            return;
+        }
+
+        let mut index = 0;
+        while index < parameters.len() {
+            match parameters[index] {
+                0 => *self = TextAttributes::new(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                22 => { self.bold = false; self.dim = false; },
+                7 => self.reverse = true,
+                27 => self.reverse = false,
+                code @ 30..=37 => self.foreground = TextColor::Indexed((code - 30) as u8),
+                38 => self.foreground = parse_extended_color(parameters, &mut index).unwrap_or(self.foreground),
+                39 => self.foreground = TextColor::Default,
+                code @ 40..=47 => self.background = TextColor::Indexed((code - 40) as u8),
+                48 => self.background = parse_extended_color(parameters, &mut index).unwrap_or(self.background),
+                49 => self.background = TextColor::Default,
+                code @ 90..=97 => self.foreground = TextColor::Indexed((code - 90 + 8) as u8),
+                code @ 100..=107 => self.background = TextColor::Indexed((code - 100 + 8) as u8),
+                _ => {},
+            }
+            index += 1;
+        }
+    }
+
+    /// Work out the colors to draw with given the renderer's default colors
+    pub fn resolve(&self, (default_foreground, default_background): (Color, Color)) -> (Color, Color) {
+        let mut foreground = match self.foreground {
+            TextColor::Default => default_foreground,
+            // Bold brightens the standard colors like most terminals
+            TextColor::Indexed(index) if self.bold && index < 8 => palette_color(index + 8),
+            TextColor::Indexed(index) => palette_color(index),
+            TextColor::Rgb(color) => color,
+        };
+        let background = match self.background {
+            TextColor::Default => default_background,
+            TextColor::Indexed(index) => palette_color(index),
+            TextColor::Rgb(color) => color,
+        };
+
+        if self.dim { foreground = foreground / 2; }
+        if self.reverse { (background, foreground) } else { (foreground, background) }
+    }
+}
+
+/// Parse the 5;n or 2;r;g;b that follows a 38 or 48 SGR parameter
+///
+/// index is left on the last parameter used
+fn parse_extended_color(parameters: &[u16], index: &mut usize) -> Option<TextColor> {
+    match parameters.get(*index + 1)? {
+        5 => {
+            let color = *parameters.get(*index + 2)?;
+            *index += 2;
+            Some(TextColor::Indexed(color.min(255) as u8))
+        },
+        2 => {
+            let red = *parameters.get(*index + 2)?;
+            let green = *parameters.get(*index + 3)?;
+            let blue = *parameters.get(*index + 4)?;
+            *index += 4;
+            Some(TextColor::Rgb(Color(red.min(255) as u8, green.min(255) as u8, blue.min(255) as u8)))
+        },
+        _ => None,
+    }
+}
+
+/// The standard xterm colors used for the first 16 palette entries
+const STANDARD_COLORS: [u32; 16] = [
+    0x000000, 0xCD0000, 0x00CD00, 0xCDCD00, 0x0000EE, 0xCD00CD, 0x00CDCD, 0xE5E5E5,
+    0x7F7F7F, 0xFF0000, 0x00FF00, 0xFFFF00, 0x5C5CFF, 0xFF00FF, 0x00FFFF, 0xFFFFFF,
+];
+
+const COLOR_CUBE_LEVELS: [u8; 6] = [0x00, 0x5F, 0x87, 0xAF, 0xD7, 0xFF];
+
+/// Look up an entry in the xterm 256 color palette
+pub fn palette_color(index: u8) -> Color {
+    match index {
+        0..=15 => Color::new(STANDARD_COLORS[index as usize]),
+        16..=231 => {
+            let cube_index = index - 16;
+            Color(
+                COLOR_CUBE_LEVELS[(cube_index / 36) as usize],
+                COLOR_CUBE_LEVELS[(cube_index / 6 % 6) as usize],
+                COLOR_CUBE_LEVELS[(cube_index % 6) as usize],
+            )
+        },
+        232..=255 => {
+            let level = 8 + (index - 232) * 10;
+            Color(level, level, level)
+        },
+    }
+}
@@ -1,22 +1,29 @@
-use core::fmt::{self, Debug, Write};
+use core::{fmt::{self, Debug, Write}, ptr::addr_of_mut};
 
 use bootinfo::BootInfo;
+use spin::Mutex;
 use x86_64_hardware::devices::uart::COM1;
 
-use crate::{font_renderer::FontRenderer, graphics_renderer::{Color, FrameBuffer}, layout_renderer::LayoutRenderer};
+use crate::{font_renderer::FontRenderer, graphics_renderer::{Canvas, Color, FrameBuffer}, layout_renderer::LayoutRenderer};
 
-const MIN_SERIAL_LOG_LEVEL: LogLevel = LogLevel::Debug;
-const MIN_DISPLAY_LOG_LEVEL: LogLevel = LogLevel::Debug;
+const DEFAULT_MIN_SERIAL_LOG_LEVEL: LogLevel = LogLevel::Debug;
+const DEFAULT_MIN_DISPLAY_LOG_LEVEL: LogLevel = LogLevel::Debug;
 
-const OUTPUT_SERIAL_COLORS: bool = true;
-const OUTPUT_LOG_LEVEL: bool = false;
+const DEFAULT_OUTPUT_OPTIONS: OutputOptions = OutputOptions {
+    serial: true,
+    serial_colors: true,
+    level_prefix: false,
+};
+
+/// The boot module holding the PSF font used for the display
+const FONT_MODULE: &str = "font";
 
 const SERIAL_COLOR_RESET: &str = "\x1b[0;0;0m";
 const DEFAULT_DISPLAY_FOREGROUND: Color = Color::new(0x00FF00);
 const DEFAULT_DISPLAY_BACKGROUND: Color = Color::new(0x0000000);
 
 #[repr(u8)]
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 #[allow(dead_code)]
 pub enum LogLevel {
     Debug = 0,
@@ -27,6 +34,17 @@ pub enum LogLevel {
 }
 
 impl LogLevel {
+    pub fn from_name(name: &str) -> Option<LogLevel> {
+        match name {
+            "debug" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "warn" => Some(LogLevel::Warn),
+            "error" => Some(LogLevel::Error),
+            "critical" => Some(LogLevel::Critical),
+            _ => None,
+        }
+    }
+
     pub fn get_serial_color(&self) -> &str {
         match self {
             LogLevel::Debug => "\x1b[2;37m",
@@ -58,20 +76,69 @@ impl LogLevel {
     }
 }
 
+/// How log messages are written out
+#[derive(Debug, Clone, Copy)]
+pub struct OutputOptions {
+    /// Write log messages and printed output to COM1
+    pub serial: bool,
+    /// Color log messages on COM1 by level using ANSI escape codes
+    pub serial_colors: bool,
+    /// Start each log message with a letter for its level
+    pub level_prefix: bool,
+}
+
 static mut RENDERER: Option<LayoutRenderer> = None;
 
+static MIN_SERIAL_LOG_LEVEL: Mutex<LogLevel> = Mutex::new(DEFAULT_MIN_SERIAL_LOG_LEVEL);
+static MIN_DISPLAY_LOG_LEVEL: Mutex<LogLevel> = Mutex::new(DEFAULT_MIN_DISPLAY_LOG_LEVEL);
+static OUTPUT_OPTIONS: Mutex<OutputOptions> = Mutex::new(DEFAULT_OUTPUT_OPTIONS);
+
+/// The lowest levels that are logged to the serial port and the display
+pub fn get_min_log_levels() -> (LogLevel, LogLevel) {
+    (*MIN_SERIAL_LOG_LEVEL.lock(), *MIN_DISPLAY_LOG_LEVEL.lock())
+}
+
+pub fn set_min_serial_log_level(level: LogLevel) {
+    *MIN_SERIAL_LOG_LEVEL.lock() = level;
+}
+
+pub fn set_min_display_log_level(level: LogLevel) {
+    *MIN_DISPLAY_LOG_LEVEL.lock() = level;
+}
+
+pub fn get_output_options() -> OutputOptions {
+    *OUTPUT_OPTIONS.lock()
+}
+
+pub fn set_output_options(options: OutputOptions) {
+    *OUTPUT_OPTIONS.lock() = options;
+}
+
 pub fn initialize_com1() {
     COM1.lock().initialize();
 }
 
 pub fn initialize_screen_output(bootinfo: &BootInfo) {
-    let mut frame_buffer = FrameBuffer::from_boot_data(&bootinfo)
-        .expect("Could not create frame buffer.");
+    let Some(font) = bootinfo.modules.find(FONT_MODULE) else {
+        crate::log_error!("Logger", "The bootloader did not load the console font {FONT_MODULE}");
+        return;
+    };
+
+    let mut frame_buffer = match FrameBuffer::from_boot_data(bootinfo) {
+        Ok(frame_buffer) => frame_buffer,
+        Err(error) => {
+            // Without a usable frame buffer everything is still logged to COM1
+            crate::log_error!("Logger", "Could not use the frame buffer ({:?} {:?}): {error:?}",
+                bootinfo.framebuffer.pixel_format, bootinfo.framebuffer.pixel_bitmask);
+            return;
+        }
+    };
+    frame_buffer.enable_back_buffer();
     frame_buffer.fill(Color::new(0x000000));
 
     let font_renderer = FontRenderer::create(
-        bootinfo.font_file_address.as_u64() as *mut u8, 
-        bootinfo.font_file_size, 
+        font.virtual_address.as_u64() as *mut u8, 
+        font.size, 
         frame_buffer
     ).expect("Could not create font renderer");
     
@@ -81,8 +148,43 @@ pub fn initialize_screen_output(bootinfo: &BootInfo) {
     unsafe { RENDERER = Some(layout_renderer) };
 }
 
+/// Draw straight onto the display, returning None if there is no display
+///
+/// Anything drawn this way is not part of the console text so it is lost when the console redraws.
+pub fn with_display<R>(draw: impl FnOnce(&mut FrameBuffer) -> R) -> Option<R> {
+    unsafe {
+        let frame_buffer = (*addr_of_mut!(RENDERER)).as_mut()?.frame_buffer_mut();
+        let result = draw(frame_buffer);
+        frame_buffer.flush();
+        Some(result)
+    }
+}
+
+/// Show or hide the console on the display. Output is still kept in the scrollback while hidden
+pub fn set_console_visible(visible: bool) {
+    unsafe {
+        if let Some(renderer) = (*addr_of_mut!(RENDERER)).as_mut() { renderer.set_visible(visible); }
+    }
+}
+
+/// Scroll the display back through earlier output by a screen
+pub fn page_display_up() {
+    unsafe {
+        if let Some(renderer) = (*addr_of_mut!(RENDERER)).as_mut() { renderer.page_up(); }
+    }
+}
+
+/// Scroll the display forward towards the latest output by a screen
+pub fn page_display_down() {
+    unsafe {
+        if let Some(renderer) = (*addr_of_mut!(RENDERER)).as_mut() { renderer.page_down(); }
+    }
+}
+
 pub fn _print_fmt(args: fmt::Arguments) {
-    COM1.lock().write_fmt(args).unwrap();
+    if get_output_options().serial {
+        COM1.lock().write_fmt(args).unwrap();
+    }
 
     unsafe {
         match RENDERER.as_mut() {
@@ -93,31 +195,40 @@ pub fn _print_fmt(args: fmt::Arguments) {
 }
 
 pub fn _log_fmt(level: LogLevel, args: fmt::Arguments) {
-    if level >= MIN_SERIAL_LOG_LEVEL {
-        let color_code = if OUTPUT_SERIAL_COLORS { level.get_serial_color() } else { SERIAL_COLOR_RESET };
+    let (min_serial_level, min_display_level) = get_min_log_levels();
+    let options = get_output_options();
+
+    if options.serial && level >= min_serial_level {
+        let (color_code, reset_code) = if options.serial_colors {
+            (level.get_serial_color(), SERIAL_COLOR_RESET)
+        } else {
+            ("", "")
+        };
 
-        if OUTPUT_LOG_LEVEL {
+        if options.level_prefix {
             COM1.lock().write_fmt(format_args!(
-                "{color_code}[{}] {args}{SERIAL_COLOR_RESET}\n", level.get_prefix()
+                "{color_code}[{}] {args}{reset_code}\n", level.get_prefix()
             )).unwrap();
         } else {
-            COM1.lock().write_fmt(format_args!("{color_code}{args}{SERIAL_COLOR_RESET}\n"))
+            COM1.lock().write_fmt(format_args!("{color_code}{args}{reset_code}\n"))
                 .unwrap();
         }
         
     }
 
-    if level >= MIN_DISPLAY_LOG_LEVEL {
+    if level >= min_display_level {
         unsafe {
             match RENDERER.as_mut() {
                 Some(renderer) => { 
                     renderer.set_colors(level.get_display_color());
-                    if OUTPUT_LOG_LEVEL {
-                        renderer.write_fmt(format_args!("[{}] {args}\n", level.get_prefix())).unwrap(); 
+                    if options.level_prefix {
+                        renderer.write_fmt(format_args!("[{}] {args}", level.get_prefix())).unwrap(); 
                     } else {
-                        renderer.write_fmt(format_args!("{args}\n")).unwrap();
+                        renderer.write_fmt(format_args!("{args}")).unwrap();
                     }
+                    // Reset the colors before the newline so the new line is cleared with the default background
                     renderer.set_colors((DEFAULT_DISPLAY_FOREGROUND, DEFAULT_DISPLAY_BACKGROUND)); 
+                    renderer.print_char('\n');
                 },
                 None => {},
             }
@@ -136,7 +247,7 @@ macro_rules! print {
 
 #[macro_export]
 macro_rules! println {
-    () => ($crate::com1_print!("\n"));
+    () => ($crate::print!("\n"));
     ($($arg:tt)*) => ($crate::print!("{}\n", format_args!($($arg)*)));
 }
 
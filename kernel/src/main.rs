@@ -1,18 +1,36 @@
 #![no_std]
 #![no_main]
+#![feature(abi_x86_interrupt)]
+#![feature(thread_local)]
+
+extern crate alloc;
 
 use core::panic::PanicInfo;
 
 use bootinfo::BootInfo;
-use x86_64_hardware::memory::PageFrameAllocator;
-
-use core::arch::asm;
+use command_line::Console;
 
+mod acpi;
+mod backtrace;
+mod command_line;
+mod cpu;
 mod errors;
 mod graphics_renderer;
 mod font_renderer;
+mod image;
+mod initrd;
+mod input;
+mod interrupts;
 mod layout_renderer;
 mod logger;
+mod memory;
+mod shell;
+mod splash;
+mod symbols;
+mod tls;
+
+/// The number of steps in kernel initialization shown on the splash progress bar
+const BOOT_STEPS: usize = 4;
 
 /// This function is called on panic. 
 #[panic_handler]
@@ -25,6 +43,7 @@ fn panic(info: &PanicInfo) -> ! {
         Some(location) => println!("  At: {location}"),
         None => {},
     }
+    backtrace::print();
 
     loop {}
 }
@@ -34,38 +53,42 @@ pub extern "C" fn kernel_main(bootinfo: *mut BootInfo) {
     let bootinfo = unsafe { &mut *bootinfo };
 
     logger::initialize_com1();
-    logger::initialize_screen_output(bootinfo);
+    let options = command_line::apply(bootinfo);
+    symbols::initialize(bootinfo);
+    cpu::initialize();
+    interrupts::initialize();
+    // The screen output keeps its scrollback on the heap
+    memory::initialize(bootinfo);
+    tls::initialize(bootinfo);
+    if options.console == Console::FrameBuffer {
+        logger::initialize_screen_output(bootinfo);
+        splash::show(bootinfo);
+    }
 
     println!("Hello World from the kernel");
 
-    let allocator = unsafe { 
-        PageFrameAllocator::new_from_bitmap(
-            &mut bootinfo.meminfo.bitmap, 
-            bootinfo.meminfo.free_memory, 
-            bootinfo.meminfo.used_memory
-        ) 
-    };
     println!("Initialized Page Allocator:");
-    println!(
-        "  Free Memory: {:#X} ({} GB, {} MB, {} KB)", 
-        allocator.get_free_ram(), 
-        allocator.get_free_ram() / (1024 * 1024 * 1024), 
-        allocator.get_free_ram() / (1024 * 1024) % 1024, 
-        allocator.get_free_ram() / 1024 % 1024
-    );
-    println!(
-        "  Used Memory: {:#X} ({} GB, {} MB, {} KB)", 
-        allocator.get_used_ram(), allocator.get_used_ram() / (1024 * 1024 * 1024), 
-        allocator.get_used_ram() / (1024 * 1024) % 1024, 
-        allocator.get_used_ram() / 1024 % 1024);
-    let total_memory = allocator.get_free_ram() + allocator.get_used_ram();
-    println!(
-        "  Total Usable Memory: {:#X} ({} GB, {} MB, {} KB)", 
-        total_memory, total_memory / (1024 * 1024 * 1024), 
-        total_memory / (1024 * 1024) % 1024, 
-        total_memory / 1024 % 1024
+    memory::print_memory_usage();
+
+    log_debug!(
+        "Kernel", "Kernel slide {:#X}, physical memory mapped at {:#X}",
+        bootinfo.kernel_slide, bootinfo.page_table_memory_offset
     );
+    for module in bootinfo.modules.iter() {
+        log_debug!("Kernel", "Boot module {} at {:#X}, {} bytes", module.name(), module.virtual_address.as_u64(), module.size);
+    }
+    splash::set_progress(1, BOOT_STEPS);
+
+    initrd::initialize(bootinfo);
+    splash::set_progress(2, BOOT_STEPS);
+
+    acpi::initialize(bootinfo);
+    splash::set_progress(3, BOOT_STEPS);
 
+    input::initialize();
+    splash::set_progress(4, BOOT_STEPS);
+
+    splash::finish();
     println!("Kernel Finished");
 
     log_debug!("Kernel", "Debug Test");
@@ -74,10 +97,5 @@ pub extern "C" fn kernel_main(bootinfo: *mut BootInfo) {
     log_error!("Kernel", "Oh no!");
     log_critical!("Kernel", "BOOM");
 
-    loop {
-        unsafe {
-            asm!("cli");
-            asm!("hlt");
-        }
-    }
+    shell::run();
 }
\ No newline at end of file
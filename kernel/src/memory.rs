@@ -0,0 +1,78 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use bootinfo::BootInfo;
+use x86_64_hardware::{
+    cpuid::CpuFeatures,
+    memory::{PageFrameAllocator, PageTableManager},
+    registers::{Cr3, Efer, EferFlags},
+};
+
+use crate::{cpu, log_warn, println};
+
+mod heap;
+
+pub static PAGE_FRAME_ALLOCATOR: PageFrameAllocator = PageFrameAllocator::new_uninitialized();
+
+static PHYSICAL_MEMORY_OFFSET: AtomicU64 = AtomicU64::new(0);
+
+/// Take over the page bitmap built by the bootloader and set up the kernel heap
+///
+/// The heap grows upwards from the first kernel page the bootloader left unused.
+pub fn initialize(bootinfo: &mut BootInfo) {
+    enable_no_execute();
+    unsafe {
+        PAGE_FRAME_ALLOCATOR.init(
+            &mut bootinfo.meminfo.bitmap,
+            bootinfo.meminfo.free_memory,
+            bootinfo.meminfo.used_memory
+        );
+    }
+    PHYSICAL_MEMORY_OFFSET.store(bootinfo.page_table_memory_offset, Ordering::Relaxed);
+    heap::initialize(bootinfo.next_availiable_kernel_page);
+}
+
+/// Let page table entries mark pages as not executable, if the CPU supports it
+///
+/// Must run after cpu::initialize. Setting the bit on a CPU without NX faults.
+fn enable_no_execute() {
+    if !cpu::info().is_some_and(|info| info.has(CpuFeatures::NX)) {
+        log_warn!("Memory", "NX is not supported, so every mapped page is executable");
+        return;
+    }
+
+    let mut efer = Efer::read();
+    efer.insert(EferFlags::NO_EXECUTE_ENABLE);
+    // Safety: The CPU reported NX support and this only adds that flag
+    unsafe { Efer::write(efer); }
+}
+
+/// The virtual address all of physical memory is mapped at
+pub fn physical_memory_offset() -> u64 {
+    PHYSICAL_MEMORY_OFFSET.load(Ordering::Relaxed)
+}
+
+/// Get a manager for the page table that is currently loaded in CR3
+pub fn active_page_table() -> PageTableManager {
+    let (p4_address, _) = Cr3::read();
+    PageTableManager::new(p4_address, physical_memory_offset())
+}
+
+pub fn print_memory_usage() {
+    let free_memory = PAGE_FRAME_ALLOCATOR.get_free_ram();
+    let used_memory = PAGE_FRAME_ALLOCATOR.get_used_ram();
+    let total_memory = free_memory + used_memory;
+
+    print_memory_size("Free Memory", free_memory);
+    print_memory_size("Used Memory", used_memory);
+    print_memory_size("Total Usable Memory", total_memory);
+}
+
+fn print_memory_size(name: &str, size: u64) {
+    println!(
+        "  {name}: {:#X} ({} GB, {} MB, {} KB)",
+        size,
+        size / (1024 * 1024 * 1024),
+        size / (1024 * 1024) % 1024,
+        size / 1024 % 1024
+    );
+}
@@ -0,0 +1,195 @@
+use core::{alloc::{GlobalAlloc, Layout}, mem::{align_of, size_of}, ptr::null_mut};
+
+use spin::Mutex;
+use x86_64_hardware::memory::{AllocError, FrameAllocator, PageTableManager, VirtualAddress, PAGE_SIZE};
+
+use super::{active_page_table, PAGE_FRAME_ALLOCATOR};
+
+/// The minimum number of pages mapped each time the heap runs out of space
+const HEAP_GROWTH_PAGES: u64 = 16;
+
+/// The most the heap can grow to, keeping it well clear of the top of the address space
+const HEAP_MAX_SIZE: u64 = 256 * 1024 * 1024;
+
+/// A free region of the heap. Stored at the start of the region it describes
+struct FreeBlock {
+    size: usize,
+    next: *mut FreeBlock,
+}
+
+const MIN_BLOCK_SIZE: usize = size_of::<FreeBlock>();
+const BLOCK_ALIGN: usize = align_of::<FreeBlock>();
+
+/// A first fit free list allocator over a region of virtual memory that grows upwards
+struct Heap {
+    end: u64,
+    /// The heap never grows past this address
+    limit: u64,
+    /// Free blocks sorted by address
+    free_list: *mut FreeBlock,
+}
+
+// The free list is only ever accessed through the heap's mutex
+unsafe impl Send for Heap {}
+
+impl Heap {
+    const fn new() -> Heap {
+        Heap { end: 0, limit: 0, free_list: null_mut() }
+    }
+
+    /// Find a free block that can hold an allocation and carve the allocation out of it
+    unsafe fn allocate(&mut self, size: usize, align: usize) -> Option<*mut u8> {
+        let mut previous: *mut FreeBlock = null_mut();
+        let mut current = self.free_list;
+
+        while !current.is_null() {
+            let block_start = current as usize;
+            let block_end = block_start + (*current).size;
+
+            if let Some(allocation_start) = fit_allocation(block_start, block_end, size, align) {
+                let next = (*current).next;
+                if previous.is_null() { self.free_list = next; } else { (*previous).next = next; }
+
+                // Return whatever is left on either side of the allocation to the free list
+                let allocation_end = allocation_start + size;
+                if allocation_start > block_start { self.free(block_start, allocation_start - block_start); }
+                if block_end > allocation_end { self.free(allocation_end, block_end - allocation_end); }
+
+                return Some(allocation_start as *mut u8);
+            }
+
+            previous = current;
+            current = (*current).next;
+        }
+
+        None
+    }
+
+    /// Add a region to the free list, merging it with any neighbouring free blocks
+    unsafe fn free(&mut self, start: usize, size: usize) {
+        let mut previous: *mut FreeBlock = null_mut();
+        let mut next = self.free_list;
+        while !next.is_null() && (next as usize) < start {
+            previous = next;
+            next = (*next).next;
+        }
+
+        let block = start as *mut FreeBlock;
+        block.write(FreeBlock { size, next });
+
+        if !next.is_null() && start + size == next as usize {
+            (*block).size += (*next).size;
+            (*block).next = (*next).next;
+        }
+
+        if previous.is_null() {
+            self.free_list = block;
+        } else if previous as usize + (*previous).size == start {
+            (*previous).size += (*block).size;
+            (*previous).next = (*block).next;
+        } else {
+            (*previous).next = block;
+        }
+    }
+
+    /// Map enough new pages at the end of the heap to hold at least min_size more bytes
+    ///
+    /// If mapping fails partway the pages that were mapped still join the heap, so the same
+    /// addresses are never mapped twice.
+    unsafe fn grow(&mut self, min_size: usize) -> Result<(), AllocError> {
+        // The heap has not been given a location yet
+        if self.end == 0 { return Err(AllocError::OutOfMemory); }
+
+        let needed_pages = (min_size as u64).div_ceil(PAGE_SIZE);
+        let available_pages = (self.limit - self.end) / PAGE_SIZE;
+        if needed_pages > available_pages { return Err(AllocError::OutOfMemory); }
+
+        let page_count = needed_pages.max(HEAP_GROWTH_PAGES).min(available_pages);
+        let page_table = active_page_table();
+        let start = VirtualAddress::new(self.end);
+
+        let mut mapped_pages = 0;
+        let mut result = Ok(());
+        while mapped_pages < page_count {
+            if let Err(error) = map_heap_page(&page_table, start.increment_pages(mapped_pages)) {
+                result = Err(error);
+                break;
+            }
+            mapped_pages += 1;
+        }
+
+        if mapped_pages != 0 {
+            self.end += mapped_pages * PAGE_SIZE;
+            self.free(start.as_u64() as usize, (mapped_pages * PAGE_SIZE) as usize);
+        }
+        result
+    }
+}
+
+/// Back a heap page with a new frame, giving the frame back if it can't be mapped
+fn map_heap_page(page_table: &PageTableManager, address: VirtualAddress) -> Result<(), AllocError> {
+    let physical_address = PAGE_FRAME_ALLOCATOR.request_page()?;
+    if let Err(error) = page_table.map_memory(address, physical_address, &mut &PAGE_FRAME_ALLOCATOR) {
+        let _ = PAGE_FRAME_ALLOCATOR.free_page(physical_address);
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Find where an allocation would start within a free block if it fits
+///
+/// Any space left before or after the allocation must be large enough to hold a FreeBlock
+fn fit_allocation(block_start: usize, block_end: usize, size: usize, align: usize) -> Option<usize> {
+    let mut allocation_start = block_start.next_multiple_of(align);
+    if allocation_start != block_start && allocation_start - block_start < MIN_BLOCK_SIZE {
+        allocation_start = (block_start + MIN_BLOCK_SIZE).next_multiple_of(align);
+    }
+
+    let allocation_end = allocation_start.checked_add(size)?;
+    if allocation_end > block_end { return None; }
+
+    let remaining = block_end - allocation_end;
+    if remaining != 0 && remaining < MIN_BLOCK_SIZE { return None; }
+
+    Some(allocation_start)
+}
+
+/// Round a layout up so that every block handed out can later hold a FreeBlock
+fn block_layout(layout: Layout) -> (usize, usize) {
+    let size = layout.size().max(MIN_BLOCK_SIZE).next_multiple_of(BLOCK_ALIGN);
+    let align = layout.align().max(BLOCK_ALIGN);
+    (size, align)
+}
+
+pub struct KernelHeap {
+    heap: Mutex<Heap>,
+}
+
+#[global_allocator]
+static KERNEL_HEAP: KernelHeap = KernelHeap { heap: Mutex::new(Heap::new()) };
+
+/// Start the heap at the given address. Everything from there up to HEAP_MAX_SIZE above it must be unmapped
+pub fn initialize(start: VirtualAddress) {
+    let mut heap = KERNEL_HEAP.heap.lock();
+    heap.end = start.as_u64();
+    heap.limit = start.as_u64().saturating_add(HEAP_MAX_SIZE) & !(PAGE_SIZE - 1);
+}
+
+unsafe impl GlobalAlloc for KernelHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let (size, align) = block_layout(layout);
+        let mut heap = self.heap.lock();
+
+        loop {
+            if let Some(pointer) = heap.allocate(size, align) { return pointer; }
+
+            // Leave room to align the allocation within the new pages
+            if heap.grow(size + align).is_err() { return null_mut(); }
+        }
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        let (size, _) = block_layout(layout);
+        self.heap.lock().free(pointer as usize, size);
+    }
+}
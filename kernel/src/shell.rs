@@ -0,0 +1,337 @@
+use core::str::SplitWhitespace;
+
+use x86_64_hardware::{devices::{pci::PCI_CONFIG_SPACE, ps2_controller::PS2_CONTROLLER, uart::COM1}, memory::VirtualAddress};
+
+use crate::{acpi, backtrace, cpu, interrupts, graphics_renderer::{Canvas, Color, Surface}, initrd::{self, EntryKind}, input::{self, keyboard::{KeyCode, KeyState}, InputEvent}, logger::{self, LogLevel}, memory, print, println};
+
+const LINE_CAPACITY: usize = 256;
+const PROMPT: &str = "> ";
+
+const ASCII_BACKSPACE: u8 = 0x08;
+const ASCII_DELETE: u8 = 0x7F;
+
+const PAGE_TABLE_LEVEL_NAMES: [&str; 4] = ["P4", "P3", "P2", "P1"];
+
+struct Command {
+    name: &'static str,
+    usage: &'static str,
+    description: &'static str,
+    handler: fn(&mut SplitWhitespace),
+}
+
+const COMMANDS: [Command; 12] = [
+    Command { name: "help", usage: "help", description: "List the available commands", handler: help_command },
+    Command { name: "mem", usage: "mem", description: "Show page frame allocator usage", handler: mem_command },
+    Command { name: "cpu", usage: "cpu", description: "Show the CPU model and features", handler: cpu_command },
+    Command { name: "acpi", usage: "acpi", description: "List the ACPI tables", handler: acpi_command },
+    Command { name: "pci", usage: "pci", description: "List the PCI functions", handler: pci_command },
+    Command { name: "map", usage: "map <vaddr>", description: "Walk the page tables for an address", handler: map_command },
+    Command {
+        name: "log",
+        usage: "log [serial|display] [debug|info|warn|error|critical]",
+        description: "Show or set the minimum log level",
+        handler: log_command,
+    },
+    Command { name: "ls", usage: "ls [path]", description: "List a directory in the initial ramdisk", handler: ls_command },
+    Command { name: "cat", usage: "cat <path>", description: "Print a file from the initial ramdisk", handler: cat_command },
+    Command { name: "draw", usage: "draw", description: "Draw a test pattern on the display", handler: draw_command },
+    Command { name: "backtrace", usage: "backtrace", description: "Print the current call stack", handler: backtrace_command },
+    Command { name: "reboot", usage: "reboot", description: "Reset the machine", handler: reboot_command },
+];
+
+/// A line of input being typed by the user
+struct LineBuffer {
+    buffer: [u8; LINE_CAPACITY],
+    len: usize,
+}
+
+impl LineBuffer {
+    const fn new() -> LineBuffer {
+        LineBuffer { buffer: [0; LINE_CAPACITY], len: 0 }
+    }
+
+    fn push(&mut self, byte: u8) -> bool {
+        if self.len == LINE_CAPACITY { return false; }
+
+        self.buffer[self.len] = byte;
+        self.len += 1;
+        true
+    }
+
+    fn pop(&mut self) -> bool {
+        if self.len == 0 { return false; }
+
+        self.len -= 1;
+        true
+    }
+
+    fn clear(&mut self) { self.len = 0; }
+
+    fn as_str(&self) -> &str {
+        // Only printable ASCII is ever pushed
+        core::str::from_utf8(&self.buffer[..self.len]).unwrap_or("")
+    }
+}
+
+/// Run the debug shell, reading commands from COM1 and the keyboard
+pub fn run() -> ! {
+    COM1.lock().enable_receive_interrupt();
+    println!("Type 'help' for a list of commands");
+
+    let mut line = LineBuffer::new();
+    loop {
+        print!("{PROMPT}");
+        read_line(&mut line);
+        execute(line.as_str());
+    }
+}
+
+fn read_line(line: &mut LineBuffer) {
+    line.clear();
+
+    loop {
+        let Some(byte) = next_input_byte() else {
+            interrupts::wait_for_interrupt();
+            continue;
+        };
+
+        match byte {
+            b'\r' | b'\n' => {
+                println!();
+                return;
+            },
+            ASCII_BACKSPACE | ASCII_DELETE if line.pop() => print!("\x08 \x08"),
+            0x20..=0x7E if line.push(byte) => print!("{}", byte as char),
+            _ => {},
+        }
+    }
+}
+
+fn next_input_byte() -> Option<u8> {
+    if let Some(byte) = COM1.lock().try_read_byte() { return Some(byte); }
+
+    match input::next_event()? {
+        InputEvent::Keyboard(event) if event.state == KeyState::Pressed => {
+            if event.modifiers.shift() {
+                match event.key {
+                    KeyCode::PageUp => { logger::page_display_up(); return None; },
+                    KeyCode::PageDown => { logger::page_display_down(); return None; },
+                    _ => {},
+                }
+            }
+
+            let character = event.character?;
+            if character.is_ascii() { Some(character as u8) } else { None }
+        },
+        _ => None,
+    }
+}
+
+fn execute(line: &str) {
+    let mut arguments = line.split_whitespace();
+    let Some(name) = arguments.next() else { return };
+
+    match COMMANDS.iter().find(|command| command.name == name) {
+        Some(command) => (command.handler)(&mut arguments),
+        None => println!("Unknown command '{name}'. Type 'help' for a list of commands"),
+    }
+}
+
+fn help_command(_arguments: &mut SplitWhitespace) {
+    for command in COMMANDS.iter() {
+        println!("  {:<12} {}", command.name, command.description);
+        println!("  {:<12} Usage: {}", "", command.usage);
+    }
+}
+
+fn mem_command(_arguments: &mut SplitWhitespace) {
+    memory::print_memory_usage();
+}
+
+fn cpu_command(_arguments: &mut SplitWhitespace) {
+    let Some(info) = cpu::info() else {
+        println!("CPU information is not available");
+        return;
+    };
+
+    println!("  {} {}", info.vendor(), info.brand());
+    println!("  Family {:#X}, model {:#X}, stepping {}", info.family, info.model, info.stepping);
+    println!(
+        "  APIC ID {}, {} logical processors, {} threads per core",
+        info.topology.apic_id, info.topology.logical_processors, info.topology.threads_per_core
+    );
+    println!("  Features: {}", info.features);
+}
+
+fn acpi_command(_arguments: &mut SplitWhitespace) {
+    acpi::for_each_table(|table| {
+        let signature = table.get_signature_array();
+        let header = table.header();
+        println!(
+            "  {} at {:#X}, length {}, revision {}",
+            core::str::from_utf8(&signature).unwrap_or("????"),
+            table.physical_address(),
+            header.length(),
+            header.revision()
+        );
+    });
+}
+
+fn pci_command(_arguments: &mut SplitWhitespace) {
+    PCI_CONFIG_SPACE.lock().for_each_function(|function| {
+        println!(
+            "  {:02X}:{:02X}.{} {:04X}:{:04X} class {:02X}.{:02X}.{:02X} revision {:02X}",
+            function.address.bus,
+            function.address.device,
+            function.address.function,
+            function.vendor_id,
+            function.device_id,
+            function.class,
+            function.subclass,
+            function.prog_if,
+            function.revision
+        );
+    });
+}
+
+fn map_command(arguments: &mut SplitWhitespace) {
+    let Some(address) = arguments.next().and_then(parse_address) else {
+        println!("Usage: map <vaddr>");
+        return;
+    };
+
+    let virtual_address = VirtualAddress::new(address);
+    let walk = memory::active_page_table().walk(virtual_address);
+
+    println!("  Virtual address: {:#018X}", virtual_address.as_u64());
+    for (name, entry) in PAGE_TABLE_LEVEL_NAMES.iter().zip(walk.entries.iter()) {
+        let Some(entry) = entry else { break };
+        println!(
+            "  {name}: address {:#X}, flags {:#X}, present {}, writable {}, huge {}",
+            entry.address().as_u64(),
+            entry.flags(),
+            entry.present(),
+            entry.read_write(),
+            entry.page_size()
+        );
+    }
+
+    match walk.physical_address {
+        Some(physical_address) => println!("  Physical address: {physical_address:#X}"),
+        None => println!("  Not mapped"),
+    }
+}
+
+fn parse_address(text: &str) -> Option<u64> {
+    let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+    u64::from_str_radix(digits, 16).ok()
+}
+
+fn log_command(arguments: &mut SplitWhitespace) {
+    let (serial, display, level) = match (arguments.next(), arguments.next()) {
+        (None, _) => {
+            let (serial_level, display_level) = logger::get_min_log_levels();
+            println!("  Serial: {serial_level:?}, Display: {display_level:?}");
+            return;
+        },
+        (Some("serial"), Some(level)) => (true, false, level),
+        (Some("display"), Some(level)) => (false, true, level),
+        (Some(level), None) => (true, true, level),
+        _ => {
+            println!("Usage: log [serial|display] [debug|info|warn|error|critical]");
+            return;
+        },
+    };
+
+    let Some(level) = LogLevel::from_name(level) else {
+        println!("Unknown log level '{level}'");
+        return;
+    };
+
+    if serial { logger::set_min_serial_log_level(level); }
+    if display { logger::set_min_display_log_level(level); }
+}
+
+fn ls_command(arguments: &mut SplitWhitespace) {
+    let path = arguments.next().unwrap_or("/");
+    if let Some(entry) = initrd::find(path) {
+        if entry.kind != EntryKind::Directory {
+            println!("  {:<24} {} bytes", entry.file_name(), entry.data.len());
+            return;
+        }
+    }
+
+    let mut found = false;
+    let mounted = initrd::for_each_in_directory(path, |entry| {
+        found = true;
+        match entry.kind {
+            EntryKind::Directory => println!("  {}/", entry.file_name()),
+            EntryKind::File => println!("  {:<24} {} bytes", entry.file_name(), entry.data.len()),
+            EntryKind::Other => println!("  {}", entry.file_name()),
+        }
+    });
+
+    if !mounted {
+        println!("There is no initial ramdisk");
+    } else if !found && initrd::find(path).is_none() && !path.trim_matches('/').is_empty() {
+        println!("No such directory '{path}'");
+    }
+}
+
+fn cat_command(arguments: &mut SplitWhitespace) {
+    let Some(path) = arguments.next() else {
+        println!("Usage: cat <path>");
+        return;
+    };
+
+    match initrd::find(path) {
+        Some(entry) if entry.kind == EntryKind::File => match core::str::from_utf8(entry.data) {
+            Ok(text) => print!("{text}"),
+            Err(_) => println!("'{path}' is not a text file"),
+        },
+        Some(_) => println!("'{path}' is not a file"),
+        None => println!("No such file '{path}'"),
+    }
+}
+
+fn draw_command(_arguments: &mut SplitWhitespace) {
+    let drawn = logger::with_display(|display| {
+        // Keep to the top right corner so the prompt stays visible
+        let (width, _) = display.size();
+        let (x, y) = (width as isize - 220, 20);
+
+        display.fill_rect_clipped(x, y, 200, 200, Color::new(0x202020));
+        display.draw_rect(x, y, 200, 200, Color::new(0xFFFFFF));
+        display.draw_line((x, y), (x + 199, y + 199), Color::new(0xCC0000));
+        display.draw_line((x + 199, y), (x, y + 199), Color::new(0x00CC00));
+        display.fill_circle(x + 100, y + 100, 60, Color::new(0x0000EE));
+        display.draw_circle(x + 100, y + 100, 80, Color::new(0xCDCD00));
+
+        // A white bar fading in from transparent checks alpha blending
+        let mut overlay = Surface::new(160, 30);
+        for overlay_y in 0..overlay.height() {
+            for overlay_x in 0..overlay.width() {
+                let alpha = overlay_x * 255 / (overlay.width() - 1);
+                overlay.set_pixel_with_alpha(overlay_x, overlay_y, Color::new(0xFFFFFF), alpha as u8);
+            }
+        }
+        display.blit(&overlay, x + 20, y + 155);
+    });
+
+    if drawn.is_none() { println!("There is no display to draw on"); }
+}
+
+fn backtrace_command(_arguments: &mut SplitWhitespace) {
+    backtrace::print();
+}
+
+fn reboot_command(_arguments: &mut SplitWhitespace) {
+    println!("Rebooting...");
+
+    acpi::reset_system();
+
+    // Fall back to the keyboard controller if the ACPI reset did not work
+    if let Err(error) = unsafe { PS2_CONTROLLER.lock().pulse_reset_line() } {
+        println!("Could not reset the machine: {error:?}");
+    }
+}
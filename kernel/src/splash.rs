@@ -0,0 +1,83 @@
+use bootinfo::BootInfo;
+use spin::Mutex;
+
+use crate::{graphics_renderer::{Canvas, Color, Surface}, image, log_warn, logger};
+
+const BACKGROUND_COLOR: Color = Color::new(0x000000);
+const BAR_COLOR: Color = Color::new(0x00B000);
+const BAR_OUTLINE_COLOR: Color = Color::new(0x808080);
+const BAR_WIDTH: usize = 320;
+const BAR_HEIGHT: usize = 12;
+/// Space between the logo and the progress bar
+const BAR_MARGIN: usize = 24;
+/// The boot module holding the logo image
+const LOGO_MODULE: &str = "logo";
+
+/// Where the progress bar is on screen
+struct Splash {
+    bar_x: isize,
+    bar_y: isize,
+}
+
+static SPLASH: Mutex<Option<Splash>> = Mutex::new(None);
+
+/// Hide the console and show the boot logo centered above an empty progress bar
+///
+/// The logo is left out if it is missing or can't be decoded.
+pub fn show(bootinfo: &BootInfo) {
+    let logo = load_logo(bootinfo);
+    logger::set_console_visible(false);
+
+    let splash = logger::with_display(|display| {
+        let (width, height) = display.size();
+        let (logo_width, logo_height) = logo.as_ref().map_or((0, 0), |logo| (logo.width(), logo.height()));
+
+        // Center the logo and bar together as one block
+        let block_height = logo_height + BAR_MARGIN + BAR_HEIGHT;
+        let top = (height as isize - block_height as isize) / 2;
+
+        display.fill(BACKGROUND_COLOR);
+        if let Some(logo) = &logo {
+            display.blit(logo, (width as isize - logo_width as isize) / 2, top);
+        }
+
+        let splash = Splash {
+            bar_x: (width as isize - BAR_WIDTH as isize) / 2,
+            bar_y: top + (logo_height + BAR_MARGIN) as isize,
+        };
+        display.draw_rect(splash.bar_x, splash.bar_y, BAR_WIDTH, BAR_HEIGHT, BAR_OUTLINE_COLOR);
+        splash
+    });
+
+    *SPLASH.lock() = splash;
+}
+
+/// Fill the progress bar to show how many of the boot steps are done
+pub fn set_progress(completed: usize, total: usize) {
+    let splash = SPLASH.lock();
+    let Some(splash) = splash.as_ref() else { return };
+
+    // Leave a one pixel gap inside the outline
+    let filled_width = (BAR_WIDTH - 4) * completed.min(total) / total.max(1);
+    logger::with_display(|display| {
+        display.fill_rect_clipped(splash.bar_x + 2, splash.bar_y + 2, filled_width, BAR_HEIGHT - 4, BAR_COLOR);
+    });
+}
+
+/// Remove the splash and bring back the console
+pub fn finish() {
+    if SPLASH.lock().take().is_none() { return; }
+    logger::set_console_visible(true);
+}
+
+fn load_logo(bootinfo: &BootInfo) -> Option<Surface> {
+    let logo = bootinfo.modules.find(LOGO_MODULE)?;
+
+    match image::decode(unsafe { logo.data() }) {
+        Ok(logo) => Some(logo),
+        Err(error) => {
+            log_warn!("Splash", "Could not decode the boot logo: {error:?}");
+            None
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use bootinfo::BootInfo;
+use elf::{ElfSymbol64, ElfSymbolTable, ElfSymbolType};
+use spin::Mutex;
+
+use crate::log_warn;
+
+pub use self::demangle::Demangle;
+
+mod demangle;
+
+/// A function containing an address
+#[derive(Debug, Clone, Copy)]
+pub struct Symbol {
+    /// The mangled name, which can be shown with Demangle
+    pub name: &'static str,
+    /// How far into the function the address is
+    pub offset: u64,
+}
+
+/// The kernel's symbol table and how far the kernel was moved from the addresses in it
+#[derive(Clone, Copy)]
+struct KernelSymbolTable {
+    table: ElfSymbolTable<'static>,
+    slide: u64,
+}
+
+static SYMBOLS: Mutex<Option<KernelSymbolTable>> = Mutex::new(None);
+
+/// Use the symbol tables loaded by the bootloader to name addresses
+pub fn initialize(bootinfo: &BootInfo) {
+    let kernel_symbols = bootinfo.kernel_symbols;
+    if !kernel_symbols.is_loaded() {
+        log_warn!("Symbols", "The bootloader did not load the kernel symbols, backtraces will not have names");
+        return;
+    }
+
+    // Safety: The bootloader maps both tables into kernel space and they are never freed
+    let table = unsafe {
+        ElfSymbolTable::new(
+            core::slice::from_raw_parts(
+                kernel_symbols.symbol_table.as_u64() as *const u8,
+                kernel_symbols.symbol_table_size
+            ),
+            core::slice::from_raw_parts(
+                kernel_symbols.string_table.as_u64() as *const u8,
+                kernel_symbols.string_table_size
+            ),
+        )
+    };
+    *SYMBOLS.lock() = Some(KernelSymbolTable { table, slide: bootinfo.kernel_slide });
+}
+
+/// Find the function an address is in
+///
+/// If no function covers the address the closest one before it is used, in case it was missing a size.
+pub fn lookup(address: u64) -> Option<Symbol> {
+    // Never wait here so a panic while the table is locked can still print a backtrace
+    let KernelSymbolTable { table, slide } = (*SYMBOLS.try_lock()?)?;
+    let address = address.wrapping_sub(slide);
+
+    if let Some(symbol) = table.iter().find(|symbol| is_function(symbol) && symbol.contains(address)) {
+        return Some(Symbol { name: table.name(&symbol).ok()?, offset: address - symbol.st_value });
+    }
+
+    let symbol = table.iter()
+        .filter(|symbol| is_function(symbol) && symbol.st_value <= address)
+        .max_by_key(|symbol| symbol.st_value)?;
+    Some(Symbol { name: table.name(&symbol).ok()?, offset: address - symbol.st_value })
+}
+
+fn is_function(symbol: &ElfSymbol64) -> bool {
+    symbol.symbol_type() == ElfSymbolType::Func
+}
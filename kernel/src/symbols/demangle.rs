@@ -0,0 +1,115 @@
+use core::fmt;
+
+/// Escapes used in legacy Rust symbol names for characters that can't be in a symbol
+const ESCAPES: [(&str, &str); 8] = [
+    ("$SP$", "@"),
+    ("$BP$", "*"),
+    ("$RF$", "&"),
+    ("$LT$", "<"),
+    ("$GT$", ">"),
+    ("$LP$", "("),
+    ("$RP$", ")"),
+    ("$C$", ","),
+];
+
+/// Shows a legacy mangled Rust symbol as its path, such as `kernel::shell::run`
+///
+/// The kernel is built with `-C symbol-mangling-version=legacy` for this. Names that aren't legacy
+/// mangled are shown unchanged.
+pub struct Demangle<'a>(pub &'a str);
+
+impl fmt::Display for Demangle<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match path_components(self.0) {
+            Some(components) => write_path(f, components),
+            None => f.write_str(self.0),
+        }
+    }
+}
+
+/// Get the path components of a name in the form `_ZN<length><name>...E`, checking they are all valid
+///
+/// Anything after the final E, such as a ".llvm.1234" suffix added by LLVM, is ignored.
+fn path_components(name: &str) -> Option<PathComponents<'_>> {
+    let components = PathComponents { remaining: name.strip_prefix("_ZN")? };
+
+    let mut check = PathComponents { remaining: components.remaining };
+    while check.next()?.is_some() {}
+    Some(components)
+}
+
+struct PathComponents<'a> {
+    remaining: &'a str,
+}
+
+impl<'a> PathComponents<'a> {
+    /// Returns None if the name is invalid and Some(None) at the end
+    fn next(&mut self) -> Option<Option<&'a str>> {
+        if self.remaining.starts_with('E') { return Some(None); }
+
+        let digits = self.remaining.find(|c: char| !c.is_ascii_digit())?;
+        let length: usize = self.remaining[..digits].parse().ok()?;
+        let component = self.remaining.get(digits..digits + length)?;
+        self.remaining = &self.remaining[digits + length..];
+        Some(Some(component))
+    }
+}
+
+fn write_path(f: &mut fmt::Formatter<'_>, mut components: PathComponents) -> fmt::Result {
+    let mut first = true;
+    while let Some(Some(component)) = components.next() {
+        // The last component is a hash of the crate that makes the name unique
+        if components.remaining.starts_with('E') && is_hash(component) { break; }
+
+        if !first { f.write_str("::")?; }
+        first = false;
+        write_component(f, component)?;
+    }
+
+    Ok(())
+}
+
+fn is_hash(component: &str) -> bool {
+    component.len() == 17 && component.starts_with('h') && component[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Write a path component, turning escapes such as `$LT$` and `$u20$` back into characters
+fn write_component(f: &mut fmt::Formatter<'_>, component: &str) -> fmt::Result {
+    // Components that would start with an escape begin with an underscore
+    let mut rest = if component.starts_with("_$") { &component[1..] } else { component };
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("..") {
+            f.write_str("::")?;
+            rest = after;
+            continue;
+        }
+
+        if rest.starts_with('$') {
+            if let Some((escape, replacement)) = ESCAPES.iter().find(|(escape, _)| rest.starts_with(escape)) {
+                f.write_str(replacement)?;
+                rest = &rest[escape.len()..];
+                continue;
+            }
+            if let Some((character, length)) = unicode_escape(rest) {
+                fmt::Write::write_char(f, character)?;
+                rest = &rest[length..];
+                continue;
+            }
+        }
+
+        let length = rest.chars().next().map_or(1, char::len_utf8);
+        f.write_str(&rest[..length])?;
+        rest = &rest[length..];
+    }
+
+    Ok(())
+}
+
+/// Read an escape such as `$u20$`, returning the character and the length of the escape
+fn unicode_escape(text: &str) -> Option<(char, usize)> {
+    let hex = text.strip_prefix("$u")?;
+    let end = hex.find('$')?;
+    let character = char::from_u32(u32::from_str_radix(&hex[..end], 16).ok()?)?;
+    Some((character, end + 3))
+}
@@ -0,0 +1,103 @@
+use alloc::{alloc::alloc_zeroed, boxed::Box};
+use core::{alloc::Layout, arch::asm, mem::size_of, ptr};
+
+use bootinfo::{BootInfo, TlsTemplate};
+use spin::Mutex;
+use x86_64_hardware::{memory::VirtualAddress, registers::{FsBase, GsBase}};
+
+use crate::{errors::{Error, ErrorStatus}, log_error, log_info};
+
+/// A thread local with a known value, read back to check the TLS block was set up correctly
+const TLS_CHECK_VALUE: u64 = 0x544C_535F_4F4B_2121;
+
+#[thread_local]
+static TLS_CHECK: u64 = TLS_CHECK_VALUE;
+
+/// The template from the bootloader, kept so every CPU can create its own TLS block from it
+static TEMPLATE: Mutex<Option<TlsTemplate>> = Mutex::new(None);
+
+/// Data each CPU keeps for itself, found through GS base
+#[repr(C)]
+pub struct PerCpu {
+    /// Points back at this structure so it can be found by reading gs:0
+    self_pointer: *const PerCpu,
+    pub cpu_index: usize,
+    /// The address FS base is set to, just past the end of this CPU's TLS data
+    pub thread_pointer: u64,
+}
+
+/// Set up thread local storage for the boot CPU. The heap must be initialized first
+pub fn initialize(bootinfo: &BootInfo) {
+    *TEMPLATE.lock() = Some(bootinfo.tls_template);
+    if !bootinfo.tls_template.is_present() {
+        log_info!("TLS", "The kernel has no thread locals");
+    }
+
+    if let Err(error) = initialize_cpu(0) {
+        log_error!("TLS", "Could not create the TLS block for the boot CPU: {error:?}");
+        return;
+    }
+
+    // Read through a pointer so the compiler can't use the value it knows the thread local starts with
+    // Safety: The thread local is valid for as long as this CPU's TLS block, which is never freed
+    let check = unsafe { ptr::read_volatile(ptr::addr_of!(TLS_CHECK)) };
+    if check != TLS_CHECK_VALUE {
+        log_error!("TLS", "Thread local has the wrong value {check:#X}");
+    }
+    if let Some(cpu) = current() {
+        log_info!("TLS", "CPU {} thread pointer at {:#X}", cpu.cpu_index, cpu.thread_pointer);
+    }
+}
+
+/// Create a TLS block and per CPU data for the CPU this runs on and point FS and GS base at them
+///
+/// The block uses the x86_64 layout where thread locals sit just below the thread pointer, which
+/// points at itself so the compiler can read it from fs:0.
+pub fn initialize_cpu(cpu_index: usize) -> Result<(), Error> {
+    let template = (*TEMPLATE.lock()).unwrap_or_default();
+    let alignment = template.alignment.max(1);
+    if !alignment.is_power_of_two() { return Err(Error::new(ErrorStatus::InvalidFileFormat)); }
+
+    // Thread local offsets from the thread pointer are calculated from the size rounded up to the
+    // template's alignment. Padding in front keeps the thread pointer itself 8 byte aligned
+    let data_size = template.memory_size.next_multiple_of(alignment);
+    let padding = data_size.next_multiple_of(size_of::<u64>()) - data_size;
+    let layout = Layout::from_size_align(padding + data_size + size_of::<u64>(), alignment.max(size_of::<u64>()))
+        .map_err(|_| Error::new(ErrorStatus::InvalidFileFormat))?;
+
+    // Safety: The layout is never zero sized because it always has room for the thread pointer
+    let block = unsafe { alloc_zeroed(layout) };
+    if block.is_null() { return Err(Error::new(ErrorStatus::OutOfMemory)); }
+
+    // Safety: The block has room for the template and the thread pointer, and the template is part of
+    //         the kernel image which stays mapped. .tbss is already zero because the block was zeroed
+    let thread_pointer = unsafe {
+        let data = block.add(padding);
+        ptr::copy_nonoverlapping(template.address.as_u64() as *const u8, data, template.file_size);
+
+        let thread_pointer = data.add(data_size) as *mut u64;
+        thread_pointer.write(thread_pointer as u64);
+        thread_pointer as u64
+    };
+
+    let per_cpu = Box::leak(Box::new(PerCpu { self_pointer: ptr::null(), cpu_index, thread_pointer }));
+    per_cpu.self_pointer = per_cpu;
+
+    // Safety: Both bases point at memory that is never freed
+    unsafe {
+        FsBase::write(VirtualAddress::new(thread_pointer));
+        GsBase::write(VirtualAddress::new(per_cpu as *const PerCpu as u64));
+    }
+    Ok(())
+}
+
+/// The per CPU data of the CPU this runs on, or None before initialize_cpu has run on it
+pub fn current() -> Option<&'static PerCpu> {
+    let per_cpu: *const PerCpu;
+    // Safety: GS base is 0 until initialize_cpu sets it, so check before reading through it
+    unsafe {
+        if GsBase::read().as_u64() == 0 { return None; }
+        asm!("mov {}, gs:0", out(reg) per_cpu, options(nostack, preserves_flags, readonly));
+        Some(&*per_cpu)
+    }
+}
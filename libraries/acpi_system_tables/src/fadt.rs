@@ -0,0 +1,124 @@
+use core::mem::offset_of;
+
+use crate::{SignatureType, SystemDescriptionTable, SystemDescriptionTableHeader};
+
+/// IA-PC boot architecture flag indicating that the motherboard contains an 8042 controller
+const BOOT_ARCH_8042: u16 = 1 << 1;
+
+/// The first FADT revision where the IA-PC boot architecture flags are defined (ACPI 2.0)
+const BOOT_ARCH_MIN_REVISION: u8 = 3;
+
+/// A Generic Address Structure describing a register location
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct GenericAddress {
+    pub address_space: u8,
+    pub bit_width: u8,
+    pub bit_offset: u8,
+    pub access_size: u8,
+    pub address: u64,
+}
+
+#[repr(C, packed)]
+struct FixedAcpiDescriptionTableInternal {
+    header: SystemDescriptionTableHeader,
+    firmware_ctrl: u32,
+    dsdt: u32,
+    reserved: u8,
+    preferred_pm_profile: u8,
+    sci_int: u16,
+    smi_cmd: u32,
+    acpi_enable: u8,
+    acpi_disable: u8,
+    s4bios_req: u8,
+    pstate_cnt: u8,
+    pm1a_evt_blk: u32,
+    pm1b_evt_blk: u32,
+    pm1a_cnt_blk: u32,
+    pm1b_cnt_blk: u32,
+    pm2_cnt_blk: u32,
+    pm_tmr_blk: u32,
+    gpe0_blk: u32,
+    gpe1_blk: u32,
+    pm1_evt_len: u8,
+    pm1_cnt_len: u8,
+    pm2_cnt_len: u8,
+    pm_tmr_len: u8,
+    gpe0_blk_len: u8,
+    gpe1_blk_len: u8,
+    gpe1_base: u8,
+    cst_cnt: u8,
+    p_lvl2_lat: u16,
+    p_lvl3_lat: u16,
+    flush_size: u16,
+    flush_stride: u16,
+    duty_offset: u8,
+    duty_width: u8,
+    day_alrm: u8,
+    mon_alrm: u8,
+    century: u8,
+    iapc_boot_arch: u16,
+    reserved2: u8,
+    flags: u32,
+    reset_reg: GenericAddress,
+    reset_value: u8,
+}
+
+/// The Fixed ACPI Description Table (signature FACP)
+pub struct FixedAcpiDescriptionTable {
+    fadt_ptr: *const FixedAcpiDescriptionTableInternal,
+}
+
+impl FixedAcpiDescriptionTable {
+    /// Interpret a system description table as the FADT
+    ///
+    /// Returns None if the table does not have the FACP signature
+    pub fn from_table(table: &SystemDescriptionTable) -> Option<FixedAcpiDescriptionTable> {
+        if table.get_signature() != SignatureType::FACP { return None; }
+
+        Some(FixedAcpiDescriptionTable {
+            fadt_ptr: table.as_ptr() as *const FixedAcpiDescriptionTableInternal
+        })
+    }
+
+    pub fn revision(&self) -> u8 { self.header().revision() }
+
+    pub fn dsdt_address(&self) -> u32 { unsafe { (*self.fadt_ptr).dsdt } }
+
+    pub fn iapc_boot_arch(&self) -> u16 {
+        if !self.has_field(offset_of!(FixedAcpiDescriptionTableInternal, iapc_boot_arch), size_of::<u16>()) { return 0; }
+        unsafe { (*self.fadt_ptr).iapc_boot_arch }
+    }
+
+    pub fn flags(&self) -> u32 {
+        if !self.has_field(offset_of!(FixedAcpiDescriptionTableInternal, flags), size_of::<u32>()) { return 0; }
+        unsafe { (*self.fadt_ptr).flags }
+    }
+
+    /// Whether the motherboard has an 8042 PS/2 controller
+    ///
+    /// Tables older than ACPI 2.0 do not define this flag so the controller is assumed
+    /// to be present for them, matching legacy PC behaviour.
+    pub fn has_8042_controller(&self) -> bool {
+        if self.revision() < BOOT_ARCH_MIN_REVISION { return true; }
+        self.iapc_boot_arch() & BOOT_ARCH_8042 != 0
+    }
+
+    /// The reset register and the value to write to it if the table provides one
+    pub fn reset_register(&self) -> Option<(GenericAddress, u8)> {
+        if self.revision() < BOOT_ARCH_MIN_REVISION { return None; }
+        if !self.has_field(offset_of!(FixedAcpiDescriptionTableInternal, reset_value), size_of::<u8>()) { return None; }
+
+        unsafe { Some(((*self.fadt_ptr).reset_reg, (*self.fadt_ptr).reset_value)) }
+    }
+
+    fn header(&self) -> &SystemDescriptionTableHeader {
+        // The header is the first field so the table pointer is also a header pointer
+        unsafe { &*(self.fadt_ptr as *const SystemDescriptionTableHeader) }
+    }
+
+    /// Check that the table is long enough to contain a field
+    fn has_field(&self, offset: usize, size: usize) -> bool {
+        self.header().length() as usize >= offset + size
+    }
+}
@@ -1,10 +1,12 @@
 #![no_std]
 
+mod fadt;
 mod rsdp;
 mod rsdt;
 mod system_description_table;
 mod xsdt;
 
+pub use fadt::*;
 pub use rsdp::*;
 pub use rsdt::*;
 pub use system_description_table::*;
@@ -17,6 +17,9 @@ impl RsdpV1 {
         self.valid_signature() && self.valid_checksum()
     }
 
+    /// Revision 0 is ACPI 1.0. Revision 2 and above mean the structure is actually an RsdpV2
+    pub fn revision(&self) -> u8 { self.revision }
+
     pub fn get_rsdt(&self, offset: u64) -> RootSystemDescriptionTable {
         return unsafe { RootSystemDescriptionTable::new(self.rsdt_physical_address, offset) };
     }
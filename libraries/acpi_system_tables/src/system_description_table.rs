@@ -175,6 +175,8 @@ pub struct SystemDescriptionTableHeader {
 
 impl SystemDescriptionTableHeader {
     pub fn length(&self) -> u32 { self.length }
+
+    pub fn revision(&self) -> u8 { self.revision }
 }
 
 pub struct SystemDescriptionTable {
@@ -191,6 +193,16 @@ impl SystemDescriptionTable {
         }
     }
 
+    pub fn header(&self) -> &SystemDescriptionTableHeader {
+        unsafe { &*self.std_ptr }
+    }
+
+    /// The physical address the table was found at
+    pub fn physical_address(&self) -> u64 { self.std_ptr as u64 - self.mem_offset }
+
+    /// Get a pointer to the start of the table
+    pub(crate) fn as_ptr(&self) -> *const u8 { self.std_ptr as *const u8 }
+
     pub fn get_signature_array(&self) -> [u8;4] {
         unsafe { (*self.std_ptr).signature }
     }
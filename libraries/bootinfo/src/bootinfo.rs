@@ -2,8 +2,12 @@
 
 use x86_64_hardware::memory::VirtualAddress;
 
+use crate::command_line::CommandLine;
 use crate::framebuffer::FrameBuffer;
+use crate::kernel_symbols::KernelSymbols;
 use crate::meminfo::MemInfo;
+use crate::modules::BootModuleList;
+use crate::tls_template::TlsTemplate;
 
 // This could be changed to something more significant like the bootloader name
 const BOOTINFO_MAGIC: [u8; 4] = [b'B', b'O', b'O', b'T'];
@@ -15,10 +19,20 @@ pub struct BootInfo {
     magic: [u8; 4],  
     pub framebuffer: FrameBuffer,
     pub page_table_memory_offset: u64,
+    /// How far the kernel was moved from the address it was linked at. Symbol values need this added
+    pub kernel_slide: u64,
     pub next_availiable_kernel_page: VirtualAddress,
     pub meminfo: MemInfo,
-    pub font_file_address: VirtualAddress,
-    pub font_file_size: usize,
+    /// Files such as fonts and images loaded for the kernel
+    pub modules: BootModuleList,
+    /// Physical address of the ACPI RSDP or 0 if the firmware did not provide one
+    pub acpi_rsdp_address: u64,
+    /// Options for the kernel from the boot config or the firmware's load options
+    pub command_line: CommandLine,
+    /// The kernel's symbols for naming addresses in backtraces
+    pub kernel_symbols: KernelSymbols,
+    /// The template each CPU's thread local storage is created from
+    pub tls_template: TlsTemplate,
 }
 
 impl BootInfo {
@@ -31,10 +45,14 @@ impl Default for BootInfo {
             magic: BOOTINFO_MAGIC,
             framebuffer: FrameBuffer::default(),
             page_table_memory_offset: 0,
+            kernel_slide: 0,
             next_availiable_kernel_page: VirtualAddress::new(0),
             meminfo: MemInfo::default(),
-            font_file_address: VirtualAddress::new(0),
-            font_file_size: 0,
+            modules: BootModuleList::default(),
+            acpi_rsdp_address: 0,
+            command_line: CommandLine::default(),
+            kernel_symbols: KernelSymbols::default(),
+            tls_template: TlsTemplate::default(),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,39 @@
+/// The longest command line that can be passed to the kernel
+pub const MAX_COMMAND_LINE_LENGTH: usize = 256;
+
+/// Options for the kernel as space separated words, such as `loglevel=warn serial=off`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CommandLine {
+    text: [u8; MAX_COMMAND_LINE_LENGTH],
+    length: usize,
+}
+
+impl CommandLine {
+    /// Returns None if the text is longer than MAX_COMMAND_LINE_LENGTH
+    pub fn new(text: &str) -> Option<CommandLine> {
+        if text.len() > MAX_COMMAND_LINE_LENGTH { return None; }
+
+        let mut command_line = CommandLine {
+            text: [0; MAX_COMMAND_LINE_LENGTH],
+            length: text.len(),
+        };
+        command_line.text[..text.len()].copy_from_slice(text.as_bytes());
+
+        Some(command_line)
+    }
+
+    pub fn as_str(&self) -> &str {
+        // The text was copied from a str so it is always valid
+        core::str::from_utf8(&self.text[..self.length]).unwrap_or("")
+    }
+}
+
+impl Default for CommandLine {
+    fn default() -> CommandLine {
+        CommandLine {
+            text: [0; MAX_COMMAND_LINE_LENGTH],
+            length: 0,
+        }
+    }
+}
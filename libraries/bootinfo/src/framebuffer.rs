@@ -1,5 +1,29 @@
 use x86_64_hardware::memory::PhysicalAddress;
 
+/// How the color channels are laid out in each 32 bit pixel, matching the GOP pixel formats
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Red in the lowest byte, then green and blue
+    Rgb = 0,
+    /// Blue in the lowest byte, then green and red
+    Bgr = 1,
+    /// The channels are described by the frame buffer's pixel bitmask
+    Bitmask = 2,
+    /// There is no linear frame buffer and the screen can only be drawn to through GOP Blt
+    BltOnly = 3,
+}
+
+/// The bits used by each color channel when the pixel format is Bitmask
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PixelBitmask {
+    pub red_mask: u32,
+    pub green_mask: u32,
+    pub blue_mask: u32,
+    pub reserved_mask: u32,
+}
+
 /// Represents a frame buffer which can be drawn to
 #[repr(C)]
 pub struct FrameBuffer {
@@ -8,6 +32,8 @@ pub struct FrameBuffer {
     pub width: u32,
     pub height: u32,
     pub pixels_per_scan_line: u32,
+    pub pixel_format: PixelFormat,
+    pub pixel_bitmask: PixelBitmask,
 }
 
 impl FrameBuffer {
@@ -18,16 +44,18 @@ impl FrameBuffer {
     /// is used.
     /// 
     /// The buffer size must be at least as big as pixels_per_scan_line * height * 4 (size of u32) or 
-    /// a FrameBufferError will be returned
+    /// a FrameBufferError will be returned. BltOnly frame buffers have no memory so are not checked.
     pub fn new(
         base_address: PhysicalAddress, 
         buffer_size: usize, 
         width: u32, height: u32, 
-        pixels_per_scan_line: u32
+        pixels_per_scan_line: u32,
+        pixel_format: PixelFormat,
+        pixel_bitmask: PixelBitmask,
     ) -> Result<FrameBuffer, FrameBufferError> {
         // Check to ensure that the buffer is big enough to avoid errors later
         let required_size = pixels_per_scan_line as usize * height as usize * size_of::<u32>();
-        if buffer_size < required_size {
+        if pixel_format != PixelFormat::BltOnly && buffer_size < required_size {
             return Err(FrameBufferError::SizeTooSmall { required: required_size, available: buffer_size });
         }
 
@@ -37,13 +65,20 @@ impl FrameBuffer {
             width,
             height,
             pixels_per_scan_line,
+            pixel_format,
+            pixel_bitmask,
         })
     }
 
-    /// Fill the buffer with the given color
+    /// Whether the frame buffer memory can be written to directly
+    pub fn is_linear(&self) -> bool { self.pixel_format != PixelFormat::BltOnly }
+
+    /// Fill the buffer with the given raw pixel value. Does nothing if there is no linear frame buffer
     /// 
     /// Safety Conditions: The caller must ensure the frame buffer memory is reserved for the frame buffer alone
     pub unsafe fn fill(&self, color: u32, memory_offset: u64) {
+        if !self.is_linear() { return; }
+
         let virt_addr = self.base_address.get_virtual_address_at_offset(memory_offset);
         let first_pixel = virt_addr.get_mut_ptr::<u32>();
         for x_pos in 0..self.width {
@@ -72,6 +107,8 @@ impl Default for FrameBuffer {
             width: 0,
             height: 0,
             pixels_per_scan_line: 0,
+            pixel_format: PixelFormat::BltOnly,
+            pixel_bitmask: PixelBitmask::default(),
         }
     }
 }
@@ -0,0 +1,29 @@
+use x86_64_hardware::memory::VirtualAddress;
+
+/// The kernel's symbol table and the string table holding the symbol names
+///
+/// Both are copied from the kernel ELF file and mapped into kernel space so the kernel can name
+/// addresses in backtraces. The sizes are 0 if the bootloader could not load them.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct KernelSymbols {
+    pub symbol_table: VirtualAddress,
+    pub symbol_table_size: usize,
+    pub string_table: VirtualAddress,
+    pub string_table_size: usize,
+}
+
+impl KernelSymbols {
+    pub fn is_loaded(&self) -> bool { self.symbol_table_size != 0 && self.string_table_size != 0 }
+}
+
+impl Default for KernelSymbols {
+    fn default() -> KernelSymbols {
+        KernelSymbols {
+            symbol_table: VirtualAddress::new(0),
+            symbol_table_size: 0,
+            string_table: VirtualAddress::new(0),
+            string_table_size: 0,
+        }
+    }
+}
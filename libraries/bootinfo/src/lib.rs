@@ -1,8 +1,16 @@
 #![no_std]
 mod bootinfo;
+mod command_line;
 mod framebuffer;
+mod kernel_symbols;
 mod meminfo;
+mod modules;
+mod tls_template;
 
 pub use bootinfo::*;
-pub use framebuffer::FrameBuffer;
-pub use meminfo::MemInfo;
\ No newline at end of file
+pub use command_line::{CommandLine, MAX_COMMAND_LINE_LENGTH};
+pub use framebuffer::{FrameBuffer, PixelBitmask, PixelFormat};
+pub use kernel_symbols::KernelSymbols;
+pub use meminfo::MemInfo;
+pub use modules::{BootModule, BootModuleList, MAX_BOOT_MODULES, MAX_MODULE_NAME_LENGTH};
+pub use tls_template::TlsTemplate;
\ No newline at end of file
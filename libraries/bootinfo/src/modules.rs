@@ -0,0 +1,95 @@
+use x86_64_hardware::memory::VirtualAddress;
+
+/// The most files the bootloader can pass to the kernel
+pub const MAX_BOOT_MODULES: usize = 16;
+/// The longest module name that can be stored
+pub const MAX_MODULE_NAME_LENGTH: usize = 64;
+
+/// A file loaded by the bootloader and mapped into kernel space
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct BootModule {
+    name: [u8; MAX_MODULE_NAME_LENGTH],
+    name_length: usize,
+    pub virtual_address: VirtualAddress,
+    pub size: usize,
+}
+
+impl BootModule {
+    /// Returns None if the name is longer than MAX_MODULE_NAME_LENGTH
+    pub fn new(name: &str, virtual_address: VirtualAddress, size: usize) -> Option<BootModule> {
+        if name.len() > MAX_MODULE_NAME_LENGTH { return None; }
+
+        let mut module = BootModule {
+            name: [0; MAX_MODULE_NAME_LENGTH],
+            name_length: name.len(),
+            virtual_address,
+            size,
+        };
+        module.name[..name.len()].copy_from_slice(name.as_bytes());
+
+        Some(module)
+    }
+
+    pub fn name(&self) -> &str {
+        // The name was copied from a str so it is always valid
+        core::str::from_utf8(&self.name[..self.name_length]).unwrap_or("")
+    }
+
+    /// Get the contents of the module
+    ///
+    /// ## Safety
+    /// The module must be mapped at its virtual address, which is true in the kernel
+    pub unsafe fn data(&self) -> &'static [u8] {
+        unsafe { core::slice::from_raw_parts(self.virtual_address.as_u64() as *const u8, self.size) }
+    }
+}
+
+impl Default for BootModule {
+    fn default() -> BootModule {
+        BootModule {
+            name: [0; MAX_MODULE_NAME_LENGTH],
+            name_length: 0,
+            virtual_address: VirtualAddress::new(0),
+            size: 0,
+        }
+    }
+}
+
+/// The files loaded by the bootloader for the kernel
+#[repr(C)]
+pub struct BootModuleList {
+    modules: [BootModule; MAX_BOOT_MODULES],
+    count: usize,
+}
+
+impl BootModuleList {
+    /// Add a module to the list
+    ///
+    /// Returns the module back if the list is full
+    pub fn push(&mut self, module: BootModule) -> Result<(), BootModule> {
+        if self.count == MAX_BOOT_MODULES { return Err(module); }
+
+        self.modules[self.count] = module;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Find a module by name
+    pub fn find(&self, name: &str) -> Option<&BootModule> {
+        self.iter().find(|module| module.name() == name)
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, BootModule> {
+        self.modules[..self.count].iter()
+    }
+}
+
+impl Default for BootModuleList {
+    fn default() -> BootModuleList {
+        BootModuleList {
+            modules: [BootModule::default(); MAX_BOOT_MODULES],
+            count: 0,
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use x86_64_hardware::memory::VirtualAddress;
+
+/// The initial contents of the kernel's thread local storage, from its PT_TLS segment
+///
+/// The kernel copies file_size bytes from address into each CPU's TLS block and zeroes the rest up to
+/// memory_size. The template lies inside the loaded kernel image. memory_size is 0 if the kernel has
+/// no thread locals.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TlsTemplate {
+    pub address: VirtualAddress,
+    pub file_size: usize,
+    pub memory_size: usize,
+    pub alignment: usize,
+}
+
+impl TlsTemplate {
+    pub fn is_present(&self) -> bool { self.memory_size != 0 }
+}
+
+impl Default for TlsTemplate {
+    fn default() -> TlsTemplate {
+        TlsTemplate {
+            address: VirtualAddress::new(0),
+            file_size: 0,
+            memory_size: 0,
+            alignment: 1,
+        }
+    }
+}
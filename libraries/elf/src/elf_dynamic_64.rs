@@ -0,0 +1,162 @@
+use crate::read_struct;
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ElfDynamicTag {
+    Unknown,
+    Null,
+    Needed,
+    PltRelSz,
+    PltGot,
+    Hash,
+    StrTab,
+    SymTab,
+    Rela,
+    RelaSz,
+    RelaEnt,
+    StrSz,
+    SymEnt,
+    Init,
+    Fini,
+    SoName,
+    RPath,
+    Symbolic,
+    Rel,
+    RelSz,
+    RelEnt,
+    PltRel,
+    Debug,
+    TextRel,
+    JmpRel,
+    BindNow,
+    InitArray,
+    FiniArray,
+    InitArraySz,
+    FiniArraySz,
+    RunPath,
+    Flags,
+    GnuHash,
+    RelaCount,
+    RelCount,
+    Flags1,
+}
+
+/// An entry in the dynamic section
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ElfDynamic64 {
+    d_tag: i64,
+    /// A value or address depending on the tag
+    pub d_val: u64,
+}
+
+impl ElfDynamic64 {
+    pub fn d_tag(&self) -> ElfDynamicTag {
+        match self.d_tag {
+            0 => ElfDynamicTag::Null,
+            1 => ElfDynamicTag::Needed,
+            2 => ElfDynamicTag::PltRelSz,
+            3 => ElfDynamicTag::PltGot,
+            4 => ElfDynamicTag::Hash,
+            5 => ElfDynamicTag::StrTab,
+            6 => ElfDynamicTag::SymTab,
+            7 => ElfDynamicTag::Rela,
+            8 => ElfDynamicTag::RelaSz,
+            9 => ElfDynamicTag::RelaEnt,
+            10 => ElfDynamicTag::StrSz,
+            11 => ElfDynamicTag::SymEnt,
+            12 => ElfDynamicTag::Init,
+            13 => ElfDynamicTag::Fini,
+            14 => ElfDynamicTag::SoName,
+            15 => ElfDynamicTag::RPath,
+            16 => ElfDynamicTag::Symbolic,
+            17 => ElfDynamicTag::Rel,
+            18 => ElfDynamicTag::RelSz,
+            19 => ElfDynamicTag::RelEnt,
+            20 => ElfDynamicTag::PltRel,
+            21 => ElfDynamicTag::Debug,
+            22 => ElfDynamicTag::TextRel,
+            23 => ElfDynamicTag::JmpRel,
+            24 => ElfDynamicTag::BindNow,
+            25 => ElfDynamicTag::InitArray,
+            26 => ElfDynamicTag::FiniArray,
+            27 => ElfDynamicTag::InitArraySz,
+            28 => ElfDynamicTag::FiniArraySz,
+            29 => ElfDynamicTag::RunPath,
+            30 => ElfDynamicTag::Flags,
+            0x6ffffef5 => ElfDynamicTag::GnuHash,
+            0x6ffffff9 => ElfDynamicTag::RelaCount,
+            0x6ffffffa => ElfDynamicTag::RelCount,
+            0x6ffffffb => ElfDynamicTag::Flags1,
+            _ => ElfDynamicTag::Unknown,
+        }
+    }
+}
+
+/// The contents of a PT_DYNAMIC segment
+#[derive(Clone, Copy)]
+pub struct ElfDynamicTable<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ElfDynamicTable<'a> {
+    pub fn new(data: &'a [u8]) -> ElfDynamicTable<'a> {
+        ElfDynamicTable { data }
+    }
+
+    /// Iterate over the entries up to the terminating Null entry
+    pub fn iter(&self) -> impl Iterator<Item = ElfDynamic64> + 'a {
+        let data = self.data;
+        (0..data.len() / core::mem::size_of::<ElfDynamic64>())
+            .map_while(move |index| read_struct::<ElfDynamic64>(data, index * core::mem::size_of::<ElfDynamic64>()))
+            .take_while(|entry| entry.d_tag() != ElfDynamicTag::Null)
+    }
+
+    /// The value of the first entry with a tag
+    pub fn find(&self, tag: ElfDynamicTag) -> Option<u64> {
+        self.iter().find(|entry| entry.d_tag() == tag).map(|entry| entry.d_val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTRY_SIZE: usize = core::mem::size_of::<ElfDynamic64>();
+    const DT_NULL: i64 = 0;
+    const DT_RELA: i64 = 7;
+    const DT_RELASZ: i64 = 8;
+
+    fn write_entry(table: &mut [u8], index: usize, tag: i64, value: u64) {
+        let entry = &mut table[index * ENTRY_SIZE..][..ENTRY_SIZE];
+        entry[0..8].copy_from_slice(&tag.to_le_bytes());
+        entry[8..16].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn stops_at_null_entry() {
+        let mut data = [0; ENTRY_SIZE * 4];
+        write_entry(&mut data, 0, DT_RELA, 0x1000);
+        write_entry(&mut data, 1, DT_RELASZ, 0x180);
+        write_entry(&mut data, 2, DT_NULL, 0);
+        // Entries after the terminator are ignored
+        write_entry(&mut data, 3, DT_RELA, 0x2000);
+
+        let table = ElfDynamicTable::new(&data);
+        assert_eq!(table.iter().count(), 2);
+        assert_eq!(table.find(ElfDynamicTag::Rela), Some(0x1000));
+        assert_eq!(table.find(ElfDynamicTag::RelaSz), Some(0x180));
+        assert_eq!(table.find(ElfDynamicTag::Null), None);
+    }
+
+    #[test]
+    fn stops_at_end_without_null_entry() {
+        let mut data = [0; ENTRY_SIZE * 2 + 4];
+        write_entry(&mut data, 0, DT_RELA, 0x1000);
+        write_entry(&mut data, 1, DT_RELASZ, 0x180);
+
+        // The partial entry at the end is not read
+        let table = ElfDynamicTable::new(&data);
+        assert_eq!(table.iter().count(), 2);
+        assert_eq!(table.find(ElfDynamicTag::RelaCount), None);
+    }
+}
@@ -0,0 +1,36 @@
+/// Why an ELF file or one of its tables could not be read
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ElfError {
+    /// The file is too short to hold an ELF header
+    TruncatedHeader,
+    InvalidMagic,
+    /// Only 64 bit files can be read
+    UnsupportedClass,
+    /// Only little endian files can be read
+    UnsupportedEndianness,
+    UnsupportedVersion,
+    /// e_phentsize is smaller than a program header
+    InvalidProgramHeaderSize,
+    /// e_shentsize is smaller than a section header
+    InvalidSectionHeaderSize,
+    /// The program header table runs past the end of the file
+    ProgramHeadersOutOfBounds,
+    /// The section header table runs past the end of the file
+    SectionHeadersOutOfBounds,
+    /// A segment's contents run past the end of the file
+    SegmentOutOfBounds,
+    /// A segment takes more space in the file than in memory
+    SegmentFileSizeTooLarge,
+    /// A segment's end address is past the end of the address space
+    SegmentAddressOverflow,
+    /// A section's contents run past the end of the file
+    SectionOutOfBounds,
+    /// A section index is past the end of the section header table
+    InvalidSectionIndex,
+    /// A section that is needed is missing
+    MissingSection,
+    /// A string offset is past the end of its string table
+    InvalidStringOffset,
+    /// A string is not NULL terminated or not UTF-8
+    InvalidString,
+}
@@ -0,0 +1,352 @@
+use crate::{read_struct, ElfClass, ElfEndianness, ElfError, ElfHeader64, ElfPhysicalHeader64, ElfPhysicalType, ElfSectionHeader64, ElfSections, ElfTlsTemplate, ElfVersion};
+
+/// A 64 bit little endian ELF file held in memory
+///
+/// Parsing checks that the header tables, every segment and every section lie inside the file, so
+/// later lookups can't read out of bounds.
+#[derive(Clone, Copy)]
+pub struct ElfFile<'a> {
+    data: &'a [u8],
+    header: ElfHeader64,
+}
+
+impl<'a> ElfFile<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<ElfFile<'a>, ElfError> {
+        let header = read_struct::<ElfHeader64>(data, 0).ok_or(ElfError::TruncatedHeader)?;
+        let common = &header.common;
+        if !common.has_valid_magic() { return Err(ElfError::InvalidMagic); }
+        if common.class() != ElfClass::Bits64 { return Err(ElfError::UnsupportedClass); }
+        if common.endianness() != ElfEndianness::Little { return Err(ElfError::UnsupportedEndianness); }
+        if common.e_version() != ElfVersion::Current { return Err(ElfError::UnsupportedVersion); }
+
+        let file = ElfFile { data, header };
+        file.validate_program_headers()?;
+        file.validate_section_headers()?;
+        Ok(file)
+    }
+
+    pub fn header(&self) -> &ElfHeader64 { &self.header }
+
+    /// The whole file
+    pub fn data(&self) -> &'a [u8] { self.data }
+
+    pub fn program_header(&self, index: usize) -> Result<ElfPhysicalHeader64, ElfError> {
+        if index >= self.header.e_phnum as usize { return Err(ElfError::ProgramHeadersOutOfBounds); }
+
+        let offset = self.header.e_phoff as usize + index * self.header.e_phentsize as usize;
+        read_struct(self.data, offset).ok_or(ElfError::ProgramHeadersOutOfBounds)
+    }
+
+    pub fn program_headers(&self) -> impl Iterator<Item = ElfPhysicalHeader64> + 'a {
+        let file = *self;
+        (0..self.header.e_phnum as usize).filter_map(move |index| file.program_header(index).ok())
+    }
+
+    /// The part of a segment stored in the file. The rest of the segment up to p_memsz is zeroed when loaded
+    pub fn segment_data(&self, program_header: &ElfPhysicalHeader64) -> Result<&'a [u8], ElfError> {
+        file_range(self.data, program_header.p_offset, program_header.p_filesz).ok_or(ElfError::SegmentOutOfBounds)
+    }
+
+    /// Copy a segment into memory that will be mapped at destination_address
+    ///
+    /// Exactly p_filesz bytes are copied from the file and the rest of the segment up to p_memsz, such as
+    /// .bss, is zeroed. Bytes of the destination outside the segment are left alone so segments that share
+    /// a page can be loaded one after another.
+    pub fn load_segment(
+        &self, program_header: &ElfPhysicalHeader64, destination: &mut [u8], destination_address: u64
+    ) -> Result<(), ElfError> {
+        let start = program_header.p_vaddr.checked_sub(destination_address).ok_or(ElfError::SegmentOutOfBounds)?;
+        let start = usize::try_from(start).map_err(|_| ElfError::SegmentOutOfBounds)?;
+        let memory_size = usize::try_from(program_header.p_memsz).map_err(|_| ElfError::SegmentOutOfBounds)?;
+        let end = start.checked_add(memory_size).ok_or(ElfError::SegmentOutOfBounds)?;
+        let segment = destination.get_mut(start..end).ok_or(ElfError::SegmentOutOfBounds)?;
+
+        let file_data = self.segment_data(program_header)?;
+        let (file_part, memory_part) = segment.split_at_mut(file_data.len());
+        file_part.copy_from_slice(file_data);
+        memory_part.fill(0);
+        Ok(())
+    }
+
+    /// The thread local storage template from the PT_TLS segment, if the file has thread locals
+    pub fn tls_template(&self) -> Option<ElfTlsTemplate> {
+        let tls = self.program_headers().find(|program_header| program_header.p_type() == ElfPhysicalType::Tls)?;
+        Some(ElfTlsTemplate {
+            address: tls.p_vaddr,
+            file_size: tls.p_filesz,
+            memory_size: tls.p_memsz,
+            alignment: tls.p_align.max(1),
+        })
+    }
+
+    pub fn sections(&self) -> ElfSections<'a> {
+        ElfSections::new(self.data, self.header)
+    }
+
+    fn validate_program_headers(&self) -> Result<(), ElfError> {
+        let header = &self.header;
+        if header.e_phnum == 0 { return Ok(()); }
+        if (header.e_phentsize as usize) < core::mem::size_of::<ElfPhysicalHeader64>() {
+            return Err(ElfError::InvalidProgramHeaderSize);
+        }
+        let table_size = header.e_phnum as u64 * header.e_phentsize as u64;
+        file_range(self.data, header.e_phoff, table_size).ok_or(ElfError::ProgramHeadersOutOfBounds)?;
+
+        for index in 0..header.e_phnum as usize {
+            let program_header = self.program_header(index)?;
+            if program_header.p_filesz > program_header.p_memsz { return Err(ElfError::SegmentFileSizeTooLarge); }
+            if program_header.p_vaddr.checked_add(program_header.p_memsz).is_none() {
+                return Err(ElfError::SegmentAddressOverflow);
+            }
+            self.segment_data(&program_header)?;
+        }
+        Ok(())
+    }
+
+    fn validate_section_headers(&self) -> Result<(), ElfError> {
+        let header = &self.header;
+        if header.e_shnum == 0 { return Ok(()); }
+        if (header.e_shentsize as usize) < core::mem::size_of::<ElfSectionHeader64>() {
+            return Err(ElfError::InvalidSectionHeaderSize);
+        }
+        let table_size = header.e_shnum as u64 * header.e_shentsize as u64;
+        file_range(self.data, header.e_shoff, table_size).ok_or(ElfError::SectionHeadersOutOfBounds)?;
+        if header.e_shstrndx >= header.e_shnum { return Err(ElfError::InvalidSectionIndex); }
+
+        let sections = self.sections();
+        for index in 0..sections.len() {
+            sections.data(&sections.get(index)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// A range of the file, or None if any of it is past the end
+pub(crate) fn file_range(data: &[u8], offset: u64, size: u64) -> Option<&[u8]> {
+    let start = usize::try_from(offset).ok()?;
+    let end = start.checked_add(usize::try_from(size).ok()?)?;
+    data.get(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE_SIZE: usize = 0x400;
+    const PROGRAM_HEADERS_OFFSET: u64 = 0x40;
+    const SECTION_HEADERS_OFFSET: u64 = 0x200;
+    /// Segment contents go after both header tables
+    const SEGMENT_DATA_OFFSET: u64 = 0x300;
+    const PROGRAM_HEADER_SIZE: u16 = 56;
+    const SECTION_HEADER_SIZE: u16 = 64;
+    const PT_LOAD: u32 = 1;
+
+    /// A hand built ELF file with fields that can be overwritten to make it malformed
+    struct TestFile {
+        data: [u8; FILE_SIZE],
+    }
+
+    impl TestFile {
+        /// A valid x86_64 executable with no program or section headers
+        fn new() -> TestFile {
+            let mut file = TestFile { data: [0; FILE_SIZE] };
+            file.data[0..4].copy_from_slice(b"\x7FELF");
+            file.data[4] = 2; // 64 bit
+            file.data[5] = 1; // Little endian
+            file.data[6] = 1; // Current version
+            file.write_u16(16, 2); // ET_EXEC
+            file.write_u16(18, 0x3E); // x86_64
+            file.write_u32(20, 1); // e_version
+            file.write_u16(52, 64); // e_ehsize
+            file
+        }
+
+        fn write_u16(&mut self, offset: usize, value: u16) { self.data[offset..offset + 2].copy_from_slice(&value.to_le_bytes()); }
+
+        fn write_u32(&mut self, offset: usize, value: u32) { self.data[offset..offset + 4].copy_from_slice(&value.to_le_bytes()); }
+
+        fn write_u64(&mut self, offset: usize, value: u64) { self.data[offset..offset + 8].copy_from_slice(&value.to_le_bytes()); }
+
+        fn set_program_header_table(&mut self, offset: u64, entry_size: u16, count: u16) {
+            self.write_u64(32, offset);
+            self.write_u16(54, entry_size);
+            self.write_u16(56, count);
+        }
+
+        fn set_section_header_table(&mut self, offset: u64, entry_size: u16, count: u16, string_table_index: u16) {
+            self.write_u64(40, offset);
+            self.write_u16(58, entry_size);
+            self.write_u16(60, count);
+            self.write_u16(62, string_table_index);
+        }
+
+        /// Write a PT_LOAD program header into the table at PROGRAM_HEADERS_OFFSET
+        fn set_load_segment(&mut self, index: usize, offset: u64, address: u64, file_size: u64, memory_size: u64) {
+            let header = PROGRAM_HEADERS_OFFSET as usize + index * PROGRAM_HEADER_SIZE as usize;
+            self.write_u32(header, PT_LOAD);
+            self.write_u64(header + 8, offset);
+            self.write_u64(header + 16, address);
+            self.write_u64(header + 32, file_size);
+            self.write_u64(header + 40, memory_size);
+        }
+
+        fn parse(&self) -> Result<ElfFile<'_>, ElfError> { ElfFile::parse(&self.data) }
+    }
+
+    /// A file with one small segment and three empty sections, which parses without errors
+    fn valid_file() -> TestFile {
+        let mut file = TestFile::new();
+        file.set_program_header_table(PROGRAM_HEADERS_OFFSET, PROGRAM_HEADER_SIZE, 1);
+        file.set_load_segment(0, SEGMENT_DATA_OFFSET, 0x1000, 0x10, 0x20);
+        file.set_section_header_table(SECTION_HEADERS_OFFSET, SECTION_HEADER_SIZE, 3, 2);
+        file
+    }
+
+    #[test]
+    fn parses_valid_file() {
+        let file = valid_file();
+        let elf_file = file.parse().unwrap();
+        assert_eq!(elf_file.program_headers().count(), 1);
+        assert_eq!(elf_file.sections().len(), 3);
+    }
+
+    #[test]
+    fn rejects_truncated_header() {
+        let file = valid_file();
+        assert_eq!(ElfFile::parse(&file.data[..63]).err(), Some(ElfError::TruncatedHeader));
+        assert_eq!(ElfFile::parse(&[]).err(), Some(ElfError::TruncatedHeader));
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let mut file = valid_file();
+        file.data[1] = b'X';
+        assert_eq!(file.parse().err(), Some(ElfError::InvalidMagic));
+    }
+
+    #[test]
+    fn rejects_wrong_class() {
+        let mut file = valid_file();
+        file.data[4] = 1;
+        assert_eq!(file.parse().err(), Some(ElfError::UnsupportedClass));
+    }
+
+    #[test]
+    fn rejects_wrong_endianness() {
+        let mut file = valid_file();
+        file.data[5] = 2;
+        assert_eq!(file.parse().err(), Some(ElfError::UnsupportedEndianness));
+    }
+
+    #[test]
+    fn rejects_program_headers_past_end() {
+        let mut file = valid_file();
+        file.set_program_header_table(FILE_SIZE as u64 - 8, PROGRAM_HEADER_SIZE, 1);
+        assert_eq!(file.parse().err(), Some(ElfError::ProgramHeadersOutOfBounds));
+
+        file.set_program_header_table(u64::MAX, PROGRAM_HEADER_SIZE, 1);
+        assert_eq!(file.parse().err(), Some(ElfError::ProgramHeadersOutOfBounds));
+    }
+
+    #[test]
+    fn rejects_section_headers_past_end() {
+        let mut file = valid_file();
+        file.set_section_header_table(FILE_SIZE as u64 - 8, SECTION_HEADER_SIZE, 3, 2);
+        assert_eq!(file.parse().err(), Some(ElfError::SectionHeadersOutOfBounds));
+
+        file.set_section_header_table(u64::MAX, SECTION_HEADER_SIZE, 3, 2);
+        assert_eq!(file.parse().err(), Some(ElfError::SectionHeadersOutOfBounds));
+    }
+
+    #[test]
+    fn rejects_small_program_header_entries() {
+        let mut file = valid_file();
+        file.set_program_header_table(PROGRAM_HEADERS_OFFSET, PROGRAM_HEADER_SIZE - 1, 1);
+        assert_eq!(file.parse().err(), Some(ElfError::InvalidProgramHeaderSize));
+    }
+
+    #[test]
+    fn rejects_file_size_larger_than_memory_size() {
+        let mut file = valid_file();
+        file.set_load_segment(0, SEGMENT_DATA_OFFSET, 0x1000, 0x20, 0x10);
+        assert_eq!(file.parse().err(), Some(ElfError::SegmentFileSizeTooLarge));
+    }
+
+    #[test]
+    fn rejects_segment_past_end() {
+        let mut file = valid_file();
+        file.set_load_segment(0, SEGMENT_DATA_OFFSET, 0x1000, 0x200, 0x200);
+        assert_eq!(file.parse().err(), Some(ElfError::SegmentOutOfBounds));
+
+        // An offset and size that overflow when added
+        file.set_load_segment(0, u64::MAX - 0x8, 0x1000, 0x10, 0x10);
+        assert_eq!(file.parse().err(), Some(ElfError::SegmentOutOfBounds));
+    }
+
+    #[test]
+    fn rejects_out_of_range_string_table_index() {
+        let mut file = valid_file();
+        file.set_section_header_table(SECTION_HEADERS_OFFSET, SECTION_HEADER_SIZE, 3, 3);
+        assert_eq!(file.parse().err(), Some(ElfError::InvalidSectionIndex));
+    }
+
+    const LOAD_ADDRESS: u64 = 0x1000;
+    /// Destination bytes that no segment has written
+    const UNWRITTEN: u8 = 0x55;
+
+    fn load_all(file: &TestFile, destination: &mut [u8]) -> Result<(), ElfError> {
+        let elf_file = file.parse().unwrap();
+        for program_header in elf_file.program_headers() {
+            elf_file.load_segment(&program_header, destination, LOAD_ADDRESS)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn loads_segments_sharing_a_page() {
+        let mut file = valid_file();
+        file.set_program_header_table(PROGRAM_HEADERS_OFFSET, PROGRAM_HEADER_SIZE, 2);
+        file.set_load_segment(0, SEGMENT_DATA_OFFSET, LOAD_ADDRESS, 0x10, 0x10);
+        file.set_load_segment(1, SEGMENT_DATA_OFFSET + 0x10, LOAD_ADDRESS + 0x10, 0x20, 0x40);
+        file.data[0x300..0x310].fill(0xAA);
+        file.data[0x310..0x330].fill(0xBB);
+
+        let mut destination = [UNWRITTEN; 0x1000];
+        load_all(&file, &mut destination).unwrap();
+
+        // The second segment must not zero the first one's bytes
+        assert!(destination[..0x10].iter().all(|&byte| byte == 0xAA));
+        assert!(destination[0x10..0x30].iter().all(|&byte| byte == 0xBB));
+        assert!(destination[0x30..0x50].iter().all(|&byte| byte == 0));
+        assert!(destination[0x50..].iter().all(|&byte| byte == UNWRITTEN));
+    }
+
+    #[test]
+    fn zeroes_bss_across_a_page_boundary() {
+        let mut file = valid_file();
+        file.set_load_segment(0, SEGMENT_DATA_OFFSET, LOAD_ADDRESS + 0xF00, 0x40, 0x200);
+        file.data[0x300..0x340].fill(0xAA);
+
+        let mut destination = [UNWRITTEN; 0x2000];
+        load_all(&file, &mut destination).unwrap();
+
+        assert!(destination[..0xF00].iter().all(|&byte| byte == UNWRITTEN));
+        assert!(destination[0xF00..0xF40].iter().all(|&byte| byte == 0xAA));
+        assert!(destination[0xF40..0x1100].iter().all(|&byte| byte == 0));
+        assert!(destination[0x1100..].iter().all(|&byte| byte == UNWRITTEN));
+    }
+
+    #[test]
+    fn rejects_short_destination() {
+        let mut file = valid_file();
+        file.set_load_segment(0, SEGMENT_DATA_OFFSET, LOAD_ADDRESS + 0xF00, 0x40, 0x200);
+
+        let mut destination = [UNWRITTEN; 0x1000];
+        assert_eq!(load_all(&file, &mut destination), Err(ElfError::SegmentOutOfBounds));
+        assert!(destination.iter().all(|&byte| byte == UNWRITTEN));
+
+        // A segment below the destination address
+        file.set_load_segment(0, SEGMENT_DATA_OFFSET, LOAD_ADDRESS - 0x10, 0x10, 0x10);
+        assert_eq!(load_all(&file, &mut destination), Err(ElfError::SegmentOutOfBounds));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::ElfHeaderCommon;
 
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct ElfHeader64 {
     pub common: ElfHeaderCommon,
     pub e_entry: u64,
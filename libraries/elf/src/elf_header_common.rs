@@ -218,6 +218,7 @@ pub enum ElfVersion {
 }
 
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct ElfHeaderCommon {
     magic: [u8;4],
     class: u8,
@@ -22,6 +22,7 @@ pub enum ElfPhysicalType {
 }
 
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct ElfPhysicalHeader64 {
     p_type: u32,
     pub p_flags: u32,
@@ -0,0 +1,177 @@
+use crate::{read_struct, ElfSymbol64, SHN_ABS, SHN_LORESERVE, SHN_UNDEF};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ElfRelocationType {
+    Unknown,
+    None,
+    R64,
+    Pc32,
+    Got32,
+    Plt32,
+    Copy,
+    GlobDat,
+    JumpSlot,
+    Relative,
+    GotPcRel,
+    R32,
+    R32S,
+    DtpMod64,
+    DtpOff64,
+    TpOff64,
+    IRelative,
+}
+
+/// What has to be done to apply a relocation
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ElfRelocationValue {
+    /// Nothing is patched, as for R_X86_64_NONE entries left behind by the linker
+    Skip,
+    /// Store this 64 bit value at r_offset
+    Write64(u64),
+}
+
+/// A relocation entry with an explicit addend, from a SHT_RELA section or DT_RELA table
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ElfRela64 {
+    /// The virtual address to patch, relative to the load base for ET_DYN files
+    pub r_offset: u64,
+    r_info: u64,
+    pub r_addend: i64,
+}
+
+impl ElfRela64 {
+    pub fn relocation_type(&self) -> ElfRelocationType {
+        match self.r_info & 0xFFFF_FFFF {
+            0 => ElfRelocationType::None,
+            1 => ElfRelocationType::R64,
+            2 => ElfRelocationType::Pc32,
+            3 => ElfRelocationType::Got32,
+            4 => ElfRelocationType::Plt32,
+            5 => ElfRelocationType::Copy,
+            6 => ElfRelocationType::GlobDat,
+            7 => ElfRelocationType::JumpSlot,
+            8 => ElfRelocationType::Relative,
+            9 => ElfRelocationType::GotPcRel,
+            10 => ElfRelocationType::R32,
+            11 => ElfRelocationType::R32S,
+            16 => ElfRelocationType::DtpMod64,
+            17 => ElfRelocationType::DtpOff64,
+            18 => ElfRelocationType::TpOff64,
+            37 => ElfRelocationType::IRelative,
+            _ => ElfRelocationType::Unknown,
+        }
+    }
+
+    /// Index of the symbol in the dynamic symbol table, 0 if the relocation has no symbol
+    pub fn symbol_index(&self) -> usize {
+        (self.r_info >> 32) as usize
+    }
+
+    /// What to store at r_offset when the file is loaded at a base address
+    ///
+    /// Only NONE, RELATIVE and 64 relocations are supported, which are all a statically linked PIE needs.
+    /// Symbols in a section move with the base but absolute symbols don't. Returns None for other
+    /// types or when a 64 relocation refers to an undefined symbol.
+    pub fn value(&self, base: u64, symbol: Option<&ElfSymbol64>) -> Option<ElfRelocationValue> {
+        match self.relocation_type() {
+            ElfRelocationType::None => Some(ElfRelocationValue::Skip),
+            ElfRelocationType::Relative => Some(ElfRelocationValue::Write64(base.wrapping_add_signed(self.r_addend))),
+            ElfRelocationType::R64 => {
+                let symbol = symbol?;
+                let symbol_value = match symbol.st_shndx {
+                    SHN_ABS => symbol.st_value,
+                    index if index != SHN_UNDEF && index < SHN_LORESERVE => base.wrapping_add(symbol.st_value),
+                    _ => return None,
+                };
+                Some(ElfRelocationValue::Write64(symbol_value.wrapping_add_signed(self.r_addend)))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// A table of relocations with addends
+#[derive(Clone, Copy)]
+pub struct ElfRelaTable<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ElfRelaTable<'a> {
+    pub fn new(data: &'a [u8]) -> ElfRelaTable<'a> {
+        ElfRelaTable { data }
+    }
+
+    pub fn len(&self) -> usize { self.data.len() / core::mem::size_of::<ElfRela64>() }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    pub fn iter(&self) -> impl Iterator<Item = ElfRela64> + 'a {
+        let data = self.data;
+        (0..self.len()).filter_map(move |index| read_struct(data, index * core::mem::size_of::<ElfRela64>()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: u64 = 0xFFFF_FFFF_8000_0000;
+    const R_X86_64_NONE: u64 = 0;
+    const R_X86_64_64: u64 = 1;
+    const R_X86_64_PC32: u64 = 2;
+    const R_X86_64_RELATIVE: u64 = 8;
+
+    fn relocation(relocation_type: u64, symbol_index: u64, addend: i64) -> ElfRela64 {
+        ElfRela64 { r_offset: 0x2000, r_info: symbol_index << 32 | relocation_type, r_addend: addend }
+    }
+
+    /// A symbol read from its file layout, since only the table readers can build one
+    fn symbol(section: u16, value: u64) -> ElfSymbol64 {
+        let mut bytes = [0; core::mem::size_of::<ElfSymbol64>()];
+        bytes[6..8].copy_from_slice(&section.to_le_bytes());
+        bytes[8..16].copy_from_slice(&value.to_le_bytes());
+        read_struct(&bytes, 0).unwrap()
+    }
+
+    #[test]
+    fn relative_adds_the_base() {
+        assert_eq!(relocation(R_X86_64_RELATIVE, 0, 0x1234).value(BASE, None), Some(ElfRelocationValue::Write64(BASE + 0x1234)));
+        assert_eq!(relocation(R_X86_64_RELATIVE, 0, -0x10).value(BASE, None), Some(ElfRelocationValue::Write64(BASE - 0x10)));
+    }
+
+    #[test]
+    fn absolute_64_against_defined_symbol_moves_with_the_base() {
+        let relocation = relocation(R_X86_64_64, 1, 8);
+        assert_eq!(relocation.symbol_index(), 1);
+        assert_eq!(relocation.value(BASE, Some(&symbol(1, 0x3000))), Some(ElfRelocationValue::Write64(BASE + 0x3008)));
+    }
+
+    #[test]
+    fn absolute_64_against_absolute_symbol_does_not_move() {
+        let relocation = relocation(R_X86_64_64, 1, -8);
+        assert_eq!(relocation.value(BASE, Some(&symbol(SHN_ABS, 0x3000))), Some(ElfRelocationValue::Write64(0x2FF8)));
+    }
+
+    #[test]
+    fn absolute_64_against_undefined_symbol_fails() {
+        let relocation = relocation(R_X86_64_64, 1, 0);
+        assert_eq!(relocation.value(BASE, Some(&symbol(SHN_UNDEF, 0))), None);
+        assert_eq!(relocation.value(BASE, None), None);
+    }
+
+    #[test]
+    fn none_is_skipped() {
+        let relocation = relocation(R_X86_64_NONE, 0, 0);
+        assert_eq!(relocation.relocation_type(), ElfRelocationType::None);
+        assert_eq!(relocation.value(BASE, None), Some(ElfRelocationValue::Skip));
+    }
+
+    #[test]
+    fn unsupported_types_fail() {
+        let relocation = relocation(R_X86_64_PC32, 1, 0);
+        assert_eq!(relocation.relocation_type(), ElfRelocationType::Pc32);
+        assert_eq!(relocation.value(BASE, Some(&symbol(1, 0x3000))), None);
+        assert_eq!(self::relocation(0xFFFF, 0, 0).relocation_type(), ElfRelocationType::Unknown);
+    }
+}
@@ -0,0 +1,71 @@
+#[derive(PartialEq, Debug)]
+pub enum ElfSectionType {
+    Unknown,
+    Null,
+    ProgBits,
+    SymTab,
+    StrTab,
+    Rela,
+    Hash,
+    Dynamic,
+    Note,
+    NoBits,
+    Rel,
+    Shlib,
+    DynSym,
+    InitArray,
+    FiniArray,
+    PreInitArray,
+    Group,
+    SymTabShndx,
+    GnuHash,
+    GnuVerDef,
+    GnuVerNeed,
+    GnuVerSym,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct ElfSectionHeader64 {
+    /// Offset of the name in the section name string table
+    pub sh_name: u32,
+    sh_type: u32,
+    pub sh_flags: u64,
+    pub sh_addr: u64,
+    pub sh_offset: u64,
+    pub sh_size: u64,
+    /// For a symbol table this is the index of its string table
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u64,
+    pub sh_entsize: u64,
+}
+
+impl ElfSectionHeader64 {
+    pub fn sh_type(&self) -> ElfSectionType {
+        match self.sh_type {
+            0 => ElfSectionType::Null,
+            1 => ElfSectionType::ProgBits,
+            2 => ElfSectionType::SymTab,
+            3 => ElfSectionType::StrTab,
+            4 => ElfSectionType::Rela,
+            5 => ElfSectionType::Hash,
+            6 => ElfSectionType::Dynamic,
+            7 => ElfSectionType::Note,
+            8 => ElfSectionType::NoBits,
+            9 => ElfSectionType::Rel,
+            10 => ElfSectionType::Shlib,
+            11 => ElfSectionType::DynSym,
+            14 => ElfSectionType::InitArray,
+            15 => ElfSectionType::FiniArray,
+            16 => ElfSectionType::PreInitArray,
+            17 => ElfSectionType::Group,
+            18 => ElfSectionType::SymTabShndx,
+            0x6ffffff6 => ElfSectionType::GnuHash,
+            0x6ffffffd => ElfSectionType::GnuVerDef,
+            0x6ffffffe => ElfSectionType::GnuVerNeed,
+            0x6fffffff => ElfSectionType::GnuVerSym,
+            _ => ElfSectionType::Unknown,
+        }
+    }
+}
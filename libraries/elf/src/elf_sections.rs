@@ -0,0 +1,168 @@
+use crate::{elf_file::file_range, read_struct, ElfError, ElfHeader64, ElfSectionHeader64, ElfSectionType, ElfStringTable, ElfSymbolTable};
+
+/// The section headers of an ELF file held in memory, from ElfFile::sections
+#[derive(Clone, Copy)]
+pub struct ElfSections<'a> {
+    file: &'a [u8],
+    header: ElfHeader64,
+}
+
+impl<'a> ElfSections<'a> {
+    pub(crate) fn new(file: &'a [u8], header: ElfHeader64) -> ElfSections<'a> {
+        ElfSections { file, header }
+    }
+
+    pub fn len(&self) -> usize { self.header.e_shnum as usize }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    pub fn get(&self, index: usize) -> Result<ElfSectionHeader64, ElfError> {
+        if index >= self.len() { return Err(ElfError::InvalidSectionIndex); }
+
+        let offset = self.header.e_shoff as usize + index * self.header.e_shentsize as usize;
+        read_struct(self.file, offset).ok_or(ElfError::SectionHeadersOutOfBounds)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ElfSectionHeader64> + 'a {
+        let sections = *self;
+        (0..self.len()).filter_map(move |index| sections.get(index).ok())
+    }
+
+    /// The contents of a section in the file. Sections such as .bss that take no space in the file are empty
+    pub fn data(&self, section: &ElfSectionHeader64) -> Result<&'a [u8], ElfError> {
+        if section.sh_type() == ElfSectionType::NoBits { return Ok(&[]); }
+
+        file_range(self.file, section.sh_offset, section.sh_size).ok_or(ElfError::SectionOutOfBounds)
+    }
+
+    /// The name of a section from the section name string table
+    pub fn name(&self, section: &ElfSectionHeader64) -> Result<&'a str, ElfError> {
+        let names = self.get(self.header.e_shstrndx as usize)?;
+        ElfStringTable::new(self.data(&names)?).get(section.sh_name)
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<ElfSectionHeader64> {
+        self.iter().find(|section| self.name(section) == Ok(name))
+    }
+
+    pub fn find_by_type(&self, section_type: ElfSectionType) -> Option<ElfSectionHeader64> {
+        self.iter().find(|section| section.sh_type() == section_type)
+    }
+
+    /// The symbol table along with its linked string table
+    pub fn symbol_table(&self) -> Result<ElfSymbolTable<'a>, ElfError> {
+        let symbols = self.find_by_type(ElfSectionType::SymTab).ok_or(ElfError::MissingSection)?;
+        let strings = self.get(symbols.sh_link as usize)?;
+        Ok(ElfSymbolTable::new(self.data(&symbols)?, self.data(&strings)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE_SIZE: usize = 0x280;
+    const SECTION_HEADERS_OFFSET: usize = 0x100;
+    const SECTION_HEADER_SIZE: usize = core::mem::size_of::<ElfSectionHeader64>();
+    const SECTION_COUNT: u16 = 5;
+    const SYMBOL_TABLE_INDEX: usize = 1;
+    const STRING_TABLE_INDEX: u32 = 2;
+    const BSS_INDEX: usize = 3;
+    const SECTION_NAMES_INDEX: u16 = 4;
+
+    const SECTION_NAMES: &[u8] = b"\0.symtab\0.strtab\0.bss\0.shstrtab\0";
+    const SECTION_NAMES_OFFSET: usize = 0;
+    const STRINGS: &[u8] = b"\0main\0";
+    const STRINGS_OFFSET: usize = 0x40;
+    const SYMBOLS_OFFSET: usize = 0x60;
+    const SYMBOLS_SIZE: usize = 48;
+
+    const SHT_PROGBITS: u32 = 1;
+    const SHT_SYMTAB: u32 = 2;
+    const SHT_STRTAB: u32 = 3;
+    const SHT_NOBITS: u32 = 8;
+
+    fn write_section(file: &mut [u8], index: usize, name: u32, section_type: u32, offset: u64, size: u64, link: u32) {
+        let header = &mut file[SECTION_HEADERS_OFFSET + index * SECTION_HEADER_SIZE..][..SECTION_HEADER_SIZE];
+        header[0..4].copy_from_slice(&name.to_le_bytes());
+        header[4..8].copy_from_slice(&section_type.to_le_bytes());
+        header[24..32].copy_from_slice(&offset.to_le_bytes());
+        header[32..40].copy_from_slice(&size.to_le_bytes());
+        header[40..44].copy_from_slice(&link.to_le_bytes());
+    }
+
+    /// A file with a symbol table, its strings, a .bss and the section names
+    fn test_file() -> [u8; FILE_SIZE] {
+        let mut file = [0; FILE_SIZE];
+        file[SECTION_NAMES_OFFSET..][..SECTION_NAMES.len()].copy_from_slice(SECTION_NAMES);
+        file[STRINGS_OFFSET..][..STRINGS.len()].copy_from_slice(STRINGS);
+        // Symbol 1 is main, a function in section 1
+        let main = &mut file[SYMBOLS_OFFSET + SYMBOLS_SIZE / 2..][..SYMBOLS_SIZE / 2];
+        main[0..4].copy_from_slice(&1u32.to_le_bytes());
+        main[4] = 0x12;
+        main[6..8].copy_from_slice(&1u16.to_le_bytes());
+        main[8..16].copy_from_slice(&0x1000u64.to_le_bytes());
+
+        write_section(&mut file, SYMBOL_TABLE_INDEX, 1, SHT_SYMTAB, SYMBOLS_OFFSET as u64, SYMBOLS_SIZE as u64, STRING_TABLE_INDEX);
+        write_section(&mut file, STRING_TABLE_INDEX as usize, 9, SHT_STRTAB, STRINGS_OFFSET as u64, STRINGS.len() as u64, 0);
+        // .bss takes no space in the file, so its offset and size don't have to be inside it
+        write_section(&mut file, BSS_INDEX, 17, SHT_NOBITS, 0x1000_0000, 0x1000, 0);
+        write_section(
+            &mut file, SECTION_NAMES_INDEX as usize, 22, SHT_STRTAB,
+            SECTION_NAMES_OFFSET as u64, SECTION_NAMES.len() as u64, 0
+        );
+        file
+    }
+
+    fn sections(file: &[u8]) -> ElfSections<'_> {
+        let header = ElfHeader64 {
+            e_shoff: SECTION_HEADERS_OFFSET as u64,
+            e_shentsize: SECTION_HEADER_SIZE as u16,
+            e_shnum: SECTION_COUNT,
+            e_shstrndx: SECTION_NAMES_INDEX,
+            ..ElfHeader64::default()
+        };
+        ElfSections::new(file, header)
+    }
+
+    #[test]
+    fn finds_sections_by_name() {
+        let file = test_file();
+        let sections = sections(&file);
+        let bss = sections.find_by_name(".bss").unwrap();
+        assert_eq!(bss.sh_type(), ElfSectionType::NoBits);
+        assert_eq!(sections.name(&sections.get(STRING_TABLE_INDEX as usize).unwrap()), Ok(".strtab"));
+        assert!(sections.find_by_name(".data").is_none());
+        assert_eq!(sections.get(SECTION_COUNT as usize).err(), Some(ElfError::InvalidSectionIndex));
+    }
+
+    #[test]
+    fn no_bits_sections_have_no_data() {
+        let file = test_file();
+        let sections = sections(&file);
+        let bss = sections.get(BSS_INDEX).unwrap();
+        assert_eq!(sections.data(&bss), Ok(&[][..]));
+    }
+
+    #[test]
+    fn reads_symbol_table() {
+        let file = test_file();
+        let symbols = sections(&file).symbol_table().unwrap();
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols.find_by_name("main").map(|symbol| symbol.st_value), Some(0x1000));
+    }
+
+    #[test]
+    fn rejects_bad_symbol_table_link() {
+        let mut file = test_file();
+        write_section(&mut file, SYMBOL_TABLE_INDEX, 1, SHT_SYMTAB, SYMBOLS_OFFSET as u64, SYMBOLS_SIZE as u64, 9);
+        assert_eq!(sections(&file).symbol_table().err(), Some(ElfError::InvalidSectionIndex));
+    }
+
+    #[test]
+    fn reports_missing_symbol_table() {
+        let mut file = test_file();
+        write_section(&mut file, SYMBOL_TABLE_INDEX, 1, SHT_PROGBITS, SYMBOLS_OFFSET as u64, SYMBOLS_SIZE as u64, 0);
+        assert_eq!(sections(&file).symbol_table().err(), Some(ElfError::MissingSection));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::ElfError;
+
+/// A section of NULL terminated strings, referred to by their offset into the section
+#[derive(Clone, Copy)]
+pub struct ElfStringTable<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ElfStringTable<'a> {
+    pub fn new(data: &'a [u8]) -> ElfStringTable<'a> {
+        ElfStringTable { data }
+    }
+
+    /// Get the string starting at an offset
+    pub fn get(&self, offset: u32) -> Result<&'a str, ElfError> {
+        let data = self.data.get(offset as usize..).ok_or(ElfError::InvalidStringOffset)?;
+        let length = data.iter().position(|&byte| byte == 0).ok_or(ElfError::InvalidString)?;
+        core::str::from_utf8(&data[..length]).map_err(|_| ElfError::InvalidString)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRINGS: &[u8] = b"\0first\0second\0";
+
+    #[test]
+    fn gets_strings() {
+        let table = ElfStringTable::new(STRINGS);
+        assert_eq!(table.get(0), Ok(""));
+        assert_eq!(table.get(1), Ok("first"));
+        assert_eq!(table.get(7), Ok("second"));
+        // Offsets into the middle of a string give its tail
+        assert_eq!(table.get(10), Ok("ond"));
+    }
+
+    #[test]
+    fn rejects_out_of_range_offsets() {
+        let table = ElfStringTable::new(STRINGS);
+        assert_eq!(table.get(STRINGS.len() as u32 + 1), Err(ElfError::InvalidStringOffset));
+        assert_eq!(table.get(u32::MAX), Err(ElfError::InvalidStringOffset));
+        // The end of the table is in range but has no terminator
+        assert_eq!(table.get(STRINGS.len() as u32), Err(ElfError::InvalidString));
+    }
+
+    #[test]
+    fn rejects_unterminated_strings() {
+        let table = ElfStringTable::new(b"\0unterminated");
+        assert_eq!(table.get(1), Err(ElfError::InvalidString));
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        let table = ElfStringTable::new(b"\0\xFF\xFE\0");
+        assert_eq!(table.get(1), Err(ElfError::InvalidString));
+    }
+}
@@ -0,0 +1,93 @@
+#[derive(PartialEq, Debug)]
+pub enum ElfSymbolBinding {
+    Unknown,
+    Local,
+    Global,
+    Weak,
+    GnuUnique,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ElfSymbolType {
+    Unknown,
+    NoType,
+    Object,
+    Func,
+    Section,
+    File,
+    Common,
+    Tls,
+    GnuIFunc,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ElfSymbolVisibility {
+    Default,
+    Internal,
+    Hidden,
+    Protected,
+}
+
+/// Section index of symbols that are not defined in this file
+pub const SHN_UNDEF: u16 = 0;
+/// Section indices from here up have special meanings instead of naming a section
+pub const SHN_LORESERVE: u16 = 0xFF00;
+/// Section index of symbols whose value is absolute and doesn't move when the file is loaded elsewhere
+pub const SHN_ABS: u16 = 0xFFF1;
+
+/// An entry in a symbol table
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ElfSymbol64 {
+    /// Offset of the name in the string table linked to the symbol table
+    pub st_name: u32,
+    st_info: u8,
+    st_other: u8,
+    /// Index of the section the symbol is defined in
+    pub st_shndx: u16,
+    pub st_value: u64,
+    pub st_size: u64,
+}
+
+impl ElfSymbol64 {
+    pub fn binding(&self) -> ElfSymbolBinding {
+        match self.st_info >> 4 {
+            0 => ElfSymbolBinding::Local,
+            1 => ElfSymbolBinding::Global,
+            2 => ElfSymbolBinding::Weak,
+            10 => ElfSymbolBinding::GnuUnique,
+            _ => ElfSymbolBinding::Unknown,
+        }
+    }
+
+    pub fn symbol_type(&self) -> ElfSymbolType {
+        match self.st_info & 0xF {
+            0 => ElfSymbolType::NoType,
+            1 => ElfSymbolType::Object,
+            2 => ElfSymbolType::Func,
+            3 => ElfSymbolType::Section,
+            4 => ElfSymbolType::File,
+            5 => ElfSymbolType::Common,
+            6 => ElfSymbolType::Tls,
+            10 => ElfSymbolType::GnuIFunc,
+            _ => ElfSymbolType::Unknown,
+        }
+    }
+
+    pub fn visibility(&self) -> ElfSymbolVisibility {
+        match self.st_other & 0x3 {
+            1 => ElfSymbolVisibility::Internal,
+            2 => ElfSymbolVisibility::Hidden,
+            3 => ElfSymbolVisibility::Protected,
+            _ => ElfSymbolVisibility::Default,
+        }
+    }
+
+    /// Whether an address is inside the symbol. Symbols without a size only contain their own address
+    pub fn contains(&self, address: u64) -> bool {
+        match self.st_size {
+            0 => address == self.st_value,
+            size => address >= self.st_value && address - self.st_value < size,
+        }
+    }
+}
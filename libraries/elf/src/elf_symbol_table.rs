@@ -0,0 +1,125 @@
+use crate::{read_struct, ElfError, ElfStringTable, ElfSymbol64, ElfSymbolType};
+
+/// A symbol table and the string table holding its names
+#[derive(Clone, Copy)]
+pub struct ElfSymbolTable<'a> {
+    data: &'a [u8],
+    strings: ElfStringTable<'a>,
+}
+
+impl<'a> ElfSymbolTable<'a> {
+    /// The tables don't need to be aligned
+    pub fn new(symbol_data: &'a [u8], string_data: &'a [u8]) -> ElfSymbolTable<'a> {
+        ElfSymbolTable { data: symbol_data, strings: ElfStringTable::new(string_data) }
+    }
+
+    pub fn len(&self) -> usize { self.data.len() / core::mem::size_of::<ElfSymbol64>() }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    pub fn get(&self, index: usize) -> Option<ElfSymbol64> {
+        read_struct(self.data, index.checked_mul(core::mem::size_of::<ElfSymbol64>())?)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ElfSymbol64> + 'a {
+        let table = *self;
+        (0..self.len()).filter_map(move |index| table.get(index))
+    }
+
+    pub fn name(&self, symbol: &ElfSymbol64) -> Result<&'a str, ElfError> {
+        self.strings.get(symbol.st_name)
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<ElfSymbol64> {
+        self.iter().find(|symbol| self.name(symbol) == Ok(name))
+    }
+
+    /// Find the function or object an address is in
+    pub fn find_by_address(&self, address: u64) -> Option<ElfSymbol64> {
+        self.iter().find(|symbol| {
+            matches!(symbol.symbol_type(), ElfSymbolType::Func | ElfSymbolType::Object | ElfSymbolType::NoType)
+                && symbol.st_shndx != 0
+                && symbol.contains(address)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SHN_UNDEF;
+
+    const SYMBOL_SIZE: usize = core::mem::size_of::<ElfSymbol64>();
+    const SYMBOL_COUNT: usize = 5;
+    const STRINGS: &[u8] = b"\0start\0data\0marker\0undefined\0";
+    const STT_NOTYPE: u8 = 0;
+    const STT_OBJECT: u8 = 1;
+    const STT_FUNC: u8 = 2;
+    const STB_GLOBAL: u8 = 1 << 4;
+
+    /// Write a symbol into a table the way it is laid out in a file
+    fn write_symbol(table: &mut [u8], index: usize, name: u32, info: u8, section: u16, value: u64, size: u64) {
+        let symbol = &mut table[index * SYMBOL_SIZE..(index + 1) * SYMBOL_SIZE];
+        symbol[0..4].copy_from_slice(&name.to_le_bytes());
+        symbol[4] = info;
+        symbol[6..8].copy_from_slice(&section.to_le_bytes());
+        symbol[8..16].copy_from_slice(&value.to_le_bytes());
+        symbol[16..24].copy_from_slice(&size.to_le_bytes());
+    }
+
+    /// A null symbol, then an undefined symbol listed before the defined ones it overlaps
+    fn symbol_data() -> [u8; SYMBOL_SIZE * SYMBOL_COUNT] {
+        let mut table = [0; SYMBOL_SIZE * SYMBOL_COUNT];
+        write_symbol(&mut table, 1, 19, STB_GLOBAL | STT_FUNC, SHN_UNDEF, 0x1000, 0x100);
+        write_symbol(&mut table, 2, 1, STB_GLOBAL | STT_FUNC, 1, 0x1000, 0x20);
+        write_symbol(&mut table, 3, 7, STB_GLOBAL | STT_OBJECT, 2, 0x2000, 8);
+        write_symbol(&mut table, 4, 12, STB_GLOBAL | STT_NOTYPE, 1, 0x1800, 0);
+        table
+    }
+
+    #[test]
+    fn finds_symbols_by_name() {
+        let data = symbol_data();
+        let table = ElfSymbolTable::new(&data, STRINGS);
+        assert_eq!(table.len(), SYMBOL_COUNT);
+
+        let symbol = table.find_by_name("data").unwrap();
+        assert_eq!(symbol.st_value, 0x2000);
+        assert_eq!(symbol.symbol_type(), ElfSymbolType::Object);
+        assert!(table.find_by_name("missing").is_none());
+    }
+
+    #[test]
+    fn finds_symbols_by_address() {
+        let data = symbol_data();
+        let table = ElfSymbolTable::new(&data, STRINGS);
+        let name_at = |address| table.find_by_address(address).map(|symbol| table.name(&symbol).unwrap());
+
+        // The undefined symbol covering the same range is skipped
+        assert_eq!(name_at(0x1000), Some("start"));
+        assert_eq!(name_at(0x101F), Some("start"));
+        assert_eq!(name_at(0x1020), None);
+        assert_eq!(name_at(0x2007), Some("data"));
+        assert_eq!(name_at(0x2008), None);
+        // The null symbol at address 0 is undefined too
+        assert_eq!(name_at(0), None);
+    }
+
+    #[test]
+    fn zero_size_symbols_only_contain_their_address() {
+        let data = symbol_data();
+        let table = ElfSymbolTable::new(&data, STRINGS);
+        assert_eq!(table.find_by_address(0x1800).map(|symbol| symbol.st_value), Some(0x1800));
+        assert!(table.find_by_address(0x1801).is_none());
+    }
+
+    #[test]
+    fn reports_bad_names() {
+        let mut data = symbol_data();
+        write_symbol(&mut data, 2, STRINGS.len() as u32 + 1, STB_GLOBAL | STT_FUNC, 1, 0x1000, 0x20);
+        let table = ElfSymbolTable::new(&data, STRINGS);
+        assert_eq!(table.name(&table.get(2).unwrap()), Err(ElfError::InvalidStringOffset));
+        assert!(table.find_by_name("start").is_none());
+        assert!(table.get(SYMBOL_COUNT).is_none());
+    }
+}
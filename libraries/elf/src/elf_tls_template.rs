@@ -0,0 +1,12 @@
+/// The PT_TLS segment, which holds the initial values of thread local variables
+///
+/// Each thread gets a block of memory_size bytes aligned to alignment. The first file_size bytes are
+/// copied from address and the rest, the .tbss part, is zeroed.
+#[derive(Clone, Copy, Debug)]
+pub struct ElfTlsTemplate {
+    /// Virtual address of the initial values, relative to the load base for ET_DYN files
+    pub address: u64,
+    pub file_size: u64,
+    pub memory_size: u64,
+    pub alignment: u64,
+}
@@ -1,9 +1,37 @@
 #![no_std]
 
+mod elf_dynamic_64;
+mod elf_error;
+mod elf_file;
 mod elf_header_64;
 mod elf_header_common;
 mod elf_physical_header_64;
+mod elf_relocation_64;
+mod elf_section_header_64;
+mod elf_sections;
+mod elf_string_table;
+mod elf_symbol_64;
+mod elf_symbol_table;
+mod elf_tls_template;
 
+pub use elf_dynamic_64::*;
+pub use elf_error::*;
+pub use elf_file::ElfFile;
 pub use elf_header_64::*;
 pub use elf_header_common::*;
-pub use elf_physical_header_64::*;
\ No newline at end of file
+pub use elf_physical_header_64::*;
+pub use elf_relocation_64::*;
+pub use elf_section_header_64::*;
+pub use elf_sections::*;
+pub use elf_string_table::*;
+pub use elf_symbol_64::*;
+pub use elf_symbol_table::*;
+pub use elf_tls_template::*;
+
+/// Read a structure from any offset in a byte slice, or None if it doesn't fit
+fn read_struct<T: Copy>(data: &[u8], offset: usize) -> Option<T> {
+    let bytes = data.get(offset..offset.checked_add(core::mem::size_of::<T>())?)?;
+
+    // Safety: The bytes are in bounds and read unaligned. Only plain repr(C) structures of integers are read
+    Some(unsafe { core::ptr::read_unaligned(bytes.as_ptr() as *const T) })
+}
\ No newline at end of file
@@ -0,0 +1,306 @@
+use core::{arch::x86_64::__cpuid_count, fmt};
+
+/// The registers returned by one CPUID leaf
+#[derive(Debug, Clone, Copy)]
+pub struct CpuidResult {
+    pub eax: u32,
+    pub ebx: u32,
+    pub ecx: u32,
+    pub edx: u32,
+}
+
+/// Run CPUID for a leaf and subleaf
+///
+/// Leaves above the maximum the CPU reports return the data of its highest leaf, so check
+/// CpuInfo::max_leaf or max_extended_leaf first.
+pub fn cpuid(leaf: u32, subleaf: u32) -> CpuidResult {
+    let result = __cpuid_count(leaf, subleaf);
+    CpuidResult { eax: result.eax, ebx: result.ebx, ecx: result.ecx, edx: result.edx }
+}
+
+const EXTENDED_LEAF_BASE: u32 = 0x8000_0000;
+
+register_flags! {
+    /// CPU features collected from the CPUID leaves that report them
+    pub struct CpuFeatures: u64 {
+        const FPU = 1 << 0;
+        const TSC = 1 << 1;
+        const MSR = 1 << 2;
+        const APIC = 1 << 3;
+        const PAGE_GLOBAL = 1 << 4;
+        const PAT = 1 << 5;
+        const FXSR = 1 << 6;
+        const SSE = 1 << 7;
+        const SSE2 = 1 << 8;
+        const SSE3 = 1 << 9;
+        const SSSE3 = 1 << 10;
+        const SSE4_1 = 1 << 11;
+        const SSE4_2 = 1 << 12;
+        const PCID = 1 << 13;
+        const X2APIC = 1 << 14;
+        const TSC_DEADLINE = 1 << 15;
+        const XSAVE = 1 << 16;
+        const OSXSAVE = 1 << 17;
+        const AVX = 1 << 18;
+        const RDRAND = 1 << 19;
+        /// Running under a hypervisor
+        const HYPERVISOR = 1 << 20;
+        const FSGSBASE = 1 << 21;
+        const AVX2 = 1 << 22;
+        const SMEP = 1 << 23;
+        const SMAP = 1 << 24;
+        const RDSEED = 1 << 25;
+        const FIVE_LEVEL_PAGING = 1 << 26;
+        const SYSCALL = 1 << 27;
+        /// No execute bit in page table entries
+        const NX = 1 << 28;
+        const PAGE_1G = 1 << 29;
+        const RDTSCP = 1 << 30;
+        /// The TSC runs at a constant rate in every power state
+        const INVARIANT_TSC = 1 << 31;
+    }
+}
+
+/// Names of the features, in the order they are printed
+const FEATURE_NAMES: [(CpuFeatures, &str); 32] = [
+    (CpuFeatures::FPU, "fpu"),
+    (CpuFeatures::TSC, "tsc"),
+    (CpuFeatures::MSR, "msr"),
+    (CpuFeatures::APIC, "apic"),
+    (CpuFeatures::PAGE_GLOBAL, "pge"),
+    (CpuFeatures::PAT, "pat"),
+    (CpuFeatures::FXSR, "fxsr"),
+    (CpuFeatures::SSE, "sse"),
+    (CpuFeatures::SSE2, "sse2"),
+    (CpuFeatures::SSE3, "sse3"),
+    (CpuFeatures::SSSE3, "ssse3"),
+    (CpuFeatures::SSE4_1, "sse4.1"),
+    (CpuFeatures::SSE4_2, "sse4.2"),
+    (CpuFeatures::PCID, "pcid"),
+    (CpuFeatures::X2APIC, "x2apic"),
+    (CpuFeatures::TSC_DEADLINE, "tsc_deadline"),
+    (CpuFeatures::XSAVE, "xsave"),
+    (CpuFeatures::OSXSAVE, "osxsave"),
+    (CpuFeatures::AVX, "avx"),
+    (CpuFeatures::RDRAND, "rdrand"),
+    (CpuFeatures::HYPERVISOR, "hypervisor"),
+    (CpuFeatures::FSGSBASE, "fsgsbase"),
+    (CpuFeatures::AVX2, "avx2"),
+    (CpuFeatures::SMEP, "smep"),
+    (CpuFeatures::SMAP, "smap"),
+    (CpuFeatures::RDSEED, "rdseed"),
+    (CpuFeatures::FIVE_LEVEL_PAGING, "la57"),
+    (CpuFeatures::SYSCALL, "syscall"),
+    (CpuFeatures::NX, "nx"),
+    (CpuFeatures::PAGE_1G, "pdpe1gb"),
+    (CpuFeatures::RDTSCP, "rdtscp"),
+    (CpuFeatures::INVARIANT_TSC, "invariant_tsc"),
+];
+
+/// The bit each feature has in the register of the CPUID leaf the constant is named after
+const LEAF_1_ECX: [(u32, CpuFeatures); 12] = [
+    (0, CpuFeatures::SSE3),
+    (9, CpuFeatures::SSSE3),
+    (17, CpuFeatures::PCID),
+    (19, CpuFeatures::SSE4_1),
+    (20, CpuFeatures::SSE4_2),
+    (21, CpuFeatures::X2APIC),
+    (24, CpuFeatures::TSC_DEADLINE),
+    (26, CpuFeatures::XSAVE),
+    (27, CpuFeatures::OSXSAVE),
+    (28, CpuFeatures::AVX),
+    (30, CpuFeatures::RDRAND),
+    (31, CpuFeatures::HYPERVISOR),
+];
+const LEAF_1_EDX: [(u32, CpuFeatures); 9] = [
+    (0, CpuFeatures::FPU),
+    (4, CpuFeatures::TSC),
+    (5, CpuFeatures::MSR),
+    (9, CpuFeatures::APIC),
+    (13, CpuFeatures::PAGE_GLOBAL),
+    (16, CpuFeatures::PAT),
+    (24, CpuFeatures::FXSR),
+    (25, CpuFeatures::SSE),
+    (26, CpuFeatures::SSE2),
+];
+const LEAF_7_EBX: [(u32, CpuFeatures); 5] = [
+    (0, CpuFeatures::FSGSBASE),
+    (5, CpuFeatures::AVX2),
+    (7, CpuFeatures::SMEP),
+    (18, CpuFeatures::RDSEED),
+    (20, CpuFeatures::SMAP),
+];
+const LEAF_7_ECX: [(u32, CpuFeatures); 1] = [
+    (16, CpuFeatures::FIVE_LEVEL_PAGING),
+];
+const LEAF_80000001_EDX: [(u32, CpuFeatures); 4] = [
+    (11, CpuFeatures::SYSCALL),
+    (20, CpuFeatures::NX),
+    (26, CpuFeatures::PAGE_1G),
+    (27, CpuFeatures::RDTSCP),
+];
+const LEAF_80000007_EDX: [(u32, CpuFeatures); 1] = [
+    (8, CpuFeatures::INVARIANT_TSC),
+];
+
+/// How the logical processors in the package are arranged
+#[derive(Debug, Clone, Copy)]
+pub struct CpuTopology {
+    /// Initial APIC ID of the processor CPUID was run on
+    pub apic_id: u32,
+    /// Hardware threads sharing each core
+    pub threads_per_core: u32,
+    /// Logical processors in the whole package
+    pub logical_processors: u32,
+}
+
+/// What the CPU is and what it supports, decoded from CPUID
+#[derive(Clone, Copy)]
+pub struct CpuInfo {
+    vendor: [u8; 12],
+    brand: [u8; 48],
+    pub max_leaf: u32,
+    pub max_extended_leaf: u32,
+    /// Family, model and stepping with the extended family and model already added in
+    pub family: u32,
+    pub model: u32,
+    pub stepping: u32,
+    pub topology: CpuTopology,
+    pub features: CpuFeatures,
+    /// Address sizes the CPU supports, in bits
+    pub physical_address_bits: u8,
+    pub virtual_address_bits: u8,
+}
+
+impl CpuInfo {
+    /// Decode CPUID on the processor this runs on
+    pub fn read() -> CpuInfo {
+        let leaf_0 = cpuid(0, 0);
+        let max_leaf = leaf_0.eax;
+        let mut vendor = [0; 12];
+        vendor[0..4].copy_from_slice(&leaf_0.ebx.to_le_bytes());
+        vendor[4..8].copy_from_slice(&leaf_0.edx.to_le_bytes());
+        vendor[8..12].copy_from_slice(&leaf_0.ecx.to_le_bytes());
+
+        let max_extended_leaf = cpuid(EXTENDED_LEAF_BASE, 0).eax;
+        let has_leaf = |leaf: u32| if leaf >= EXTENDED_LEAF_BASE { leaf <= max_extended_leaf } else { leaf <= max_leaf };
+
+        let leaf_1 = cpuid(1, 0);
+        let (family, model, stepping) = decode_signature(leaf_1.eax);
+
+        let mut features = CpuFeatures::empty();
+        add_features(&mut features, leaf_1.ecx, &LEAF_1_ECX);
+        add_features(&mut features, leaf_1.edx, &LEAF_1_EDX);
+        if has_leaf(7) {
+            let leaf_7 = cpuid(7, 0);
+            add_features(&mut features, leaf_7.ebx, &LEAF_7_EBX);
+            add_features(&mut features, leaf_7.ecx, &LEAF_7_ECX);
+        }
+        if has_leaf(0x8000_0001) {
+            add_features(&mut features, cpuid(0x8000_0001, 0).edx, &LEAF_80000001_EDX);
+        }
+        if has_leaf(0x8000_0007) {
+            add_features(&mut features, cpuid(0x8000_0007, 0).edx, &LEAF_80000007_EDX);
+        }
+
+        let mut brand = [0; 48];
+        if has_leaf(0x8000_0004) {
+            for (index, leaf) in (0x8000_0002..=0x8000_0004).enumerate() {
+                let result = cpuid(leaf, 0);
+                for (register_index, register) in [result.eax, result.ebx, result.ecx, result.edx].iter().enumerate() {
+                    let start = index * 16 + register_index * 4;
+                    brand[start..start + 4].copy_from_slice(&register.to_le_bytes());
+                }
+            }
+        }
+
+        // Without leaf 0x80000008 assume the sizes every x86_64 CPU supports
+        let (physical_address_bits, virtual_address_bits) = if has_leaf(0x8000_0008) {
+            let sizes = cpuid(0x8000_0008, 0).eax;
+            (sizes as u8, (sizes >> 8) as u8)
+        } else {
+            (36, 48)
+        };
+
+        CpuInfo {
+            vendor,
+            brand,
+            max_leaf,
+            max_extended_leaf,
+            family,
+            model,
+            stepping,
+            topology: read_topology(leaf_1, has_leaf(0xB)),
+            features,
+            physical_address_bits,
+            virtual_address_bits,
+        }
+    }
+
+    /// The vendor ID such as GenuineIntel or AuthenticAMD
+    pub fn vendor(&self) -> &str {
+        core::str::from_utf8(&self.vendor).unwrap_or("Unknown")
+    }
+
+    /// The model name, or an empty string if the CPU doesn't report one
+    pub fn brand(&self) -> &str {
+        let length = self.brand.iter().position(|&byte| byte == 0).unwrap_or(self.brand.len());
+        core::str::from_utf8(&self.brand[..length]).unwrap_or("").trim()
+    }
+
+    pub fn has(&self, features: CpuFeatures) -> bool { self.features.contains(features) }
+}
+
+impl fmt::Display for CpuFeatures {
+    /// The names of the set features separated by spaces
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        for (_, name) in FEATURE_NAMES.iter().filter(|(feature, _)| self.contains(*feature)) {
+            if !first { f.write_str(" ")?; }
+            f.write_str(name)?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+fn add_features(features: &mut CpuFeatures, register: u32, bits: &[(u32, CpuFeatures)]) {
+    for &(bit, feature) in bits {
+        features.set(feature, register & (1 << bit) != 0);
+    }
+}
+
+/// Split leaf 1 EAX into family, model and stepping
+fn decode_signature(signature: u32) -> (u32, u32, u32) {
+    let stepping = signature & 0xF;
+    let base_model = (signature >> 4) & 0xF;
+    let base_family = (signature >> 8) & 0xF;
+    let extended_model = (signature >> 16) & 0xF;
+    let extended_family = (signature >> 20) & 0xFF;
+
+    // The extended fields only count for the families that ran out of room in the base fields
+    let family = if base_family == 0xF { base_family + extended_family } else { base_family };
+    let model = if base_family == 0x6 || base_family == 0xF { (extended_model << 4) | base_model } else { base_model };
+    (family, model, stepping)
+}
+
+/// Use the extended topology leaf if there is one, otherwise the counts in leaf 1
+fn read_topology(leaf_1: CpuidResult, has_topology_leaf: bool) -> CpuTopology {
+    let apic_id = leaf_1.ebx >> 24;
+    let leaf_1_count = if leaf_1.edx & (1 << 28) != 0 { (leaf_1.ebx >> 16) & 0xFF } else { 1 };
+
+    if has_topology_leaf {
+        let smt_level = cpuid(0xB, 0);
+        let core_level = cpuid(0xB, 1);
+        // Level 0 must be the SMT level. An EBX of 0 means the leaf isn't really supported
+        if smt_level.ebx & 0xFFFF != 0 {
+            return CpuTopology {
+                apic_id: smt_level.edx,
+                threads_per_core: smt_level.ebx & 0xFFFF,
+                logical_processors: (core_level.ebx & 0xFFFF).max(smt_level.ebx & 0xFFFF),
+            };
+        }
+    }
+
+    CpuTopology { apic_id, threads_per_core: 1, logical_processors: leaf_1_count.max(1) }
+}
@@ -1,2 +1,5 @@
 pub mod ioport;
+pub mod pci;
+pub mod pic;
+pub mod ps2_controller;
 pub mod uart;
\ No newline at end of file
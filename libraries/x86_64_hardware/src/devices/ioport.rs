@@ -14,14 +14,44 @@ impl Port {
         Port { port_number }
     }
 
+    /// Write a byte to the port
+    ///
+    /// ## Safety
+    /// Writing to a port can have side effects on the device behind it, so the value must be one the
+    /// device expects at this point.
     pub unsafe fn out_u8(&self, value: u8) {
         asm!("out dx, al", in("dx") self.port_number, in("al") value, options(nomem, nostack, preserves_flags));
     }
 
+    /// Read a byte from the port
+    ///
+    /// ## Safety
+    /// Reading a port can have side effects on the device behind it, such as consuming received data.
     pub unsafe fn in_u8(&self) -> u8 {
         let output: u8;
         asm!("in al, dx", in("dx") self.port_number, out("al") output, options(nomem, nostack, preserves_flags));
 
         output
     }
+
+    /// Write a 32 bit value to the port
+    ///
+    /// ## Safety
+    /// Writing to a port can have side effects on the device behind it, so the value must be one the
+    /// device expects at this point. The device must support 32 bit accesses to the port.
+    pub unsafe fn out_u32(&self, value: u32) {
+        asm!("out dx, eax", in("dx") self.port_number, in("eax") value, options(nomem, nostack, preserves_flags));
+    }
+
+    /// Read a 32 bit value from the port
+    ///
+    /// ## Safety
+    /// Reading a port can have side effects on the device behind it, such as consuming received data.
+    /// The device must support 32 bit accesses to the port.
+    pub unsafe fn in_u32(&self) -> u32 {
+        let output: u32;
+        asm!("in eax, dx", in("dx") self.port_number, out("eax") output, options(nomem, nostack, preserves_flags));
+
+        output
+    }
 }
\ No newline at end of file
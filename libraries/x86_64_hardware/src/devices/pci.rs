@@ -0,0 +1,118 @@
+use spin::Mutex;
+
+use super::ioport::Port;
+
+const CONFIG_ADDRESS_PORT: u16 = 0xCF8;
+const CONFIG_DATA_PORT: u16 = 0xCFC;
+const CONFIG_ENABLE: u32 = 1 << 31;
+
+const MAX_BUS: u16 = 256;
+const MAX_DEVICE: u8 = 32;
+const MAX_FUNCTION: u8 = 8;
+
+const VENDOR_ID_OFFSET: u8 = 0x00;
+const CLASS_OFFSET: u8 = 0x08;
+const HEADER_TYPE_OFFSET: u8 = 0x0C;
+const HEADER_TYPE_MULTIFUNCTION: u8 = 0x80;
+const INVALID_VENDOR_ID: u16 = 0xFFFF;
+
+/// The location of a function on the PCI bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciAddress {
+    pub bus: u8,
+    pub device: u8,
+    pub function: u8,
+}
+
+/// Identification information read from a function's configuration space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciFunction {
+    pub address: PciAddress,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub class: u8,
+    pub subclass: u8,
+    pub prog_if: u8,
+    pub revision: u8,
+}
+
+/// Access to PCI configuration space using the legacy I/O port mechanism
+pub struct PciConfigSpace {
+    address_port: Port,
+    data_port: Port,
+}
+
+impl PciConfigSpace {
+    /// ## Safety
+    ///
+    /// Only one instance should exist since the address and data ports must be
+    /// used as a pair
+    pub const unsafe fn new() -> PciConfigSpace {
+        PciConfigSpace {
+            address_port: Port::new(CONFIG_ADDRESS_PORT),
+            data_port: Port::new(CONFIG_DATA_PORT),
+        }
+    }
+
+    /// Read a dword from a function's configuration space
+    ///
+    /// The offset is rounded down to a multiple of 4
+    pub fn read_u32(&self, address: PciAddress, offset: u8) -> u32 {
+        unsafe {
+            self.address_port.out_u32(config_address(address, offset));
+            self.data_port.in_u32()
+        }
+    }
+
+    /// Read the identification registers of a function if it exists
+    pub fn read_function(&self, address: PciAddress) -> Option<PciFunction> {
+        let ids = self.read_u32(address, VENDOR_ID_OFFSET);
+        let vendor_id = ids as u16;
+        if vendor_id == INVALID_VENDOR_ID { return None; }
+
+        let class = self.read_u32(address, CLASS_OFFSET);
+
+        Some(PciFunction {
+            address,
+            vendor_id,
+            device_id: (ids >> 16) as u16,
+            class: (class >> 24) as u8,
+            subclass: (class >> 16) as u8,
+            prog_if: (class >> 8) as u8,
+            revision: class as u8,
+        })
+    }
+
+    /// Call f with every function present on every bus
+    pub fn for_each_function(&self, mut f: impl FnMut(PciFunction)) {
+        for bus in 0..MAX_BUS {
+            for device in 0..MAX_DEVICE {
+                let address = PciAddress { bus: bus as u8, device, function: 0 };
+                let Some(function) = self.read_function(address) else { continue };
+                f(function);
+
+                if !self.is_multifunction(address) { continue; }
+
+                for function in 1..MAX_FUNCTION {
+                    let address = PciAddress { function, ..address };
+                    if let Some(function) = self.read_function(address) { f(function); }
+                }
+            }
+        }
+    }
+
+    fn is_multifunction(&self, address: PciAddress) -> bool {
+        let header_type = (self.read_u32(address, HEADER_TYPE_OFFSET) >> 16) as u8;
+        header_type & HEADER_TYPE_MULTIFUNCTION != 0
+    }
+}
+
+fn config_address(address: PciAddress, offset: u8) -> u32 {
+    CONFIG_ENABLE
+        | (address.bus as u32) << 16
+        | (address.device as u32 & 0x1F) << 11
+        | (address.function as u32 & 0x07) << 8
+        | (offset as u32 & 0xFC)
+}
+
+pub static PCI_CONFIG_SPACE: Mutex<PciConfigSpace> = Mutex::new(unsafe { PciConfigSpace::new() });
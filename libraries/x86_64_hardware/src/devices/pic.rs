@@ -0,0 +1,123 @@
+use spin::Mutex;
+
+use super::ioport::Port;
+
+const PRIMARY_COMMAND_PORT: u16 = 0x20;
+const PRIMARY_DATA_PORT: u16 = 0x21;
+const SECONDARY_COMMAND_PORT: u16 = 0xA0;
+const SECONDARY_DATA_PORT: u16 = 0xA1;
+/// Writing to this unused port takes long enough for the PICs to settle between commands
+const WAIT_PORT: u16 = 0x80;
+
+/// Start initialization and say that a fourth initialization word follows
+const ICW1_INITIALIZE: u8 = 0x11;
+const ICW4_8086_MODE: u8 = 0x01;
+const COMMAND_END_OF_INTERRUPT: u8 = 0x20;
+const COMMAND_READ_IN_SERVICE: u8 = 0x0B;
+
+/// The primary IRQ the secondary PIC is wired to
+const CASCADE_IRQ: u8 = 2;
+const IRQS_PER_PIC: u8 = 8;
+/// The IRQ each PIC reports when an interrupt goes away before the CPU acknowledges it
+const SPURIOUS_IRQ: u8 = 7;
+
+/// Driver for the two chained Intel 8259 programmable interrupt controllers
+pub struct Pic {
+    primary_command: Port,
+    primary_data: Port,
+    secondary_command: Port,
+    secondary_data: Port,
+    wait_port: Port,
+    /// IRQs that are masked, with the primary PIC in the low byte
+    masks: u16,
+}
+
+impl Pic {
+    /// Create a new PIC driver
+    ///
+    /// ## Safety
+    /// The caller must ensure that the system has a pair of 8259 PICs at the standard ports.
+    pub const unsafe fn new() -> Pic {
+        Pic {
+            primary_command: Port::new(PRIMARY_COMMAND_PORT),
+            primary_data: Port::new(PRIMARY_DATA_PORT),
+            secondary_command: Port::new(SECONDARY_COMMAND_PORT),
+            secondary_data: Port::new(SECONDARY_DATA_PORT),
+            wait_port: Port::new(WAIT_PORT),
+            masks: 0xFFFF,
+        }
+    }
+
+    /// Deliver IRQs 0 to 15 to the vectors starting at vector_offset, with every IRQ masked
+    ///
+    /// vector_offset must be a multiple of 8 past the CPU exception vectors.
+    pub fn initialize(&mut self, vector_offset: u8) {
+        unsafe {
+            self.primary_command.out_u8(ICW1_INITIALIZE);
+            self.wait();
+            self.secondary_command.out_u8(ICW1_INITIALIZE);
+            self.wait();
+
+            self.primary_data.out_u8(vector_offset);
+            self.wait();
+            self.secondary_data.out_u8(vector_offset + IRQS_PER_PIC);
+            self.wait();
+
+            // Tell the primary which line the secondary is on and the secondary its cascade identity
+            self.primary_data.out_u8(1 << CASCADE_IRQ);
+            self.wait();
+            self.secondary_data.out_u8(CASCADE_IRQ);
+            self.wait();
+
+            self.primary_data.out_u8(ICW4_8086_MODE);
+            self.wait();
+            self.secondary_data.out_u8(ICW4_8086_MODE);
+            self.wait();
+        }
+
+        self.masks = 0xFFFF;
+        self.write_masks();
+    }
+
+    /// Let an IRQ through. IRQs on the secondary PIC also unmask the cascade line
+    pub fn unmask(&mut self, irq: u8) {
+        self.masks &= !(1 << irq);
+        if irq >= IRQS_PER_PIC { self.masks &= !(1 << CASCADE_IRQ); }
+        self.write_masks();
+    }
+
+    /// Tell the PICs that the handler for an IRQ is done
+    ///
+    /// Spurious IRQs are not in service so they are not acknowledged, apart from the cascade line
+    /// on the primary for a spurious IRQ from the secondary.
+    pub fn end_of_interrupt(&self, irq: u8) {
+        unsafe {
+            if irq >= IRQS_PER_PIC {
+                if irq != IRQS_PER_PIC + SPURIOUS_IRQ || self.is_in_service(&self.secondary_command, SPURIOUS_IRQ) {
+                    self.secondary_command.out_u8(COMMAND_END_OF_INTERRUPT);
+                }
+                self.primary_command.out_u8(COMMAND_END_OF_INTERRUPT);
+            } else if irq != SPURIOUS_IRQ || self.is_in_service(&self.primary_command, SPURIOUS_IRQ) {
+                self.primary_command.out_u8(COMMAND_END_OF_INTERRUPT);
+            }
+        }
+    }
+
+    unsafe fn is_in_service(&self, command_port: &Port, irq: u8) -> bool {
+        command_port.out_u8(COMMAND_READ_IN_SERVICE);
+        command_port.in_u8() & (1 << irq) != 0
+    }
+
+    fn write_masks(&self) {
+        unsafe {
+            self.primary_data.out_u8(self.masks as u8);
+            self.secondary_data.out_u8((self.masks >> 8) as u8);
+        }
+    }
+
+    unsafe fn wait(&self) {
+        self.wait_port.out_u8(0);
+    }
+}
+
+pub static PIC: Mutex<Pic> = Mutex::new(unsafe { Pic::new() });
@@ -0,0 +1,307 @@
+use spin::Mutex;
+
+use super::ioport::Port;
+
+const DATA_PORT: u16 = 0x60;
+const STATUS_COMMAND_PORT: u16 = 0x64;
+
+const STATUS_OUTPUT_FULL: u8 = 1 << 0;
+const STATUS_INPUT_FULL: u8 = 1 << 1;
+const STATUS_SECOND_PORT_DATA: u8 = 1 << 5;
+
+const COMMAND_READ_CONFIG: u8 = 0x20;
+const COMMAND_WRITE_CONFIG: u8 = 0x60;
+const COMMAND_DISABLE_SECOND_PORT: u8 = 0xA7;
+const COMMAND_ENABLE_SECOND_PORT: u8 = 0xA8;
+const COMMAND_TEST_SECOND_PORT: u8 = 0xA9;
+const COMMAND_SELF_TEST: u8 = 0xAA;
+const COMMAND_TEST_FIRST_PORT: u8 = 0xAB;
+const COMMAND_DISABLE_FIRST_PORT: u8 = 0xAD;
+const COMMAND_ENABLE_FIRST_PORT: u8 = 0xAE;
+const COMMAND_WRITE_SECOND_PORT: u8 = 0xD4;
+const COMMAND_PULSE_RESET_LINE: u8 = 0xFE;
+
+const CONFIG_FIRST_PORT_INTERRUPT: u8 = 1 << 0;
+const CONFIG_SECOND_PORT_INTERRUPT: u8 = 1 << 1;
+const CONFIG_SECOND_PORT_CLOCK_DISABLED: u8 = 1 << 5;
+const CONFIG_FIRST_PORT_TRANSLATION: u8 = 1 << 6;
+
+const SELF_TEST_PASSED: u8 = 0x55;
+const PORT_TEST_PASSED: u8 = 0x00;
+
+const DEVICE_RESET: u8 = 0xFF;
+const DEVICE_ACK: u8 = 0xFA;
+const DEVICE_RESEND: u8 = 0xFE;
+const DEVICE_SELF_TEST_PASSED: u8 = 0xAA;
+const DEVICE_COMMAND_RETRIES: usize = 3;
+
+/// Number of status register polls before an operation is considered to have timed out
+const TIMEOUT_POLLS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ps2Error {
+    Timeout,
+    SelfTestFailed(u8),
+    PortTestFailed(Ps2Port, u8),
+    PortUnavailable(Ps2Port),
+    NoAcknowledge(u8),
+    DeviceSelfTestFailed(u8),
+    /// A device answered a query with a value the driver doesn't support
+    UnexpectedReply(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ps2Port {
+    First,
+    Second,
+}
+
+/// The ports which passed their interface test during initialization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ps2Ports {
+    pub first: bool,
+    pub second: bool,
+}
+
+impl Ps2Ports {
+    pub fn is_available(&self, port: Ps2Port) -> bool {
+        match port {
+            Ps2Port::First => self.first,
+            Ps2Port::Second => self.second,
+        }
+    }
+}
+
+/// Driver for the Intel 8042 PS/2 controller
+pub struct Ps2Controller {
+    data_port: Port,
+    status_command_port: Port,
+    ports: Ps2Ports,
+}
+
+impl Ps2Controller {
+    /// Create a new PS/2 controller
+    ///
+    /// ## Safety
+    /// The caller must ensure that an 8042 controller is actually present.
+    /// On modern systems this should be checked with the ACPI FADT before use.
+    pub const unsafe fn new() -> Ps2Controller {
+        Ps2Controller {
+            data_port: Port::new(DATA_PORT),
+            status_command_port: Port::new(STATUS_COMMAND_PORT),
+            ports: Ps2Ports { first: false, second: false },
+        }
+    }
+
+    /// Initialize the controller and detect which ports are usable
+    ///
+    /// Both ports are left enabled with interrupts and scancode translation disabled,
+    /// so devices must be polled with try_read_data.
+    pub fn initialize(&mut self) -> Result<Ps2Ports, Ps2Error> {
+        self.ports = Ps2Ports::default();
+
+        // Disable both ports so devices can't interfere with the setup
+        self.write_command(COMMAND_DISABLE_FIRST_PORT)?;
+        self.write_command(COMMAND_DISABLE_SECOND_PORT)?;
+        self.flush_output_buffer();
+
+        let mut config = self.read_config()?;
+        config &= !(CONFIG_FIRST_PORT_INTERRUPT | CONFIG_SECOND_PORT_INTERRUPT | CONFIG_FIRST_PORT_TRANSLATION);
+        self.write_config(config)?;
+
+        self.write_command(COMMAND_SELF_TEST)?;
+        let response = self.read_data()?;
+        if response != SELF_TEST_PASSED { return Err(Ps2Error::SelfTestFailed(response)); }
+
+        // Some controllers reset themselves during the self test
+        self.write_config(config)?;
+
+        // If the second port clock turns on when enabled the controller is dual channel
+        let mut dual_channel = false;
+        if config & CONFIG_SECOND_PORT_CLOCK_DISABLED != 0 {
+            self.write_command(COMMAND_ENABLE_SECOND_PORT)?;
+            dual_channel = self.read_config()? & CONFIG_SECOND_PORT_CLOCK_DISABLED == 0;
+            self.write_command(COMMAND_DISABLE_SECOND_PORT)?;
+        }
+
+        let mut ports = Ps2Ports { first: self.test_port(Ps2Port::First)?, second: false };
+        if dual_channel {
+            ports.second = self.test_port(Ps2Port::Second)?;
+        }
+
+        if ports.first { self.write_command(COMMAND_ENABLE_FIRST_PORT)?; }
+        if ports.second { self.write_command(COMMAND_ENABLE_SECOND_PORT)?; }
+        self.flush_output_buffer();
+
+        self.ports = ports;
+        Ok(ports)
+    }
+
+    /// The ports detected by the last call to initialize
+    pub fn ports(&self) -> Ps2Ports { self.ports }
+
+    /// Raise IRQ 1 and 12 when the devices on the first and second port send a byte
+    ///
+    /// Bytes must still be read with try_read_data, the interrupts only signal that one arrived.
+    pub fn enable_interrupts(&mut self) -> Result<(), Ps2Error> {
+        let mut config = self.read_config()?;
+        if self.ports.first { config |= CONFIG_FIRST_PORT_INTERRUPT; }
+        if self.ports.second { config |= CONFIG_SECOND_PORT_INTERRUPT; }
+        self.write_config(config)
+    }
+
+    /// Reset the device attached to a port and wait for it to pass its self test
+    ///
+    /// Any identification bytes sent by the device after the self test are discarded.
+    pub fn reset_device(&mut self, port: Ps2Port) -> Result<(), Ps2Error> {
+        self.send_device_command(port, DEVICE_RESET)?;
+
+        let response = self.read_data()?;
+        if response != DEVICE_SELF_TEST_PASSED { return Err(Ps2Error::DeviceSelfTestFailed(response)); }
+
+        self.flush_output_buffer();
+        Ok(())
+    }
+
+    /// Send a command byte to a device and wait for it to be acknowledged
+    ///
+    /// The command is resent a few times if the device asks for it.
+    pub fn send_device_command(&mut self, port: Ps2Port, command: u8) -> Result<(), Ps2Error> {
+        if !self.ports.is_available(port) { return Err(Ps2Error::PortUnavailable(port)); }
+
+        for _ in 0..DEVICE_COMMAND_RETRIES {
+            self.write_device(port, command)?;
+            match self.read_data()? {
+                DEVICE_ACK => return Ok(()),
+                DEVICE_RESEND => continue,
+                response => return Err(Ps2Error::NoAcknowledge(response)),
+            }
+        }
+
+        Err(Ps2Error::NoAcknowledge(DEVICE_RESEND))
+    }
+
+    /// Send a command byte followed by a data byte to a device
+    pub fn send_device_command_with_data(&mut self, port: Ps2Port, command: u8, data: u8) -> Result<(), Ps2Error> {
+        self.send_device_command(port, command)?;
+        self.send_device_command(port, data)
+    }
+
+    /// Send a sequence of bytes to a device while it may also be sending input, waiting for each to be acknowledged
+    ///
+    /// Input that arrives ahead of an acknowledgement, such as a key pressed while the keyboard
+    /// LEDs are updated, is passed to other_byte instead of being mistaken for the reply.
+    pub fn send_device_sequence(&mut self, port: Ps2Port, bytes: &[u8], mut other_byte: impl FnMut(Ps2Port, u8)) -> Result<(), Ps2Error> {
+        if !self.ports.is_available(port) { return Err(Ps2Error::PortUnavailable(port)); }
+
+        'bytes: for &byte in bytes {
+            for _ in 0..DEVICE_COMMAND_RETRIES {
+                self.write_device(port, byte)?;
+                if self.wait_for_reply(port, &mut other_byte)? == DEVICE_ACK { continue 'bytes; }
+            }
+            return Err(Ps2Error::NoAcknowledge(DEVICE_RESEND));
+        }
+
+        Ok(())
+    }
+
+    /// Read a single byte from the controller, waiting until one is available
+    pub fn read_data(&self) -> Result<u8, Ps2Error> {
+        for _ in 0..TIMEOUT_POLLS {
+            if self.status() & STATUS_OUTPUT_FULL != 0 {
+                return Ok(unsafe { self.data_port.in_u8() });
+            }
+        }
+
+        Err(Ps2Error::Timeout)
+    }
+
+    /// Read a byte from the controller if one is waiting along with the port it came from
+    pub fn try_read_data(&self) -> Option<(Ps2Port, u8)> {
+        let status = self.status();
+        if status & STATUS_OUTPUT_FULL == 0 { return None; }
+
+        let port = if status & STATUS_SECOND_PORT_DATA != 0 { Ps2Port::Second } else { Ps2Port::First };
+        Some((port, unsafe { self.data_port.in_u8() }))
+    }
+
+    /// Reset the CPU by pulsing the controller's reset line
+    ///
+    /// ## Safety
+    /// This immediately resets the whole system
+    pub unsafe fn pulse_reset_line(&self) -> Result<(), Ps2Error> {
+        self.write_command(COMMAND_PULSE_RESET_LINE)
+    }
+
+    /// Wait for a device to acknowledge a byte or ask for it again, passing on any other bytes
+    fn wait_for_reply(&self, port: Ps2Port, other_byte: &mut impl FnMut(Ps2Port, u8)) -> Result<u8, Ps2Error> {
+        for _ in 0..TIMEOUT_POLLS {
+            let Some((from, byte)) = self.try_read_data() else { continue };
+            if from == port && (byte == DEVICE_ACK || byte == DEVICE_RESEND) { return Ok(byte); }
+            other_byte(from, byte);
+        }
+
+        Err(Ps2Error::Timeout)
+    }
+
+    fn test_port(&mut self, port: Ps2Port) -> Result<bool, Ps2Error> {
+        let command = match port {
+            Ps2Port::First => COMMAND_TEST_FIRST_PORT,
+            Ps2Port::Second => COMMAND_TEST_SECOND_PORT,
+        };
+
+        self.write_command(command)?;
+        Ok(self.read_data()? == PORT_TEST_PASSED)
+    }
+
+    fn write_device(&self, port: Ps2Port, byte: u8) -> Result<(), Ps2Error> {
+        if port == Ps2Port::Second {
+            self.write_command(COMMAND_WRITE_SECOND_PORT)?;
+        }
+
+        self.write_data(byte)
+    }
+
+    fn read_config(&self) -> Result<u8, Ps2Error> {
+        self.write_command(COMMAND_READ_CONFIG)?;
+        self.read_data()
+    }
+
+    fn write_config(&self, config: u8) -> Result<(), Ps2Error> {
+        self.write_command(COMMAND_WRITE_CONFIG)?;
+        self.write_data(config)
+    }
+
+    fn write_command(&self, command: u8) -> Result<(), Ps2Error> {
+        self.wait_for_input_empty()?;
+        unsafe { self.status_command_port.out_u8(command); }
+        Ok(())
+    }
+
+    fn write_data(&self, data: u8) -> Result<(), Ps2Error> {
+        self.wait_for_input_empty()?;
+        unsafe { self.data_port.out_u8(data); }
+        Ok(())
+    }
+
+    fn wait_for_input_empty(&self) -> Result<(), Ps2Error> {
+        for _ in 0..TIMEOUT_POLLS {
+            if self.status() & STATUS_INPUT_FULL == 0 { return Ok(()); }
+        }
+
+        Err(Ps2Error::Timeout)
+    }
+
+    fn flush_output_buffer(&self) {
+        for _ in 0..TIMEOUT_POLLS {
+            if self.status() & STATUS_OUTPUT_FULL == 0 { return; }
+            unsafe { self.data_port.in_u8(); }
+        }
+    }
+
+    fn status(&self) -> u8 {
+        unsafe { self.status_command_port.in_u8() }
+    }
+}
+
+pub static PS2_CONTROLLER: Mutex<Ps2Controller> = Mutex::new(unsafe { Ps2Controller::new() });
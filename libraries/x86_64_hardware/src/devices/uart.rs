@@ -45,6 +45,13 @@ impl SerialPort {
         }
     }
 
+    /// Raise the port's IRQ whenever a byte is received
+    ///
+    /// Bytes must still be read with try_read_byte, the interrupt only signals that one arrived.
+    pub fn enable_receive_interrupt(&self) {
+        unsafe { self.inter_reg.out_u8(0x01); }
+    }
+
     pub fn write_byte(&self, byte: u8) {
         unsafe {
             // Wait for transmit to clear
@@ -54,6 +61,14 @@ impl SerialPort {
         }
     }
 
+    /// Read a byte if one has been received
+    pub fn try_read_byte(&self) -> Option<u8> {
+        unsafe {
+            if !self.is_data_ready() { return None; }
+            Some(self.data_reg.in_u8())
+        }
+    }
+
     unsafe fn set_baud_divisor(&self, divisor: u16) {
         let least_significant_byte = (divisor & 0xFF) as u8;
         let most_significant_byte = (divisor >> 8 & 0xFF) as u8;
@@ -80,6 +95,11 @@ impl SerialPort {
         // Line status register bit 5 low indicates empty 
         (self.line_status_reg.in_u8() & 0x20) != 0
     }
+
+    unsafe fn is_data_ready(&self) -> bool {
+        // Line status register bit 0 high indicates there is data to read
+        (self.line_status_reg.in_u8() & 0x01) != 0
+    }
 }
 
 impl fmt::Write for SerialPort {
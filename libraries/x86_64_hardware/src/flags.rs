@@ -0,0 +1,58 @@
+/// Define a set of flags stored in the bits of a register
+///
+/// The type wraps the raw value and every bit, known or not, is kept when it is read and written back.
+macro_rules! register_flags {
+    (
+        $(#[$attribute:meta])*
+        pub struct $name:ident: $bits:ty {
+            $(
+                $(#[$flag_attribute:meta])*
+                const $flag:ident = $value:expr;
+            )*
+        }
+    ) => {
+        $(#[$attribute])*
+        #[repr(transparent)]
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        pub struct $name($bits);
+
+        impl $name {
+            $(
+                $(#[$flag_attribute])*
+                pub const $flag: $name = $name($value);
+            )*
+
+            pub const fn empty() -> $name { $name(0) }
+
+            pub const fn from_bits(bits: $bits) -> $name { $name(bits) }
+
+            pub const fn bits(self) -> $bits { self.0 }
+
+            /// Whether every flag in other is set
+            pub const fn contains(self, other: $name) -> bool { self.0 & other.0 == other.0 }
+
+            pub fn insert(&mut self, other: $name) { self.0 |= other.0; }
+
+            pub fn remove(&mut self, other: $name) { self.0 &= !other.0; }
+
+            pub fn set(&mut self, other: $name, value: bool) {
+                if value { self.insert(other) } else { self.remove(other) }
+            }
+        }
+
+        impl core::ops::BitOr for $name {
+            type Output = $name;
+            fn bitor(self, other: $name) -> $name { $name(self.0 | other.0) }
+        }
+
+        impl core::ops::BitAnd for $name {
+            type Output = $name;
+            fn bitand(self, other: $name) -> $name { $name(self.0 & other.0) }
+        }
+
+        impl core::ops::Not for $name {
+            type Output = $name;
+            fn not(self) -> $name { $name(!self.0) }
+        }
+    };
+}
@@ -1,4 +1,9 @@
 #![no_std]
 
+#[macro_use]
+mod flags;
+
+pub mod cpuid;
 pub mod memory;
-pub mod devices;
\ No newline at end of file
+pub mod devices;
+pub mod registers;
\ No newline at end of file
@@ -22,6 +22,9 @@ struct PageFrameAllocatorInner {
     last_allocated_page: usize,
 }
 
+// The bitmap buffer is only ever accessed through the allocator's mutex
+unsafe impl Send for PageFrameAllocatorInner {}
+
 impl PageFrameAllocatorInner {
     pub const unsafe fn new_uninitialized() -> PageFrameAllocatorInner {
         PageFrameAllocatorInner {
@@ -108,7 +111,7 @@ impl PageFrameAllocator {
         )
     }
 
-    pub unsafe fn init(&mut self, page_bitmap: *mut Bitmap, free_memory: u64, used_memory: u64) {
+    pub unsafe fn init(&self, page_bitmap: *mut Bitmap, free_memory: u64, used_memory: u64) {
         self.lockable_allocator.lock().init(page_bitmap, free_memory, used_memory);
     }
 
@@ -155,4 +158,15 @@ impl FrameAllocator for PageFrameAllocator {
     fn free_page(&self, address: PhysicalAddress) -> Result<(), AllocError> {
         self.lockable_allocator.lock().free_page(address)
     }
-}
\ No newline at end of file
+}
+
+/// Allows a shared allocator, such as one in a static, to be passed where a mutable one is expected
+impl<T: FrameAllocator> FrameAllocator for &T {
+    fn request_page(&self) -> Result<PhysicalAddress, AllocError> {
+        (**self).request_page()
+    }
+
+    fn free_page(&self, address: PhysicalAddress) -> Result<(), AllocError> {
+        (**self).free_page(address)
+    }
+}
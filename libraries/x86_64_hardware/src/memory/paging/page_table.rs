@@ -53,6 +53,10 @@ impl PageTableEntry {
         self.entry = (self.entry & !PHYSICAL_ADDRESS_MASK) | addr.as_u64();
     }
 
+    /// The entry with the address bits masked out
+    #[inline]
+    pub fn flags(&self) -> u64 { self.entry & !PHYSICAL_ADDRESS_MASK }
+
     #[inline]
     fn are_flag_set(&self, flags: u64) -> bool { (self.entry & flags) == flags }
 
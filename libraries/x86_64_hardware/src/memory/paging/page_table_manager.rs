@@ -1,10 +1,18 @@
-use crate::{com1_println, memory::{PhysicalAddress, VirtualAddress}};
+use crate::{com1_println, memory::{PhysicalAddress, VirtualAddress, PAGE_SIZE}, registers::{Cr3, Cr3Flags}};
 
 use super::{AllocError, FrameAllocator, PageTable, PageTableEntry, PAGE_TABLE_MAX_INDEX};
 
 pub const MEM_1G: u64 = 1024 * 1024 * 1024;
 pub const MAX_MEM_SIZE: u64 = 512 * MEM_1G;
 
+/// The entries visited while translating a virtual address
+pub struct PageWalk {
+    /// The entry used at each level starting with P4. Levels that were not reached are None
+    pub entries: [Option<PageTableEntry>; 4],
+    /// The physical address the virtual address maps to, if it is mapped
+    pub physical_address: Option<u64>,
+}
+
 pub struct PageTableManager {
     p4: PhysicalAddress,
     offset: u64,
@@ -23,15 +31,11 @@ impl PageTableManager {
     }
 
     pub fn new_from_cr3(offset: u64) -> PageTableManager {
-        let p4_addr: u64;
+        let (p4_addr, _) = Cr3::read();
 
-        unsafe {
-            core::arch::asm!("mov {}, cr3", out(reg) p4_addr, options(nomem, nostack, preserves_flags))
-        }
+        com1_println!("Firmware page tabel address: {:#X}", p4_addr.as_u64());
 
-        com1_println!("Firmware page tabel address: {:#X}", p4_addr);
-
-        PageTableManager::new(PhysicalAddress::new(p4_addr), offset)
+        PageTableManager::new(p4_addr, offset)
     }
 
     pub fn new(p4: PhysicalAddress, offset: u64) -> PageTableManager {
@@ -71,7 +75,7 @@ impl PageTableManager {
     }
 
     pub unsafe fn activate_page_table(&self) {
-        core::arch::asm!("mov cr3, {}", in(reg) self.p4.as_u64(), options(nostack, preserves_flags));
+        Cr3::write(self.p4, Cr3Flags::empty());
     }
 
     pub fn map_memory_pages(
@@ -209,6 +213,30 @@ impl PageTableManager {
         Some(page_table_entry.address())
     }
 
+    /// Walk the page tables for a virtual address, following 1 GiB and 2 MiB pages
+    pub fn walk(&self, virtual_address: VirtualAddress) -> PageWalk {
+        let mut walk = PageWalk { entries: [None; 4], physical_address: None };
+        let mut table_ptr = unsafe { self.translate_address(self.p4).get_mut_ptr::<PageTable>() };
+
+        for level in (1..=4u8).rev() {
+            let entry = unsafe { (*table_ptr).get_entry(virtual_address.get_pn_index(level)) };
+            walk.entries[4 - level as usize] = Some(entry);
+            if !entry.present() { break; }
+
+            // The low bits of the address select a byte within the page at this level
+            let page_size = PAGE_SIZE << (9 * (level as u64 - 1));
+            if level == 1 || (entry.page_size() && level <= 3) {
+                let page_base = entry.address().as_u64() & !(page_size - 1);
+                walk.physical_address = Some(page_base + (virtual_address.as_u64() & (page_size - 1)));
+                break;
+            }
+
+            table_ptr = unsafe { self.translate_address(entry.address()).get_mut_ptr::<PageTable>() };
+        }
+
+        walk
+    }
+
     pub fn unmap_p4_index(&self, p4_index: usize, allocator: &mut impl FrameAllocator) -> Result<(), AllocError> {
         // TODO: Should probably error here
         if p4_index > PAGE_TABLE_MAX_INDEX { return Ok(()); }
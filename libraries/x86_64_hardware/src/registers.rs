@@ -0,0 +1,7 @@
+mod control;
+mod msr;
+mod tsc;
+
+pub use control::*;
+pub use msr::*;
+pub use tsc::*;
@@ -0,0 +1,123 @@
+use core::arch::asm;
+
+use crate::memory::{PhysicalAddress, VirtualAddress, PHYSICAL_ADDRESS_MASK};
+
+register_flags! {
+    /// Flags in CR0 that control the operating mode of the CPU
+    pub struct Cr0Flags: u64 {
+        const PROTECTED_MODE_ENABLE = 1 << 0;
+        const MONITOR_COPROCESSOR = 1 << 1;
+        const EMULATE_COPROCESSOR = 1 << 2;
+        const TASK_SWITCHED = 1 << 3;
+        const EXTENSION_TYPE = 1 << 4;
+        const NUMERIC_ERROR = 1 << 5;
+        /// Read only pages can't be written by the kernel either
+        const WRITE_PROTECT = 1 << 16;
+        const ALIGNMENT_MASK = 1 << 18;
+        const NOT_WRITE_THROUGH = 1 << 29;
+        const CACHE_DISABLE = 1 << 30;
+        const PAGING = 1 << 31;
+    }
+}
+
+register_flags! {
+    /// Caching flags for the top level page table in CR3
+    pub struct Cr3Flags: u64 {
+        const PAGE_LEVEL_WRITE_THROUGH = 1 << 3;
+        const PAGE_LEVEL_CACHE_DISABLE = 1 << 4;
+    }
+}
+
+register_flags! {
+    /// Flags in CR4 that turn on architecture extensions
+    pub struct Cr4Flags: u64 {
+        const VIRTUAL_8086_MODE_EXTENSIONS = 1 << 0;
+        const PROTECTED_MODE_VIRTUAL_INTERRUPTS = 1 << 1;
+        /// Only ring 0 can read the timestamp counter
+        const TIMESTAMP_DISABLE = 1 << 2;
+        const DEBUGGING_EXTENSIONS = 1 << 3;
+        const PAGE_SIZE_EXTENSION = 1 << 4;
+        const PHYSICAL_ADDRESS_EXTENSION = 1 << 5;
+        const MACHINE_CHECK_EXCEPTION = 1 << 6;
+        const PAGE_GLOBAL = 1 << 7;
+        const PERFORMANCE_COUNTER = 1 << 8;
+        const OSFXSR = 1 << 9;
+        const OSXMMEXCPT = 1 << 10;
+        const USER_MODE_INSTRUCTION_PREVENTION = 1 << 11;
+        const FIVE_LEVEL_PAGING = 1 << 12;
+        const VIRTUAL_MACHINE_EXTENSIONS = 1 << 13;
+        const SAFER_MODE_EXTENSIONS = 1 << 14;
+        /// RDFSBASE, WRFSBASE, RDGSBASE and WRGSBASE can be used
+        const FSGSBASE = 1 << 16;
+        const PCID = 1 << 17;
+        const OSXSAVE = 1 << 18;
+        const SUPERVISOR_MODE_EXECUTION_PREVENTION = 1 << 20;
+        const SUPERVISOR_MODE_ACCESS_PREVENTION = 1 << 21;
+        const PROTECTION_KEYS = 1 << 22;
+    }
+}
+
+pub struct Cr0;
+
+impl Cr0 {
+    pub fn read() -> Cr0Flags {
+        let value: u64;
+        unsafe { asm!("mov {}, cr0", out(reg) value, options(nomem, nostack, preserves_flags)); }
+        Cr0Flags::from_bits(value)
+    }
+
+    /// ## Safety
+    ///
+    /// Changing CR0 can turn off paging or protection, so the caller must make sure the flags are valid
+    pub unsafe fn write(flags: Cr0Flags) {
+        asm!("mov cr0, {}", in(reg) flags.bits(), options(nostack, preserves_flags));
+    }
+}
+
+/// Holds the address that caused the last page fault
+pub struct Cr2;
+
+impl Cr2 {
+    pub fn read() -> VirtualAddress {
+        let value: u64;
+        unsafe { asm!("mov {}, cr2", out(reg) value, options(nomem, nostack, preserves_flags)); }
+        VirtualAddress::new(value)
+    }
+}
+
+/// Holds the physical address of the top level page table
+pub struct Cr3;
+
+impl Cr3 {
+    pub fn read() -> (PhysicalAddress, Cr3Flags) {
+        let value: u64;
+        unsafe { asm!("mov {}, cr3", out(reg) value, options(nomem, nostack, preserves_flags)); }
+        (PhysicalAddress::new(value), Cr3Flags::from_bits(value & !PHYSICAL_ADDRESS_MASK))
+    }
+
+    /// Switch to another page table
+    ///
+    /// ## Safety
+    ///
+    /// The page table must map the code that is running, its stack and everything it goes on to use
+    pub unsafe fn write(p4: PhysicalAddress, flags: Cr3Flags) {
+        asm!("mov cr3, {}", in(reg) p4.as_u64() | flags.bits(), options(nostack, preserves_flags));
+    }
+}
+
+pub struct Cr4;
+
+impl Cr4 {
+    pub fn read() -> Cr4Flags {
+        let value: u64;
+        unsafe { asm!("mov {}, cr4", out(reg) value, options(nomem, nostack, preserves_flags)); }
+        Cr4Flags::from_bits(value)
+    }
+
+    /// ## Safety
+    ///
+    /// The CPU must support every extension that is turned on
+    pub unsafe fn write(flags: Cr4Flags) {
+        asm!("mov cr4, {}", in(reg) flags.bits(), options(nostack, preserves_flags));
+    }
+}
@@ -0,0 +1,211 @@
+use core::arch::asm;
+
+use crate::memory::{PhysicalAddress, VirtualAddress};
+
+/// A model specific register
+pub struct Msr {
+    number: u32,
+}
+
+impl Msr {
+    /// ## Safety
+    ///
+    /// The MSR must exist on this CPU, otherwise reading or writing it raises a general protection fault
+    pub const unsafe fn new(number: u32) -> Msr {
+        Msr { number }
+    }
+
+    pub fn read(&self) -> u64 {
+        let low: u32;
+        let high: u32;
+        // Safety: The MSR was checked to exist when this was created
+        unsafe { asm!("rdmsr", in("ecx") self.number, out("eax") low, out("edx") high, options(nomem, nostack, preserves_flags)); }
+        (high as u64) << 32 | low as u64
+    }
+
+    /// ## Safety
+    ///
+    /// Writing an MSR can change how the CPU behaves, so the caller must make sure the value is valid for it
+    pub unsafe fn write(&self, value: u64) {
+        asm!("wrmsr", in("ecx") self.number, in("eax") value as u32, in("edx") (value >> 32) as u32, options(nostack, preserves_flags));
+    }
+}
+
+const IA32_APIC_BASE: u32 = 0x1B;
+const IA32_PAT: u32 = 0x277;
+const IA32_EFER: u32 = 0xC000_0080;
+const IA32_FS_BASE: u32 = 0xC000_0100;
+const IA32_GS_BASE: u32 = 0xC000_0101;
+const IA32_KERNEL_GS_BASE: u32 = 0xC000_0102;
+
+register_flags! {
+    /// Flags in the extended feature enable register
+    pub struct EferFlags: u64 {
+        const SYSTEM_CALL_EXTENSIONS = 1 << 0;
+        const LONG_MODE_ENABLE = 1 << 8;
+        const LONG_MODE_ACTIVE = 1 << 10;
+        const NO_EXECUTE_ENABLE = 1 << 11;
+        const SECURE_VIRTUAL_MACHINE_ENABLE = 1 << 12;
+        const LONG_MODE_SEGMENT_LIMIT_ENABLE = 1 << 13;
+        const FAST_FXSAVE_FXRSTOR = 1 << 14;
+        const TRANSLATION_CACHE_EXTENSION = 1 << 15;
+    }
+}
+
+register_flags! {
+    /// Flags in IA32_APIC_BASE, below the address of the local APIC
+    pub struct ApicBaseFlags: u64 {
+        /// Set on the CPU the firmware booted on
+        const BOOTSTRAP_PROCESSOR = 1 << 8;
+        const X2APIC_ENABLE = 1 << 10;
+        const GLOBAL_ENABLE = 1 << 11;
+    }
+}
+
+pub struct Efer;
+
+impl Efer {
+    pub fn read() -> EferFlags {
+        // Safety: EFER exists on every x86_64 CPU
+        EferFlags::from_bits(unsafe { Msr::new(IA32_EFER) }.read())
+    }
+
+    /// ## Safety
+    ///
+    /// Clearing long mode or turning on features the CPU lacks will crash it
+    pub unsafe fn write(flags: EferFlags) {
+        Msr::new(IA32_EFER).write(flags.bits());
+    }
+}
+
+/// Where the local APIC's registers are and whether it is enabled
+pub struct ApicBase;
+
+impl ApicBase {
+    pub fn read() -> (PhysicalAddress, ApicBaseFlags) {
+        // Safety: IA32_APIC_BASE exists on every x86_64 CPU
+        let value = unsafe { Msr::new(IA32_APIC_BASE) }.read();
+        (PhysicalAddress::new(value), ApicBaseFlags::from_bits(value & 0xFFF))
+    }
+
+    /// ## Safety
+    ///
+    /// Moving or disabling the local APIC affects every interrupt delivered to this CPU
+    pub unsafe fn write(address: PhysicalAddress, flags: ApicBaseFlags) {
+        Msr::new(IA32_APIC_BASE).write(address.as_u64() | flags.bits());
+    }
+}
+
+/// The base address of the FS segment, used as the thread pointer for thread local storage
+pub struct FsBase;
+
+impl FsBase {
+    pub fn read() -> VirtualAddress {
+        // Safety: IA32_FS_BASE exists on every x86_64 CPU
+        VirtualAddress::new(unsafe { Msr::new(IA32_FS_BASE) }.read())
+    }
+
+    /// ## Safety
+    ///
+    /// Code reading thread locals through FS expects the address to point at a valid TLS block
+    pub unsafe fn write(address: VirtualAddress) {
+        Msr::new(IA32_FS_BASE).write(address.as_u64());
+    }
+}
+
+/// The base address of the GS segment, used to find per CPU data
+pub struct GsBase;
+
+impl GsBase {
+    pub fn read() -> VirtualAddress {
+        // Safety: IA32_GS_BASE exists on every x86_64 CPU
+        VirtualAddress::new(unsafe { Msr::new(IA32_GS_BASE) }.read())
+    }
+
+    /// ## Safety
+    ///
+    /// Code reading per CPU data through GS expects the address to point at it
+    pub unsafe fn write(address: VirtualAddress) {
+        Msr::new(IA32_GS_BASE).write(address.as_u64());
+    }
+}
+
+/// The GS base swapped in by SWAPGS
+pub struct KernelGsBase;
+
+impl KernelGsBase {
+    pub fn read() -> VirtualAddress {
+        // Safety: IA32_KERNEL_GS_BASE exists on every x86_64 CPU
+        VirtualAddress::new(unsafe { Msr::new(IA32_KERNEL_GS_BASE) }.read())
+    }
+
+    /// ## Safety
+    ///
+    /// SWAPGS will make this the GS base, so it must be valid wherever that happens
+    pub unsafe fn write(address: VirtualAddress) {
+        Msr::new(IA32_KERNEL_GS_BASE).write(address.as_u64());
+    }
+}
+
+/// A memory type that page table entries can select through the PAT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatMemoryType {
+    Uncacheable = 0,
+    WriteCombining = 1,
+    WriteThrough = 4,
+    WriteProtected = 5,
+    WriteBack = 6,
+    /// Uncacheable unless an MTRR says write combining
+    UncacheableMinus = 7,
+}
+
+/// The page attribute table's 8 memory types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageAttributeTable(u64);
+
+impl PageAttributeTable {
+    pub const ENTRY_COUNT: usize = 8;
+
+    pub const fn from_bits(bits: u64) -> PageAttributeTable { PageAttributeTable(bits) }
+
+    pub const fn bits(self) -> u64 { self.0 }
+
+    /// The memory type of an entry, or None if the index is too large or the entry is reserved
+    pub fn entry(self, index: usize) -> Option<PatMemoryType> {
+        if index >= Self::ENTRY_COUNT { return None; }
+
+        match (self.0 >> (index * 8)) & 0x7 {
+            0 => Some(PatMemoryType::Uncacheable),
+            1 => Some(PatMemoryType::WriteCombining),
+            4 => Some(PatMemoryType::WriteThrough),
+            5 => Some(PatMemoryType::WriteProtected),
+            6 => Some(PatMemoryType::WriteBack),
+            7 => Some(PatMemoryType::UncacheableMinus),
+            _ => None,
+        }
+    }
+
+    /// Change an entry. Panics if the index is 8 or more
+    pub fn set_entry(&mut self, index: usize, memory_type: PatMemoryType) {
+        assert!(index < Self::ENTRY_COUNT, "PAT index {index} out of range");
+        let shift = index * 8;
+        self.0 = (self.0 & !(0xFF << shift)) | ((memory_type as u64) << shift);
+    }
+}
+
+pub struct Pat;
+
+impl Pat {
+    pub fn read() -> PageAttributeTable {
+        // Safety: IA32_PAT exists on every x86_64 CPU
+        PageAttributeTable::from_bits(unsafe { Msr::new(IA32_PAT) }.read())
+    }
+
+    /// ## Safety
+    ///
+    /// Changing an entry changes the caching of every page that uses it, and the TLB and caches must
+    /// be flushed afterwards
+    pub unsafe fn write(table: PageAttributeTable) {
+        Msr::new(IA32_PAT).write(table.bits());
+    }
+}
@@ -0,0 +1,28 @@
+use core::arch::asm;
+
+/// The timestamp counter, which counts up at a fixed rate from when the CPU was reset
+pub struct Tsc;
+
+impl Tsc {
+    pub fn read() -> u64 {
+        let low: u32;
+        let high: u32;
+        // Safety: RDTSC only reads the counter. It can only fault in user mode with CR4.TSD set
+        unsafe { asm!("rdtsc", out("eax") low, out("edx") high, options(nomem, nostack, preserves_flags)); }
+        (high as u64) << 32 | low as u64
+    }
+
+    /// Read the counter after every earlier instruction has finished, along with the IA32_TSC_AUX
+    /// value the OS set for this CPU
+    ///
+    /// ## Safety
+    /// The CPU must support RDTSCP, as reported by CpuFeatures::RDTSCP, otherwise this raises #UD.
+    pub unsafe fn read_ordered() -> (u64, u32) {
+        let low: u32;
+        let high: u32;
+        let aux: u32;
+        // Safety: RDTSCP only reads the counter and IA32_TSC_AUX, and the caller checked it exists
+        unsafe { asm!("rdtscp", out("eax") low, out("edx") high, out("ecx") aux, options(nomem, nostack, preserves_flags)); }
+        ((high as u64) << 32 | low as u64, aux)
+    }
+}
@@ -13,4 +13,7 @@ mkdir -p esp/EFI/BOOT
 mkdir -p esp/kernel
 cp $BOOTLOADER esp/EFI/BOOT/BOOTX64.EFI
 cp $KERNEL esp/kernel/kernel.elf
-cp -r assets/fonts esp/kernel/fonts
\ No newline at end of file
+cp -r assets/fonts esp/kernel/fonts
+cp -r assets/images esp/kernel/images
+tar --format=ustar -cf esp/kernel/initrd.tar -C assets/initrd .
+cp assets/boot.cfg esp/boot.cfg
\ No newline at end of file